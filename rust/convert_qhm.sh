@@ -4,17 +4,20 @@
 if [ $# -eq 0 ] || [ "$1" = "help" ] || [ "$1" = "-h" ] || [ "$1" = "--help" ]
 then
 	echo "This script converts found PQS into Quaternionic Hadamard matrices. Usage:"
-	echo "./convert_qhm.sh <sequencetype> <length>"
+	echo "./convert_qhm.sh <sequencetype> <length> [shape]"
 	exit 0
 fi
 
 type=$1
 n=$2
-filename="./results/pairs/$type/find_$n/result.log"
+shape=${3:-none}
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+filename="$foldername/result.log"
 
 # sorting the files
 start2=`date +%s.%N`
-./target/release/rust convert qhm $type $n | tee -a $filename
+./target/release/rust convert qhm $type $n $shape | tee -a $filename
 end2=`date +%s.%N`
 elapsed=$(echo "$end2 - $start2" | bc)
 printf "Converting to PQS to QHM took %.2f seconds\n" $elapsed | tee -a $filename
@@ -5,7 +5,7 @@
 #
 # Optional flags:
 # -d: delete existing .seq, .pair and .sorted files
-# -p <pairing>: run code with chosen pairing of sequences. Options include WX, WY and WZ
+# -p <pairing>: run code with chosen pairing of sequences. Options include WX, WY, WZ and AUTO
 
 
 if [ $# -eq 0 ] || [ "$1" = "help" ] || [ "$1" = "-h" ] || [ "$1" = "--help" ]
@@ -17,7 +17,10 @@ then
 	echo "  * -h: Convert sequences to Hadamard matrices when finished"
 	echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -c: Use auto/cross correlation for matching instead of PSD/CPSD"
-	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include WX, WY and WZ (e.g., WX means that the sequences of rowsum W are paired with the sequences of rowsum X). Note that the code follows the convention W <= X <= Y <= Z. Default is WZ"
+	echo "  * -e: Use PSD/CPSD computed from the exact correlation values, which cannot lose matches to rounding"
+	echo "  * -m <factor>: Generate the sequences by decompressing their admissible compressions by the given factor, which must divide n. Default is 1 (no compression)"
+	echo "  * -y <shape>: Only search for quadruples whose sequences all have the shape: sym (symmetric), skew (skew-type) or I, II, III, IV (the symmetries of the pqs search). Default is none"
+	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include WX, WY, WZ and AUTO (e.g., WX means that the sequences of rowsum W are paired with the sequences of rowsum X, and AUTO chooses for each rowsum the pairing giving the fewest pairs). Note that the code follows the convention W <= X <= Y <= Z. Default is WZ"
 	exit 0
 fi
 
@@ -31,14 +34,15 @@ fi
 
 shift
 shift
-foldername="./results/pairs/$type/find_$n"
 rowsum_pairing="WZ"
 use_slurm=false
 hadamard=false
 match_option="psd"
+compression=1
+shape=none
+cleanup=false
 
-# Empty out existing .pair files to avoid conflicts
-while getopts "chsdp:" flag; do
+while getopts "cehsdp:m:y:" flag; do
 	case $flag in
 		s)
 		use_slurm=true
@@ -47,7 +51,7 @@ while getopts "chsdp:" flag; do
 		hadamard=true
 		;;
 		d)
-		./pair_file_cleanup.sh $type $n
+		cleanup=true
 		;;
 		p)
 		rowsum_pairing=$OPTARG
@@ -55,6 +59,15 @@ while getopts "chsdp:" flag; do
 		c)
 		match_option="correlation"
 		;;
+		e)
+		match_option="exact"
+		;;
+		m)
+		compression=$OPTARG
+		;;
+		y)
+		shape=$OPTARG
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -62,6 +75,40 @@ while getopts "chsdp:" flag; do
 	esac
 done
 
+# The aperiodic families, the complex pairs and the good and best matrices aren't quaternion-type sequences, they are not converted to Hadamard matrices or QHM
+# (the skew-Hadamard matrices of good and best matrices are built by construct)
+case $type in
+	tts|bs*|ccp|gm|bm)
+	convertible=false
+	;;
+	*)
+	convertible=true
+	;;
+esac
+
+if [ $hadamard = true ] && [ $convertible = false ]; then
+	echo "WARNING: the sequences of type $type can't be converted to Hadamard matrices, ignoring the -h flag."
+	hadamard=false
+fi
+
+# The aperiodic families and the complex pairs are searched without shape
+case $type in
+	tts|bs*|ccp)
+	if [ "$shape" != none ]; then
+		echo "WARNING: the sequences of type $type can't be restricted to a shape, ignoring the -y flag."
+		shape=none
+	fi
+	;;
+esac
+
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+
+# Empty out existing .pair files to avoid conflicts
+if [ $cleanup = true ]; then
+	./pair_file_cleanup.sh $type $n -y $shape
+fi
+
 # Check if rowsum directories still exist
 for d in "$foldername"/rowsum_*; do
   if [ -d "$d" ]; then
@@ -88,7 +135,7 @@ fi
 start=`date +%s.%N`
 
 # Creating every necessary file
-./target/release/rust pairs $type $n $match_option $rowsum_pairing | tee $filename
+./target/release/rust pairs $type $n $match_option $rowsum_pairing 0 $compression $shape | tee $filename
 if [ $? -ne 0 ]
 then
 	echo 'ERROR: pairs exited unsuccessfully. See log for additional details'
@@ -97,9 +144,9 @@ fi
 
 # sorting the files
 if [ "$use_slurm" = true ]; then
-	./sortpairs.sh $type $n -s
+	./sortpairs.sh $type $n -s -y $shape
 else
-	./sortpairs.sh $type $n
+	./sortpairs.sh $type $n -y $shape
 fi
 
 if [ $? -ne 0 ]
@@ -109,7 +156,7 @@ then
 fi
 
 # Matching the file AND reducing to equivalence
-./target/release/rust join $type $n | tee $filename -a
+./target/release/rust join $type $n $shape | tee $filename -a
 if [ $? -ne 0 ]
 then
 	echo 'ERROR: join exited unsuccessfully. See log for additional details'
@@ -118,7 +165,7 @@ fi
 
 if [ $hadamard = true ]; then
 	start2=`date +%s.%N`
-	./target/release/rust convert hm $type $n | tee $filename -a
+	./target/release/rust convert hm $type $n $shape | tee $filename -a
 	end2=`date +%s.%N`
 	elapsed=$(echo "$end2 - $start2" | bc)
 	printf "Converting to matrices up to Hadamard equivalence took %.2f seconds\n" $elapsed | tee $filename -a
@@ -128,8 +175,10 @@ if [ $hadamard = true ]; then
 	echo "$matcount matrices were found after converting up to Hadamard equivalence." | tee $filename -a
 fi
 
-echo "Converting PQS to QHM ..." | tee $filename -a
-./convert_qhm.sh $type $n
+if [ $convertible = true ]; then
+	echo "Converting PQS to QHM ..." | tee $filename -a
+	./convert_qhm.sh $type $n $shape
+fi
 
 end=`date +%s.%N`
 elapsed=$(echo "$end - $start" | bc)
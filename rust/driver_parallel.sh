@@ -7,7 +7,9 @@ then
 	echo "Optional flags:"
 	echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -c: Use auto/cross correlation for matching instead of PSD/CPSD"
+	echo "  * -e: Use PSD/CPSD computed from the exact correlation values, which cannot lose matches to rounding"
 	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include XY, XZ, XW. Default is XW"
+	echo "  * -y <shape>: Only search for quadruples whose sequences all have the shape: sym (symmetric), skew (skew-type) or I, II, III, IV (the symmetries of the pqs search). Default is none"
 	exit 0
 fi
 
@@ -15,21 +17,28 @@ type=$1
 n=$2
 shift
 shift
-foldername="./results/pairs/$type/find_$n"
 rowsum_pairing="XW"
 match_option="psd"
+shape=none
+cleanup=false
 
-while getopts "cdp:" flag; do
+while getopts "cedp:y:" flag; do
 	case $flag in
 		d)
-		./pair_file_cleanup.sh $n
+		cleanup=true
 		;;
 		p)
 		rowsum_pairing=$OPTARG
 		;;
+		y)
+		shape=$OPTARG
+		;;
 		c)
 		match_option="correlation"
 		;;
+		e)
+		match_option="exact"
+		;;
 		\?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -37,6 +46,13 @@ while getopts "cdp:" flag; do
 	esac
 done
 
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+
+if [ $cleanup = true ]; then
+	./pair_file_cleanup.sh $type $n -y $shape
+fi
+
 # Check if rowsum directories still exist
 for d in "$foldername"/rowsum_*; do
   if [ -d "$d" ]; then
@@ -46,18 +62,23 @@ for d in "$foldername"/rowsum_*; do
 done
 
 # Generate rowsums
-./target/release/rust rowsums $type $n 
+./target/release/rust rowsums $type $n $shape
 
 
 jobids=()
 
+# create the directories of all the rowsums
+./target/release/rust create $type $n $rowsum_pairing 0 1 $shape
+
 # read the rowsums file and submit jobs
-input="results/pairs/qts/find_$n/rowsums.quad"
-while IFS= read -r rowsum
+input="$foldername/rowsums.quad"
+# each line holds the rowsums w x y z of a class and its multiplicity, the lines starting with # are comments
+while read -r w x y z multiplicity
 do
-	./target/release/rust create $type $n $rowsum $rowsum_pairing
+	[[ -z "$w" || "$w" == \#* ]] && continue
+	rowsum="$w $x $y $z"
     # Submit job for first pair, capturing job ID
-	jobid=$(sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 1 | awk '{print $4}')
+	jobid=$(sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 1 $shape | awk '{print $4}')
 	# Check for successful job submission
 	if [[ -z "$jobid" ]]; then
 		echo "Failed to submit job for rowsum $rowsum"
@@ -67,7 +88,7 @@ do
 	echo "Submitted job $jobid for rowsum $rowsum"
 
 	# Submit job for second pair, capturing job ID
-	jobid=$(sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 2 | awk '{print $4}')
+	jobid=$(sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 2 $shape | awk '{print $4}')
 	# Check for successful job submission
 	if [[ -z "$jobid" ]]; then
 		echo "Failed to submit job for rowsum $rowsum"
@@ -84,7 +105,7 @@ done < "$input"
 dep_string=$(IFS=:; echo "${jobids[*]}")
 jobids2=()
 
-for dirname in results/pairs/qts/find_$n/*;
+for dirname in "$foldername"/*;
 do
 	if [ -d $dirname ]
 	then
@@ -104,5 +125,5 @@ do
 done
 
 dep_string2=$(IFS=:; echo "${jobids2[*]}")
-sbatch --dependency=afterok:$dep_string2 ./job_join.sh $type $n
+sbatch --dependency=afterok:$dep_string2 ./job_join.sh $type $n $shape
 
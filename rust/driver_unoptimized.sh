@@ -5,7 +5,7 @@
 #
 # Optional flags:
 # -d: delete existing .seq, .pair and .sorted files
-# -p <pairing>: run code with chosen pairing of sequences. Options include WX, WY and WZ
+# -p <pairing>: run code with chosen pairing of sequences. Options include WX, WY, WZ and AUTO
 
 
 if [ $# -eq 0 ] || [ "$1" = "help" ] || [ "$1" = "-h" ] || [ "$1" = "--help" ]
@@ -15,6 +15,7 @@ then
 	echo "Optional flags:"
 	echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -c: Use auto/cross correlation for matching instead of PSD/CPSD"
+	echo "  * -e: Use PSD/CPSD computed from the exact correlation values, which cannot lose matches to rounding"
 	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include XY, XZ, XW. Default is XW"
 	echo "  * -h: Convert sequences to Hadamard matrices when finished"
 	exit 0
@@ -36,7 +37,7 @@ hadamard=false
 match_option="psd"
 
 # Empty out existing .pair files to avoid conflicts
-while getopts "chdp:" flag; do
+while getopts "cehdp:" flag; do
 	case $flag in
 		h)
 		hadamard=true
@@ -50,6 +51,9 @@ while getopts "chdp:" flag; do
 		c)
 		match_option="correlation"
 		;;
+		e)
+		match_option="exact"
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
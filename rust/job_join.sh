@@ -11,6 +11,7 @@
 
 type=$1
 n=$2
+shape=${3:-none}
 
-./join_pairs.sh $type $n
+./join_pairs.sh $type $n $shape
 
@@ -9,7 +9,7 @@
 # Stops after generating the lists of auto and cross correlation values for the pairs
 # Normally called from start_pairs_parallel.sh
 
-if [ $# -ne 8 ]
+if [ $# -lt 9 ]
 then
     echo "not enough arguments"
     exit 1
@@ -24,22 +24,24 @@ d=$6
 match_option=$7
 rowsum_pairing=$8
 pair=$9
+shape=${10:-none}
 start=`date +%s`
 
 
 # go through rowsums
 # start all the batches
 
-foldername="./results/pairs/$type/find_$n"
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
 filename="$foldername/result.log"
 
 if [ ! -e $foldername ]
 then
-	mkdir $foldername
+	mkdir -p $foldername
 fi
 
 # Creating every necessary file
 start2=`date +%s`
-./target/release/rust pair_single $type $n $a $b $c $d $match_option $rowsum_pairing $pair &>> $filename
+./target/release/rust pair_single $type $n $a $b $c $d $match_option $rowsum_pairing $pair 0 1 $shape &>> $filename
 end2=`date +%s`
 echo Creating the sequences took `expr $end2 - $start2` seconds. >> $filename
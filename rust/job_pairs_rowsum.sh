@@ -8,7 +8,7 @@
 # This script runs the first part of the algorithm for a single set of rowsums
 # Stops after generating the lists of auto and cross correlation values for the pairs
 
-if [ $# -ne 6 ]
+if [ $# -lt 7 ]
 then
     echo "not enough arguments"
     exit 1
@@ -21,22 +21,24 @@ b=$4
 c=$5
 d=$6
 rowsum_pairing=$7
+shape=${8:-none}
 start=`date +%s`
 
 
 # go through rowsums
 # start all the batches
 
-foldername="./results/pairs/$type/find_$n"
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
 filename="$foldername/result.log"
 
 if [ ! -e $foldername ]
 then
-	mkdir $foldername
+	mkdir -p $foldername
 fi
 
 # Creating every necessary file
 start2=`date +%s`
-./target/release/rust pairs_rowsum $type $n $a $b $c $d $rowsum_pairing >> $filename
+./target/release/rust pairs_rowsum $type $n $a $b $c $d psd $rowsum_pairing 0 1 $shape >> $filename
 end2=`date +%s`
 echo Creating the sequences took `expr $end2 - $start2` seconds. >> $filename
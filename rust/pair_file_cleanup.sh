@@ -4,6 +4,13 @@
 
 type=$1
 
+# The folders of a search restricted to a shape are given by a trailing -y <shape>
+shape=none
+if [ $# -ge 4 ] && [ "${@: -2:1}" = "-y" ]; then
+    shape="${@: -1}"
+    set -- "${@:1:$#-2}"
+fi
+
 if [ $# -eq 2 ]; then
     START=$2
     END=$2
@@ -14,13 +21,14 @@ else
     echo "This script deletes rowsum_w_x_y_z directories (temporary files containing pair generation). Usage:"
     echo "  * To clean directories corresponding to sequences of type 'seqtype' for a single length n, use ./pair_file_cleanup.sh seqtype n"
     echo "  * To clean directories corresponding to sequences of type 'seqtype' for all lengths from a to b (inclusive), use ./pair_file_cleanup.sh seqtype a b"
+    echo "  * To clean the directories of a search restricted to a shape, add -y <shape> at the end"
     exit 0
 fi
 
-base_dir="results/pairs/$type"
+. ./pairs_folder.sh
 for (( i=START; i<=END; i++ ))
 do
-    dir="$base_dir/find_$i"
+    dir=$(pairs_folder $type $i $shape)
     if [ -d "$dir" ]; then
         rm -rf "$dir"/rowsum_*
     fi
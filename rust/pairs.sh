@@ -8,16 +8,17 @@ then
     echo "  * -s: Use this flag for SLURM jobs"
 	echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -c: Use auto/cross correlation for matching instead of PSD/CPSD"
-	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include WX, WY and WZ (e.g., WX means that the sequences of rowsum W are paired with the sequences of rowsum X). Note that the code follows the convention W <= X <= Y <= Z. Default is WZ"
+	echo "  * -e: Use PSD/CPSD computed from the exact correlation values, which cannot lose matches to rounding"
+	echo "  * -n: Dry run, only print the projected disk space and time to generate the .pair files of each rowsum without writing them"
+	echo "  * -m <factor>: Generate the sequences by decompressing their admissible compressions by the given factor, which must divide n. Default is 1 (no compression)"
+	echo "  * -y <shape>: Only search for quadruples whose sequences all have the shape: sym (symmetric), skew (skew-type) or I, II, III, IV (the symmetries of the pqs search). Default is none"
+	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include WX, WY, WZ and AUTO (e.g., WX means that the sequences of rowsum W are paired with the sequences of rowsum X, and AUTO chooses for each rowsum the pairing giving the fewest pairs). Note that the code follows the convention W <= X <= Y <= Z. Default is WZ"
 	exit 0
 fi
 
 type=$1
 n=$2
 
-# Folder to store output
-foldername="./results/pairs/$type/find_$n"
-
 if [ -z "$type" ] || [ -z "$n" ]; then
 	echo 'Incorrect args passed. Try running with --help.'
 	exit 1
@@ -30,13 +31,17 @@ shift
 use_slurm=false
 rowsum_pairing="WZ"
 match_option="psd"
-while getopts "cdsp:" flag; do
+compression=1
+shape=none
+dry_run=false
+cleanup=false
+while getopts "cedsnp:m:y:" flag; do
 	case $flag in
         s)
 		use_slurm=true
 		;;
 		d)
-		./pair_file_cleanup.sh $type $n
+		cleanup=true
 		;;
 		p)
 		rowsum_pairing=$OPTARG
@@ -44,6 +49,18 @@ while getopts "cdsp:" flag; do
 		c)
 		match_option="correlation"
 		;;
+		e)
+		match_option="exact"
+		;;
+		m)
+		compression=$OPTARG
+		;;
+		y)
+		shape=$OPTARG
+		;;
+		n)
+		dry_run=true
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -51,9 +68,27 @@ while getopts "cdsp:" flag; do
 	esac
 done
 
+# The aperiodic families and the complex pairs are searched without shape
+case $type in
+	tts|bs*|ccp)
+	if [ "$shape" != none ]; then
+		echo "WARNING: the sequences of type $type can't be restricted to a shape, ignoring the -y flag."
+		shape=none
+	fi
+	;;
+esac
+
+# Folder to store output
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+
+if [ $cleanup = true ]; then
+	./pair_file_cleanup.sh $type $n -y $shape
+fi
+
 # Check if rowsum directories still exist
 for d in "$foldername"/rowsum_*; do
-  if [ -d "$d" ]; then
+  if [ -d "$d" ] && [ $dry_run = false ]; then
     echo "WARNING: results have already been generated for length $n. To run anyway, use the -d flag to overwrite. Exiting."
 	exit 1
   fi
@@ -68,6 +103,12 @@ elif [ ! -e $release_dir ]; then
 	echo "ERROR: Binary not found, compile with 'cargo build --release'"
 fi
 
+# A dry run doesn't write anything
+if [ $dry_run = true ]; then
+	./target/release/rust pairs $type $n $match_option $rowsum_pairing 0 $compression $shape --dry-run
+	exit 0
+fi
+
 filename="$foldername/result.log"
 
 if [ ! -e $foldername ]
@@ -78,7 +119,7 @@ fi
 
 # Call rust code
 start=`date +%s`
-./target/release/rust pairs $type $n $match_option $rowsum_pairing | tee $filename
+./target/release/rust pairs $type $n $match_option $rowsum_pairing 0 $compression $shape | tee $filename
 end=`date +%s`
 echo Generating the .pair files took `expr $end - $start` seconds. 
 echo -e Generating the .pair files took `expr $end - $start` seconds. "\n \n" >> $filename
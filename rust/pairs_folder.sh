@@ -0,0 +1,9 @@
+# Sourced by the scripts to name the folder of a search: results/pairs/<type>/find_<n>, or find_<n>_<shape>
+# for a search restricted to a shape other than none. This is the naming of pairs_folder in src/find/find_write.rs
+pairs_folder() {
+	if [ -z "$3" ] || [ "$3" = none ]; then
+		echo "./results/pairs/$1/find_$2"
+	else
+		echo "./results/pairs/$1/find_${2}_$3"
+	fi
+}
@@ -6,6 +6,7 @@ then
 	echo "./driver.sh <sequencetype> <n>"
 	echo "Optional flags:"
 	echo "  * -s: Use when sorting in a SLURM job"
+	echo "  * -y <shape>: Sort the files of the search restricted to the shape (see driver.sh). Default is none"
 	exit 0
 fi
 
@@ -19,14 +20,16 @@ n=$2
 shift
 shift
 
-results="./results/pairs/$type/find_$n/result.log"
 use_slurm=false
-# Empty out existing .pair files to avoid conflicts
-while getopts "s" flag; do
+shape=none
+while getopts "sy:" flag; do
 	case $flag in
 		s)
 		use_slurm=true
 		;;
+		y)
+		shape=$OPTARG
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -34,10 +37,14 @@ while getopts "s" flag; do
 	esac
 done
 
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+results="$foldername/result.log"
+
 export LC_ALL=C
 
 start=`date +%s.%N`
-for dirname in results/pairs/$type/find_$n/*;
+for dirname in "$foldername"/*;
 do
 	if [ -d $dirname ]
 	then
@@ -0,0 +1,47 @@
+use itertools::Itertools;
+
+use crate::find::find_exact::{join_exact_pairs, write_exact_pairs, write_exact_results};
+use crate::sequences::{aperiodic::{aperiodic_quad_to_string, AperiodicFamily}, fourier::SpectralFilter, williamson::aperiodic_autocorrelation};
+
+// * Pairs and join for the aperiodic families
+// The pipeline of find_exact : the pairs (A, B) and (C, D) are written in pair_WX.pair and pair_YZ.pair with their weighted
+// aperiodic autocorrelations, the files are sorted and merged to find the quadruples whose correlations cancel.
+// The sequences of a pair always have the same weight, and the lines are keyed by the exact integer correlations since the PSDs
+// at the frequencies needed to recover them aren't integers. The aperiodic PSD bound holds at every frequency, so the filter
+// can check the pairs on a finer grid than the DFT
+
+// The oversampling factor of the spectral filter used for the aperiodic families
+pub const APERIODIC_OVERSAMPLING : usize = 4;
+
+pub fn aperiodic_folder(family : AperiodicFamily, n : usize) -> String {
+    "results/pairs/".to_string() + &family.to_string() + &"/find_" + &n.to_string()
+}
+
+pub fn write_aperiodic_pairs(folder : &String, family : AperiodicFamily, n : usize, filter : SpectralFilter) -> u64 {
+    // This is the starting point of the pairs stage for an aperiodic family, the rowsum directories are created in folder
+    // The lines are keyed by the weighted aperiodic autocorrelations at the offsets below the length of A, computed once per sequence
+    let lengths = family.lengths(n);
+    let weights = family.weights();
+    let description = family.to_string() + &" of length " + &n.to_string();
+
+    write_exact_pairs(folder, &description, lengths, family.generate_rowsums(n), filter,
+        |i| family.psd_total(n) as f64 / weights[i] as f64,
+        |seq, i, sign| -> Vec<isize> {(1..lengths[0]).map(|offset| sign * weights[i] * aperiodic_autocorrelation(seq, offset)).collect()},
+        |_, (correlations1, correlations2), _| Some(correlations1.iter().zip(correlations2.iter()).map(|(c1, c2)| (c1 + c2).to_string() + &"_").collect()))
+}
+
+pub fn join_aperiodic_pairs(folder : &String, family : AperiodicFamily, n : usize) -> Vec<[Vec<i8>; 4]> {
+    // Goes through the sorted .pair files of every rowsum directory in folder, and returns the quadruples found up to equivalence
+    let result = join_exact_pairs(folder, family.lengths(n), |quad| family.verify(&quad).then_some(quad));
+    println!("\nFound {} {} after matching", result.len(), family.to_string());
+
+    let mut reduced : Vec<[Vec<i8>; 4]> = result.iter().map(|quad| family.canonical(quad)).unique().collect();
+    reduced.sort();
+    println!("Found {} {} after reducing to equivalence\n", reduced.len(), family.to_string());
+
+    reduced
+}
+
+pub fn write_aperiodic_results(folder : &String, results : &Vec<[Vec<i8>; 4]>) {
+    write_exact_results(folder, "result.seq", results, aperiodic_quad_to_string);
+}
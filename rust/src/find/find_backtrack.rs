@@ -0,0 +1,309 @@
+use std::f64::consts::PI;
+
+use cgmath::Quaternion;
+use num_complex::Complex;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::sequences::hurwitz::{Hurwitz, H1};
+use crate::sequences::sequence::*;
+use crate::sequences::symmetries::Symmetry;
+
+
+// * Backtracking search for perfect quaternion sequences
+// The positions are assigned in increasing order, the first one being fixed to 1 as in find_optim. For every shift t,
+// the search keeps the sum C(t) of the terms q_i * conj(q_{i+t}) whose two positions are assigned, as well as the
+// rowsum and the DFT of the doubled coordinates of the assigned values. A branch is cut as soon as
+//  * the r terms left for a shift can't cancel the partial autocorrelation, each term being a unit : |C(t)| > r
+//  * the r values left can't bring the rowsum to |q_0 + ... + q_{n-1}|^2 = n, which holds for perfect sequences
+//  * the norm of the DFT of the doubled coordinates is not within 2r of sqrt(4n) at some frequency, as the PSDs of
+//    the four doubled coordinates of a perfect sequence add up to 4n at every frequency
+//  * a value forced by the symmetry differs from the value already at that position
+// The values are handled through their index in the alphabet, and all quaternions are stored by their doubled coordinates
+// (see hurwitz.rs) so that the computations are exact. The products of two values of the alphabet are computed once.
+// The top of the tree is expanded into a frontier of prefixes, which are searched in parallel with rayon's work stealing
+//
+// The same search finds the odd-perfect sequences, whose odd periodic autocorrelation negates the terms q_i * conj(q_{i+t})
+// that wrap around (i + t >= n). The PSDs of the doubled coordinates then add up to 4n at the odd frequencies (2k+1)/2n,
+// which are the ones checked, and the rowsum isn't constrained
+
+const FRONTIER_PER_THREAD : usize = 16;
+
+#[derive(Clone)]
+struct Search {
+    n : usize,
+    symmetry : Option<Symmetry>,
+    odd : bool,                         // searching for odd-perfect sequences
+    alphabet : Vec<Hurwitz>,
+    negation : Vec<usize>,              // index of -a for every value a of the alphabet
+    products : Vec<[i64; 4]>,           // a * conj(b) for every values a and b of the alphabet, at index a * len + b
+    values : Vec<Option<usize>>,
+    assigned : usize,
+    correlations : Vec<[i64; 4]>,       // partial C(t) for t = 1..=n/2
+    terms : Vec<usize>,                 // number of terms of C(t) already added, out of n
+    rowsum : [i64; 4],
+    dft : Vec<[Complex<f64>; 4]>,       // partial DFT at the frequencies k/n, or (2k+1)/2n for odd-perfect sequences
+    roots : Vec<Complex<f64>>           // e^(-i*pi*m/n)
+}
+
+fn add(total : &mut [i64; 4], term : &[i64; 4], sign : i64) {
+    for c in 0..4 {
+        total[c] += sign * term[c];
+    }
+}
+
+fn squared_norm(doubled : &[i64; 4]) -> i64 {
+    doubled.iter().map(|c| c * c).sum()
+}
+
+impl Search {
+
+    fn new(n : usize, symmetry : Option<Symmetry>, odd : bool, alphabet : &Vec<Quaternion<f32>>) -> Search {
+        let alphabet : Vec<Hurwitz> = alphabet.iter().map(|q| Hurwitz::from_quaternion(q)).collect();
+        let index = |value : Hurwitz| alphabet.iter().position(|&a| a == value);
+        let negation = alphabet.iter().map(|&a| index(-a).expect("The alphabet must be closed under negation")).collect();
+        let products = alphabet.iter().flat_map(|&a| alphabet.iter().map(move |&b| (a * b.conjugate()).doubled())).collect();
+        let one = index(H1).expect("The alphabet must contain 1");
+
+        let mut search = Search {
+            n,
+            symmetry,
+            odd,
+            alphabet,
+            negation,
+            products,
+            values : vec![None; n],
+            assigned : 0,
+            correlations : vec![[0; 4]; n/2],
+            terms : vec![0; n/2],
+            rowsum : [0; 4],
+            dft : vec![[Complex::new(0., 0.); 4]; n],
+            roots : (0..2*n).map(|m| Complex::from_polar(1., -PI * m as f64 / n as f64)).collect()
+        };
+
+        // The first value is fixed to 1, together with the value its symmetry forces
+        assert!(search.assign(0, one).is_some(), "The symmetry is incompatible with the length");
+        search
+    }
+
+    fn search_size(&self) -> usize {
+        match self.symmetry {
+            Some(_) => {(self.n+1)/2}
+            None => {self.n}
+        }
+    }
+
+    // The position set by the symmetry when choosing the value at index, with its value
+    fn image(&self, index : usize, value : usize) -> Option<(usize, usize)> {
+        let n = self.n;
+        let sign = |negate : bool| if negate {self.negation[value]} else {value};
+        match &self.symmetry {
+            Some(Symmetry::I) => Some((n - 1 - index, value)),
+            Some(Symmetry::II) => Some((n/2 + index, sign(index % 2 == 1))),
+            Some(Symmetry::III) => Some((n/2 + index, sign((index/2) % 2 == 1))),
+            Some(Symmetry::IV) => Some((n/2 + index, sign(true))),
+            None => None
+        }
+    }
+
+    // Assigns the value at index and at its symmetric position, and returns the positions newly assigned,
+    // or None if a position already holds another value
+    fn assign(&mut self, index : usize, value : usize) -> Option<[Option<usize>; 2]> {
+        let mut placed = [None; 2];
+        for (i, forced) in [Some((index, value)), self.image(index, value)].into_iter().enumerate() {
+            if let Some((position, value)) = forced {
+                match self.values[position] {
+                    Some(current) if current != value => {
+                        self.undo(&placed);
+                        return None;
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.update(position, value, 1);
+                        placed[i] = Some(position);
+                    }
+                }
+            }
+        }
+        Some(placed)
+    }
+
+    fn undo(&mut self, placed : &[Option<usize>; 2]) {
+        for position in placed.iter().rev().flatten() {
+            let value = self.values[*position].expect("Position not assigned");
+            self.update(*position, value, -1);
+        }
+    }
+
+    // Adds (sign 1) or removes (sign -1) the value at position and its contributions
+    fn update(&mut self, position : usize, value : usize, sign : i64) {
+        let n = self.n;
+        if sign < 0 {
+            self.values[position] = None;
+            self.assigned -= 1;
+        }
+
+        let len = self.alphabet.len();
+        for t in 1..=n/2 {
+            // The terms q_position * conj(q_{position+t}) and q_{position-t} * conj(q_position)
+            let next = if position + t < n {position + t} else {position + t - n};
+            let previous = if position >= t {position - t} else {position + n - t};
+            // The terms that wrap around are negated in the odd periodic autocorrelation
+            let next_sign = if self.odd && position + t >= n {-sign} else {sign};
+            let previous_sign = if self.odd && position < t {-sign} else {sign};
+            if let Some(next) = self.values[next] {
+                add(&mut self.correlations[t-1], &self.products[value * len + next], next_sign);
+                self.terms[t-1] = (self.terms[t-1] as i64 + sign) as usize;
+            }
+            if let Some(previous) = self.values[previous] {
+                add(&mut self.correlations[t-1], &self.products[previous * len + value], previous_sign);
+                self.terms[t-1] = (self.terms[t-1] as i64 + sign) as usize;
+            }
+        }
+
+        let doubled = self.alphabet[value].doubled();
+        add(&mut self.rowsum, &doubled, sign);
+        let mut m = if self.odd {position} else {0};     // position * 2k, or position * (2k+1), mod 2n
+        for k in 0..n {
+            let root = self.roots[m] * sign as f64;
+            for c in 0..4 {
+                self.dft[k][c] += root * doubled[c] as f64;
+            }
+            m = (m + 2 * position) % (2 * n);
+        }
+
+        if sign > 0 {
+            self.values[position] = Some(value);
+            self.assigned += 1;
+        }
+    }
+
+    // Checks the necessary conditions on the partial sequence
+    fn feasible(&self) -> bool {
+        let n = self.n;
+
+        // With doubled coordinates, |C(t)| <= r becomes |2C(t)|^2 <= 4r^2
+        for (correlation, &terms) in self.correlations.iter().zip(self.terms.iter()) {
+            let remaining = (n - terms) as i64;
+            if squared_norm(correlation) > 4 * remaining * remaining {
+                return false;
+            }
+        }
+
+        let remaining = n - self.assigned;
+        if remaining == 0 {
+            // Once every correlation vanishes, the rowsum and the PSDs have the values of a perfect sequence
+            return self.odd || squared_norm(&self.rowsum) == 4 * n as i64;
+        }
+
+        let remaining = remaining as f64;
+        if !self.odd && ((squared_norm(&self.rowsum) as f64).sqrt() / 2. - (n as f64).sqrt()).abs() > remaining + 1e-9 {
+            return false;
+        }
+
+        // The values left change the DFT of the doubled coordinates by at most 2r at every frequency
+        let upper = (4. * n as f64).sqrt() + 2. * remaining + 1e-6;
+        let lower = ((4. * n as f64).sqrt() - 2. * remaining - 1e-6).max(0.);
+        // The frequency 0 of the periodic DFT is the rowsum, which is already checked
+        self.dft.iter().skip(if self.odd {0} else {1}).all(|coordinates| {
+            let psd = coordinates.iter().map(|c| c.norm_sqr()).sum::<f64>();
+            lower * lower <= psd && psd <= upper * upper
+        })
+    }
+
+    fn to_qs(&self) -> QS {
+        let mut qs = QS::new(self.n, None);
+        qs.set_values(self.values.iter().map(|v| self.alphabet[v.expect("Incomplete sequence")].to_quaternion()).collect());
+        qs
+    }
+
+    // Collects the choices of values for the indices 1..depth that satisfy the conditions
+    fn expand(&mut self, index : usize, depth : usize, prefix : &mut Vec<usize>, frontier : &mut Vec<Vec<usize>>) {
+        if index >= depth.min(self.search_size()) {
+            frontier.push(prefix.clone());
+            return;
+        }
+
+        for value in 0..self.alphabet.len() {
+            if let Some(placed) = self.assign(index, value) {
+                if self.feasible() {
+                    prefix.push(value);
+                    self.expand(index+1, depth, prefix, frontier);
+                    prefix.pop();
+                }
+                self.undo(&placed);
+            }
+        }
+    }
+
+    fn search(&mut self, index : usize, found : &mut Vec<QS>) {
+        if index >= self.search_size() {
+            // Every position is assigned at this point, and the feasibility check made all the correlations vanish
+            let qs = self.to_qs();
+            debug_assert!(if self.odd {qs.is_odd_perfect()} else {qs.is_perfect()});
+            found.push(qs);
+            return;
+        }
+
+        for value in 0..self.alphabet.len() {
+            if let Some(placed) = self.assign(index, value) {
+                if self.feasible() {
+                    self.search(index+1, found);
+                }
+                self.undo(&placed);
+            }
+        }
+    }
+
+}
+
+
+pub fn find_sequences(size : usize, symmetry : Option<Symmetry>, alphabet : &Vec<Quaternion<f32>>) -> Vec<QS> {
+    // Returns the perfect sequences over the alphabet starting with 1, with the given symmetry
+    find_sequences_aux(size, symmetry, false, alphabet)
+}
+
+pub fn find_odd_sequences(size : usize, symmetry : Option<Symmetry>, alphabet : &Vec<Quaternion<f32>>) -> Vec<QS> {
+    // Returns the odd-perfect sequences over the alphabet starting with 1, with the given symmetry
+    find_sequences_aux(size, symmetry, true, alphabet)
+}
+
+fn find_sequences_aux(size : usize, symmetry : Option<Symmetry>, odd : bool, alphabet : &Vec<Quaternion<f32>>) -> Vec<QS> {
+    if size == 0 {
+        return vec![];
+    }
+    let mut root = Search::new(size, symmetry, odd, alphabet);
+    if !root.feasible() {
+        return vec![];
+    }
+
+    // The frontier is deepened until there are enough prefixes to keep every thread busy
+    let target = FRONTIER_PER_THREAD * rayon::current_num_threads();
+    let mut depth = 1;
+    let mut frontier = vec![vec![]];
+    while frontier.len() < target && depth < root.search_size() {
+        depth += 1;
+        frontier = vec![];
+        root.expand(1, depth, &mut vec![], &mut frontier);
+    }
+
+    frontier.into_par_iter()
+        .map(|prefix| {
+            let mut search = root.clone();
+            for (index, &value) in prefix.iter().enumerate() {
+                search.assign(index + 1, value).expect("Prefix of the frontier");
+            }
+            let mut found = vec![];
+            search.search(prefix.len() + 1, &mut found);
+            found
+        })
+        .flatten()
+        .collect()
+}
+
+pub fn find(size : usize, symmetry : Option<Symmetry>, alphabet : &Vec<Quaternion<f32>>) -> usize {
+    let sequences = find_sequences(size, symmetry, alphabet);
+    for qs in &sequences {
+        println!("{}", qs.to_string_raw());
+    }
+    sequences.len()
+}
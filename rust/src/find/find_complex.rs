@@ -0,0 +1,78 @@
+use itertools::Itertools;
+use num_complex::Complex;
+
+use crate::find::find_exact::{join_exact_pairs, write_exact_pairs, write_exact_results};
+use crate::sequences::{complex::{canonical_pair, complex_hadamard, complex_pair_to_string, generate_complex_rowsums, is_periodic_complementary, is_smallest_shift, CS}, fourier::SpectralFilter};
+
+// * Pairs and join for the complex complementary pairs
+// The pipeline of find_exact : a complex sequence c is given by the ±1 sequences (a, b) with (1+i)c = a + ib,
+// so the pairs (a, b) and (e, f) are written in pair_WX.pair and pair_YZ.pair with the autocorrelations of c and the opposite
+// of those of d, and the files are sorted and merged. The autocorrelations are Gaussian integers, which are written exactly.
+// The real parts of the autocorrelations of a, b, e and f cancel out, so the spectral filter of the quadruples applies to the
+// pairs (a, b), and the PSD of c is bounded by 2n. The sequences c and d can be shifted independently, so only the smallest
+// of their shifts are written
+
+pub fn complex_folder(n : usize) -> String {
+    "results/pairs/ccp/find_".to_string() + &n.to_string()
+}
+
+pub fn write_complex_pairs(folder : &String, n : usize, filter : SpectralFilter) -> u64 {
+    // This is the starting point of the pairs stage for the complex complementary pairs, the rowsum directories are created in folder
+    // The lines are keyed by the autocorrelations of the complex sequence at the offsets 1..=n/2
+
+    // The bounds are often reached, e.g. when the PSD of d vanishes, so they are slightly raised against floating point errors
+    let tolerance = 1. + f64::EPSILON.sqrt();
+    let bound = 2. * n as f64 * tolerance + filter.slack;
+
+    let mut removed : u64 = 0;
+    let mut shifted : u64 = 0;
+
+    let description = "complex pairs of length ".to_string() + &n.to_string();
+    let counter = write_exact_pairs(folder, &description, [n; 4], generate_complex_rowsums(n), filter,
+        |_| 4. * n as f64 * tolerance,
+        |_, _, _| (),
+        |pair, _, sign| {
+            // The DFT of c is (1-i)(DFT(a) + i DFT(b))/2, whose squared modulus is |DFT(a) + i DFT(b)|^2 / 2
+            if pair.dft1.iter().zip(pair.dft2.iter()).skip(1).any(|(x, y)| (x + y * Complex::i()).norm_sqr() / 2. > bound) {
+                removed += 1;
+                return None;
+            }
+
+            let seq = CS::from_pair(pair.seq_enum1.1, pair.seq_enum2.1);
+            if !is_smallest_shift(&seq) {
+                shifted += 1;
+                return None;
+            }
+
+            Some((1..=n/2).map(|t| {
+                let correlation = seq.periodic_autocorrelation(t) * sign;
+                correlation.re.to_string() + &"_" + &correlation.im.to_string() + &"_"
+            }).collect())
+        });
+
+    println!("Removed {} pairs with the complex bound", removed);
+    println!("Skipped {} pairs whose complex sequence is a shift of a smaller one", shifted);
+    counter
+}
+
+pub fn join_complex_pairs(folder : &String, n : usize) -> Vec<(CS, CS)> {
+    // Goes through the sorted .pair files of every rowsum directory in folder, and returns the complementary pairs found up to equivalence
+    let result = join_exact_pairs(folder, [n; 4], |sequences| {
+        let pair = (CS::from_pair(&sequences[0], &sequences[1]), CS::from_pair(&sequences[2], &sequences[3]));
+        is_periodic_complementary(&[pair.0.clone(), pair.1.clone()]).then_some(pair)
+    });
+    println!("\nFound {} complex pairs after matching", result.len());
+
+    let mut reduced : Vec<(CS, CS)> = result.iter().map(canonical_pair).unique().collect();
+    reduced.sort_by_key(complex_pair_to_string);
+    println!("Found {} complex pairs after reducing to equivalence\n", reduced.len());
+
+    reduced
+}
+
+pub fn write_complex_results(folder : &String, results : &Vec<(CS, CS)>) {
+    // The pairs are written in result.seq, and the complex Hadamard matrices they give in result-chm.mat
+    write_exact_results(folder, "result.seq", results, complex_pair_to_string);
+    let matrices : Vec<String> = results.iter().filter_map(complex_hadamard).map(|hm| hm.to_string_magma()).collect();
+    write_exact_results(folder, "result-chm.mat", &matrices, |s| s.clone());
+}
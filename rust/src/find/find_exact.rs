@@ -0,0 +1,146 @@
+use std::{fs::{self, File}, io::Write, time::Instant};
+
+use crate::find::find_write::{get_order_from_dir, get_rowsums_from_dir, merge_sorted_pairs, record_pairing, EquationSide};
+use crate::sequences::{fourier::{SequencePairData, SpectralFilter, SpectralPruning}, rowsum::{generate_sequences_with_rowsum, rank_sequence, unrank_sequence, Quad}, symmetries::RowsumPairing, williamson::SequenceTag};
+
+// * Pairs and join keyed by exact correlations
+// The aperiodic families and the complex complementary pairs go through the same pipeline as the quadruples, with the pairs
+// always (W, X) and (Y, Z) : the pairs are written in pair_WX.pair and pair_YZ.pair, keyed by exact integer correlations, and the
+// sorted files are merged. The searches only differ by the lengths and rowsums of their sequences, by the key of a pair and by
+// the result formed by the four ±1 sequences of a match, which are given as closures
+
+const TAGS : [SequenceTag; 4] = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
+
+// Writes the .pair files of every rowsum in folder. The pair of the sequences i and i+1 (i = 0 or 2) is pruned with bound(i),
+// prepare computes once per sequence what the keys need, from the sequence, its index and the sign of its side of the equation,
+// and key gives the key of a pair from its data and the prepared values of its sequences, or None to skip the pair
+pub fn write_exact_pairs<P>(folder : &String, description : &str, lengths : [usize; 4], rowsums : Vec<Quad>, filter : SpectralFilter,
+                            bound : impl Fn(usize) -> f64, prepare : impl Fn(&Vec<i8>, usize, isize) -> P,
+                            mut key : impl FnMut(&SequencePairData, (&P, &P), isize) -> Option<String>) -> u64 {
+    let mut counter : u64 = 0;
+    let time = Instant::now();
+    fs::create_dir_all(folder).expect("Error when creating the dir");
+
+    println!("Generating rowsum decompositions for {} ...", description);
+    for rs in &rowsums {
+        println!("{:?}", rs);
+    }
+    println!("Generated {} different rowsums\n", rowsums.len());
+
+    for rs in rowsums {
+        println!("Generating .pair files for rowsums {:?} ...", rs);
+        let folder_path = folder.clone() + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
+        fs::create_dir_all(&folder_path).expect("Error when creating the dir");
+
+        let rowsums = [rs.0, rs.1, rs.2, rs.3];
+        let sequences : Vec<Vec<Vec<i8>>> = (0..4).map(|i| generate_sequences_with_rowsum(rowsums[i], lengths[i])).collect();
+        for i in 0..4 {
+            println!("Found {} sequences of length {} with rowsum {}", sequences[i].len(), lengths[i], rowsums[i]);
+        }
+
+        // The pairs are always (W, X) and (Y, Z)
+        record_pairing(&folder_path, &Some(RowsumPairing::WX));
+
+        for (i, side) in [(0, EquationSide::LEFT), (2, EquationSide::RIGHT)] {
+            let sign = match side {
+                EquationSide::LEFT => 1,
+                EquationSide::RIGHT => -1
+            };
+            let prepared = |i : usize| -> Vec<P> {sequences[i].iter().map(|seq| prepare(seq, i, sign)).collect()};
+            let (prepared1, prepared2) = (prepared(i), prepared(i+1));
+
+            counter += write_exact_seq_pairs((&sequences[i], &sequences[i+1]), (&TAGS[i], &TAGS[i+1]), bound(i), &folder_path, filter,
+                |pair| key(pair, (&prepared1[pair.seq_enum1.0], &prepared2[pair.seq_enum2.0]), sign));
+        }
+    }
+
+    let elapsed = time.elapsed().as_secs_f32();
+    println!("Generated {} total pairs", counter);
+    println!("Total time to generate .pair files: {:.2} seconds\n", elapsed);
+    counter
+}
+
+fn write_exact_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&SequenceTag, &SequenceTag), bound : f64, folder_path : &String, filter : SpectralFilter,
+                         mut key : impl FnMut(&SequencePairData) -> Option<String>) -> u64 {
+    // Writes the .pair file of a pair of sequences, each line holding the key of a pair and the ranks of its sequences
+
+    let path = folder_path.clone() + &"/pair_" + &tags.0.to_string() + &tags.1.to_string() + ".pair";
+    let mut f = File::create(path).expect("Invalid file ?");
+
+    let mut buffer = "".to_string();
+    let mut counter : u64 = 0;
+
+    let pruning = SpectralPruning::new(sequences.0, sequences.1, bound, filter);
+    for pair in pruning.couples() {
+        let Some(mut result) = key(&pair) else {
+            continue;
+        };
+        if result.len() == 0 {
+            result += &"_";
+        }
+        result += &(":_".to_string() + &rank_sequence(pair.seq_enum1.1).to_string() + "_" + &rank_sequence(pair.seq_enum2.1).to_string() + &"\n");
+
+        buffer += &result;
+        counter += 1;
+
+        if counter % 1000 == 0 {
+            f.write(buffer.as_bytes()).expect("Error when writing in the file");
+            buffer = "".to_string();
+        }
+    }
+    f.write(buffer.as_bytes()).expect("Error when writing in the file");
+
+    println!("Generated {} pairs for pairing {}{}", counter, &tags.0.to_string(), &tags.1.to_string());
+    println!("Spectral pruning for pairing {}{}: {}", &tags.0.to_string(), &tags.1.to_string(), pruning.to_string());
+
+    counter
+}
+
+pub fn join_exact_pairs<T>(folder : &String, lengths : [usize; 4], mut result : impl FnMut([Vec<i8>; 4]) -> Option<T>) -> Vec<T> {
+    // Goes through the sorted .pair files of every rowsum directory in folder, and returns the results formed by the matches
+    let time = Instant::now();
+
+    let mut results = vec![];
+
+    for directory in fs::read_dir(folder).expect("Missing folder") {
+        let directory = directory.unwrap();
+        if !directory.metadata().unwrap().is_dir() {
+            continue;
+        }
+
+        let rowsums = get_rowsums_from_dir(&directory);
+        let (pathnames, order) = get_order_from_dir(&directory);
+
+        println!("Matching files in /{} ...", directory.file_name().into_string().expect("File error"));
+        merge_sorted_pairs(&pathnames, |ranks| {
+            results.extend(result(get_exact_sequences(lengths, rowsums, &order, ranks)));
+        });
+    }
+
+    println!("Matching took: {:.2} seconds.", time.elapsed().as_secs_f32());
+    results
+}
+
+fn get_exact_sequences(lengths : [usize; 4], rowsums : Quad, order : &(SequenceTag, SequenceTag, SequenceTag, SequenceTag), ranks : &(usize, usize, usize, usize)) -> [Vec<i8>; 4] {
+    // Reconstructs the sequences W, X, Y, Z from their ranks, order giving the sequence of each rank
+    let rowsums = [rowsums.0, rowsums.1, rowsums.2, rowsums.3];
+
+    let mut quad : [Vec<i8>; 4] = Default::default();
+    for (tag, rank) in [order.0, order.1, order.2, order.3].into_iter().zip([ranks.0, ranks.1, ranks.2, ranks.3]) {
+        let i = match tag {
+            SequenceTag::W => 0,
+            SequenceTag::X => 1,
+            SequenceTag::Y => 2,
+            SequenceTag::Z => 3
+        };
+        quad[i] = unrank_sequence(rank, lengths[i], rowsums[i]);
+    }
+    quad
+}
+
+// Writes one line per result in the file name of folder
+pub fn write_exact_results<T>(folder : &String, name : &str, results : &Vec<T>, to_string : impl Fn(&T) -> String) {
+    let mut f = File::create(folder.clone() + &"/" + name).expect("Invalid file ?");
+    let s = results.iter().map(|result| to_string(result) + &"\n").fold("".to_string(), |s, t| s + &t);
+    f.write(s.as_bytes()).expect("Error when writing in the file");
+}
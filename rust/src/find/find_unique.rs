@@ -5,7 +5,7 @@ use std::collections::HashSet;
 
 use itertools::Itertools;
 
-use crate::sequences::{equivalence::{equivalent_automorphism, equivalent_even_alternated_negation, equivalent_uniform_shift, equivalent_dual_half_shift, equivalent_uniform_half_shift, generate_equivalence_class, generate_equivalence_class_fast, generate_symmetry_group, qt_canonical, will_less_than}, symmetries::SequenceType, williamson::{QuadSeq, QUADRUPLETS}};
+use crate::sequences::{equivalence::{equivalent_automorphism, generate_canonical_representative, equivalent_even_alternated_negation, equivalent_uniform_shift, equivalent_dual_half_shift, equivalent_uniform_half_shift, generate_equivalence_class, generate_equivalence_class_fast, generate_symmetry_group, qt_canonical, will_less_than}, symmetries::SequenceType, williamson::{QuadSeq, QUADRUPLETS}};
 
 
 
@@ -187,6 +187,15 @@ pub fn reduce_to_equivalence(sequences : &Vec<QuadSeq>, seqtype : SequenceType,
 }
 
 pub fn reduce_to_canonical_reps(sequences : &Vec<QuadSeq>, seqtype : SequenceType) -> Vec<QuadSeq> {
+    // The equivalences of good and best matrices don't act on all the sequences alike, their classes are generated directly
+    if matches!(seqtype, SequenceType::Good | SequenceType::Best) {
+        return sequences.iter().map(|seq| generate_canonical_representative(seq, seqtype)).unique().collect();
+    }
+    // The symmetry group is built from the length of the first sequence, a search with a shape may find none
+    if sequences.is_empty() {
+        return vec![];
+    }
+
     let symmetries;
     if matches!(seqtype, SequenceType::QuaternionType) {
         symmetries = generate_symmetry_group(sequences[0].size(), seqtype, &vec![equivalent_automorphism, equivalent_even_alternated_negation, equivalent_uniform_shift, equivalent_dual_half_shift]);
@@ -1,101 +1,169 @@
-use std::{isize::MIN, fs::{*, self}, path::Path, io::Write, env/*, time::Instant*/};
+use std::{collections::{HashMap, HashSet}, fs::{self, File}, io::Write, path::Path, time::Instant};
 
+use itertools::Itertools;
 use memory_stats::memory_stats;
 
-use crate::sequences::{rowsum::{generate_rowsums, Quad, generate_sequences_with_rowsum, sequence_to_string}, fourier::{iter_over_filtered_dft/*, iter_over_filtered_couples*/}, equations::generate_equations, williamson::{SequenceTag/*, QuadSeq*/}, symmetries::SequenceType/*, matching::{generate_matching_table, MatchData, compute_complementary_auto_correlations, compute_complementary_cross_correlations, verify_cross_correlation}*/};
+use crate::find::find_write::reduce_matches;
+use crate::sequences::{rowsum::{generate_rowsums, Quad}, equations::{generate_formula, generate_incremental_formula, quad_variable, rowsum_assumptions}, fourier::dft_sequence, sat::{all_models, Lit, Solver, SolverBackend}, williamson::{SequenceTag, QuadSeq}, symmetries::SequenceType};
 
+// * Search with a solver
+// For every rowsum, the conditions on the quadruples are written as a formula (see equations.rs) in results/equations,
+// in the OPB format for the pseudo-Boolean solvers and in the DIMACS format for the SAT solvers, and all its models are
+// enumerated by the embedded CDCL solver or by an external SAT solver. The quadruples found are reduced to equivalence
+// as in the join stage, so that the results can be compared with the ones of the pair pipeline
 
-fn get_two_best(quad: &Quad) -> ((isize, usize),(isize, usize)){
-    let (mut maxi, mut index) = (quad.0, 0);
-    if quad.1 > maxi {(maxi, index) = (quad.1, 1)}
-    if quad.2 > maxi {(maxi, index) = (quad.2, 2)}
-    if quad.3 > maxi {(maxi, index) = (quad.3, 3)}
+pub fn equations_folder(p : usize, seqtype : SequenceType) -> String {
+    "results/equations/".to_string() + &seqtype.to_string() + &"/find_" + &p.to_string()
+}
 
-    let (mut maxi2, mut index2) = (MIN, 4);
-    if quad.0 >= maxi2 && index != 0 {(maxi2, index2) = (quad.0, 0)}
-    if quad.1 >= maxi2 && index != 1 {(maxi2, index2) = (quad.1, 1)}
-    if quad.2 >= maxi2 && index != 2 {(maxi2, index2) = (quad.2, 2)}
-    if quad.3 >= maxi2 && index != 3 {(maxi2, index2) = (quad.3, 3)}
+pub fn find(p : usize, seqtype : SequenceType, backend : &SolverBackend) -> Vec<QuadSeq> {
+    // Finds the quadruples of type seqtype and length p up to equivalence, by solving the formula of every rowsum
+    let time = Instant::now();
 
-    if index < index2 {
-        ((maxi,index),(maxi2,index2))
-    }
-    else{
-        ((maxi2,index2),(maxi,index))
-    }
-}
+    let rowsums = generate_rowsums(p, seqtype);
+    println!("Generated {} different rowsums", rowsums.len());
+
+    let folder = equations_folder(p, seqtype);
+    fs::create_dir_all(&folder).expect("Error when creating the dir");
+
+    let mut result = vec![];
+    for rs in rowsums {
+        let path = folder.clone() + &"/rowsum_" + &rs.0.to_string() + &"_" + &rs.1.to_string() + &"_" + &rs.2.to_string() + &"_" + &rs.3.to_string();
+        let formula = generate_formula(seqtype, p, &rs);
+
+        let mut f = File::create(path.clone() + &".opb").expect("Invalid file ?");
+        f.write(formula.to_opb().as_bytes()).expect("Error when writing in the file");
+        let mut f = File::create(path.clone() + &".cnf").expect("Invalid file ?");
+        f.write(formula.to_dimacs().as_bytes()).expect("Error when writing in the file");
 
+        let models = all_models(&formula, 4 * p, backend, &(path.clone() + &"_solver.cnf"));
+        println!("Found {} models for the rowsums {:?}", models.len(), rs);
 
-fn index_to_tag(index: usize) -> SequenceTag {
-    match index {
-        0 => {SequenceTag::W}
-        1 => {SequenceTag::X}
-        2 => {SequenceTag::Y}
-        3 => {SequenceTag::Z}
-        _ => {panic!("incorrect index")}
+        for model in models {
+            let quad = model_to_quad(&model, p);
+            assert!(quad.verify(seqtype), "The solver returned an invalid quadruple: {}", quad.to_string());
+            result.push(quad);
+        }
     }
+
+    println!("Solving took: {:.2} seconds.", time.elapsed().as_secs_f32());
+    println!("\nFound {} {} with the solver", result.len(), seqtype.to_string());
+
+    reduce_matches(&result, seqtype)
 }
 
-fn generate_comment(seq1 : &Vec<i8>, tag1 : &SequenceTag, seq2 : &Vec<i8>, tag2 : &SequenceTag, rowsum : &Quad) -> String {
-    let mut comment = "* ".to_string() + &tag1.to_string() + &": " + &sequence_to_string(seq1) + &"\n* " + &tag2.to_string() + &": " + &sequence_to_string(seq2) + &"\n";
-    comment += &("* rowsum: (".to_string() + &rowsum.0.to_string() + &"," + &rowsum.1.to_string() + &"," + &rowsum.2.to_string() + &"," + &rowsum.3.to_string() + &")");
-    comment
+// * Search with PSD cuts
+// As in the SAT+CAS method of MathCheck, the embedded solver is called on the formula and every partial assignment is
+// checked : the PSDs of the four sequences add up to 4p at every frequency, so as soon as some sequences are fully
+// assigned and their PSDs exceed 4p at some frequency, the clause excluding their values is added as a cut. A single
+// solver is used for all the rowsums, which are given as assumptions on counters, so that the learnt clauses and the cuts
+// are shared between the rowsums
+
+pub struct PsdCuts {
+    p : usize,
+    bound : f64,
+    psds : HashMap<Vec<i8>, Vec<f64>>,     // the PSDs already computed, frequency 0 excluded
+    cuts : u64
 }
 
+impl PsdCuts {
 
+    pub fn new(p : usize) -> PsdCuts {
+        // the bound is slightly increased against the floating point errors, the sums of PSDs being often exactly 4p
+        PsdCuts { p, bound : 4. * p as f64 * (1. + f64::EPSILON.sqrt()), psds : HashMap::new(), cuts : 0 }
+    }
 
+    pub fn cuts(&self) -> u64 {
+        self.cuts
+    }
 
-pub fn find(p : usize, seqtype : SequenceType) {
-    // Find sequences using the approach of using a solver. It ended up being much slower than desired
+    pub fn check(&mut self, values : &[i8]) -> Option<Vec<Lit>> {
+        // returns the clause excluding the values of the smallest set of fully assigned sequences exceeding the bound
+        let p = self.p;
+        let assigned : Vec<(usize, Vec<i8>)> = (0..4).filter_map(|index| {
+            let seq : Vec<i8> = (0..p).map(|k| values[quad_variable(index, k, p) as usize]).collect();
+            if seq.contains(&0) {None} else {Some((index, seq))}
+        }).collect();
 
-    let rowsums = generate_rowsums(p, seqtype);
-    println!("generated {} different rowsums", rowsums.len());
-    println!("Current directory: {:?}", env::current_dir().ok().unwrap());
+        for (_, seq) in &assigned {
+            self.psds.entry(seq.clone()).or_insert_with(|| dft_sequence(seq).iter().skip(1).map(|elm| elm.norm_sqr()).collect());
+        }
 
-    let folder = match seqtype {
-        SequenceType::QuaternionType => {"qts"}
-        _ => {panic!("not implemented yet")} // TODO
-    };
+        // The four sequences are checked by the clauses, when they are all assigned
+        for size in 1..assigned.len().min(3) + 1 {
+            for subset in assigned.iter().combinations(size) {
+                let psds : Vec<&Vec<f64>> = subset.iter().map(|(_, seq)| &self.psds[seq]).collect();
+                if (0..psds[0].len()).any(|m| psds.iter().map(|psd| psd[m]).sum::<f64>() > self.bound) {
+                    self.cuts += 1;
+                    return Some(subset.iter().flat_map(|(index, seq)| {
+                        (0..p).map(move |k| if seq[k] == 1 {-quad_variable(*index, k, p)} else {quad_variable(*index, k, p)})
+                    }).collect());
+                }
+            }
+        }
+        None
+    }
 
-    for rs in rowsums {
-        let ((maxi,index),(maxi2,index2)) = get_two_best(&rs); // should we get the two best or just the same two each time ?
-        let (tag1, tag2) = (index_to_tag(index), index_to_tag(index2));
+}
 
-        let sequences_1 = generate_sequences_with_rowsum(maxi, p);
-        let sequences_2 = generate_sequences_with_rowsum(maxi2, p);
-        let string_path = "results/equations/".to_string()+ &folder + &"/find_" + &p.to_string() + &"/rowsum_" + &maxi.to_string() + &"-" + &maxi2.to_string() + &"_at_" + &index.to_string() + &"-" + &index2.to_string();
+pub fn find_with_cuts(p : usize, seqtype : SequenceType) -> Vec<QuadSeq> {
+    // Finds the quadruples of type seqtype and length p up to equivalence with the embedded solver and the PSD cuts
+    let time = Instant::now();
 
-        println!("{}",string_path);
-        fs::create_dir_all(&string_path).expect("Error when creating the dir");
+    let rowsums = generate_rowsums(p, seqtype);
+    println!("Generated {} different rowsums", rowsums.len());
+
+    let (formula, counters) = generate_incremental_formula(seqtype, p);
+    let (num_vars, clauses) = formula.to_cnf();
+    let mut solver = Solver::new(num_vars, &clauses);
+    let mut cuts = PsdCuts::new(p);
+
+    let mut result = vec![];
+    for rs in rowsums {
+        let assumptions = match rowsum_assumptions(&counters, p, &rs) {
+            Some(assumptions) => assumptions,
+            None => continue
+        };
 
         let mut count = 0;
-        for seq1 in iter_over_filtered_dft(&sequences_1, 4.*p as f64){
-            println!("loop");
-            for seq2 in iter_over_filtered_dft(&sequences_2, 4.*p as f64){
-                let s = string_path.clone() + &"/num_" + &count.to_string() + &".opb";
-                println!("{}",s);
-                let path = Path::new(&s);
-
-                // convert to equations
-                let equations = generate_equations(&seq1, &tag1, &seq2, &tag2, &seqtype, &rs);
-                // TODO add rowsum constraints
-
-                //if there are equations to write, create a new file
-                if equations != "" {
-                    let mut f = File::create(path).expect("Invalid file ?");
-                    let comment = generate_comment(seq1, &tag1, seq2, &tag2, &rs);
-                    f.write(comment.as_bytes()).expect("Error when writing in the file");
-                    f.write(equations.as_bytes()).expect("Error when writing in the file");
-                    count +=1;
-                }
+        while let Some(model) = solver.solve_with(&assumptions, &mut |values| cuts.check(values)) {
+            let quad = model_to_quad(&model, p);
+            assert!(quad.verify(seqtype), "The solver returned an invalid quadruple: {}", quad.to_string());
+            result.push(quad);
+            count += 1;
+            solver.add_clause((1..=4 * p).map(|v| if model[v] {-(v as Lit)} else {v as Lit}).collect());
+        }
+        println!("Found {} models for the rowsums {:?}", count, rs);
+    }
 
-                // solve using libexact
+    println!("Solving took: {:.2} seconds, with {} conflicts and {} PSD cuts.", time.elapsed().as_secs_f32(), solver.conflicts(), cuts.cuts());
+    println!("\nFound {} {} with the solver", result.len(), seqtype.to_string());
 
+    reduce_matches(&result, seqtype)
+}
 
-            }
-        }
+pub fn model_to_quad(model : &Vec<bool>, p : usize) -> QuadSeq {
+    // Reads the quadruple from the values of the variables of generate_formula, model being indexed by the variables
+    let mut quad = QuadSeq::new(p);
+    for (index, tag) in [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].iter().enumerate() {
+        let seq : Vec<i8> = (0..p).map(|k| if model[quad_variable(index, k, p) as usize] {1} else {-1}).collect();
+        quad.set_sequence(&seq, tag);
+    }
+    quad
+}
+
+pub fn compare_with_pairs(p : usize, seqtype : SequenceType, result : &Vec<QuadSeq>) -> Option<(Vec<String>, Vec<String>)> {
+    // Compares the quadruples with the result.seq of the pair pipeline, if it has been computed,
+    // and returns the sequences only found by the pair pipeline and those only found by the solver
+    let path = "./results/pairs/".to_string() + &seqtype.to_string() + &"/find_" + &p.to_string() + &"/result.seq";
+    if !Path::new(&path).exists() {
+        return None;
     }
 
+    let pairs : HashSet<String> = fs::read_to_string(&path).expect("Error when reading the file").lines().map(|line| line.to_string()).collect();
+    let found : HashSet<String> = result.iter().map(|quad| quad.to_qs().to_string_raw()).collect();
+    println!("{} sequences only found by the pair pipeline, {} only found by the solver", pairs.difference(&found).count(), found.difference(&pairs).count());
+    Some((pairs.difference(&found).cloned().sorted().collect(), found.difference(&pairs).cloned().sorted().collect()))
 }
 
 
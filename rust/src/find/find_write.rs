@@ -2,7 +2,7 @@ use std::{f64, fs::{self, DirEntry, File}, io::{Error, Write}, time::Instant};
 use itertools::{iproduct, Itertools};
 use memory_stats::memory_stats;
 
-use crate::{find::find_unique::reduce_to_canonical_reps, read_lines, sequences::{equivalence::ns_canonical, equivalence::sn_ss_canonical, fourier::iter_over_enumerate_filtered_couples_psds, matching::{compute_auto_correlation_pair_dft, compute_cross_correlations_dft, compute_cross_psd_pair, compute_psd_pair}, rowsum::{generate_rowsums, generate_sequences_with_rowsum, has_sorted_rowsums, rowsum, sequence_to_string, Quad}, symmetries::*, williamson::{QuadSeq, SequenceTag}}, str_to_seqtype};
+use crate::{find::find_unique::reduce_to_canonical_reps, read_lines, sequences::{equivalence::{canonical_first_sequences, ns_canonical, sn_ss_canonical}, fourier::{psd_within_bound, SequencePairData, SpectralFilter, SpectralPruning}, matching::{compute_auto_correlation_pair_dft, compute_complementary_cross_correlations, compute_cross_correlations_dft, compute_cross_psd_pair, compute_exact_cross_psd_pair, compute_exact_psd_pair, compute_psd_pair}, rowsum::{generate_rowsums, generate_rowsum_classes, RowsumClass, rank_sequence, unrank_sequence, generate_sequences_with_rowsum, generate_sequences_with_rowsum_compressed, generate_sequences_with_shape, quad_rowsums, rowsum, Quad}, symmetries::*, williamson::{QuadSeq, SequenceTag}}, str_to_rowsum_pairing, str_to_seqtype};
 
 
 
@@ -13,21 +13,40 @@ pub fn quad_to_string(q : (isize, isize, isize, isize)) -> String {
     a.to_string() + &" " + &b.to_string() + &" " + &c.to_string() + &" " + &d.to_string() + &"\n"
 }
 
-pub fn write_rowsums(p : usize, seqtype : SequenceType) {
-    // Stores the possible rowsums for qts sequences of length p
+pub fn pairs_folder(folder : &str, p : usize, shape : Option<SequenceShape>) -> String {
+    // The folder of the search of length p, a search restricted to a shape has its own folder find_<p>_<shape>
+    "results/pairs/".to_string() + folder + &"/find_" + &p.to_string() + &shape.map_or("".to_string(), |shape| "_".to_string() + &shape.to_string())
+}
+
+pub fn write_rowsums(p : usize, seqtype : SequenceType, shape : Option<SequenceShape>) {
+    // Stores the rowsums to search for sequences of the type of length p, one line "w x y z multiplicity" per class
+    // of rowsum vectors up to equivalence, the multiplicity being the number of rowsum vectors in the class
     let folder = seqtype.to_string();
 
-    let folder_path = "results/pairs/".to_string()+ &folder + &"/find_" + &p.to_string();
+    let folder_path = pairs_folder(&folder, p, shape);
+    fs::create_dir_all(&folder_path).expect("Error when creating the dir");
     let path = folder_path.clone() + &"/rowsums.quad";
     let mut f = File::create(path).expect("Invalid file ?");
 
-    let rs = generate_rowsums(p, seqtype);
+    let classes = generate_rowsum_classes(p, seqtype);
 
-    let s = rs.iter().map(|e| quad_to_string(*e)).fold("".to_string(), |a,b| a + &b);
+    let header = format!("# {} {p}: w x y z multiplicity\n", seqtype.to_string());
+    let s = classes.iter().map(|class| class.to_string() + &"\n").fold(header, |a,b| a + &b);
 
     f.write(s.as_bytes()).expect("Error writing file");
 }
 
+pub fn read_rowsums(folder : &String, p : usize, shape : Option<SequenceShape>) -> Vec<RowsumClass> {
+    // Reads the rowsums.quad file written by write_rowsums, skipping the comment lines
+    let path = pairs_folder(folder, p, shape) + &"/rowsums.quad";
+
+    read_lines(&path).expect("Error reading the rowsums, they are written by the rowsums command")
+        .map(|line| line.expect("Error reading line"))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| RowsumClass::from_str(&line))
+        .collect()
+}
+
 
 
 #[derive(Clone, Copy)]
@@ -37,7 +56,7 @@ pub enum EquationSide {
 
 #[derive(Clone, Copy)]
 pub enum MatchOption {
-    CORRELATION, PSD
+    CORRELATION, PSD, EXACT
 }
 
 
@@ -82,17 +101,6 @@ fn index_to_tag(index: usize) -> SequenceTag {
     }
 }
 
-pub fn write_sequences(sequences : &Vec<Vec<i8>>, tag : &SequenceTag, folder_path : &String) {
-    // stores the sequences
-
-    let path = folder_path.clone() + &"/seq_" + &tag.to_string() + ".seq";
-    let mut f = File::create(path).expect("Invalid file ?");
-
-    for seq in sequences {
-        f.write((sequence_to_string(seq) + &"\n").as_bytes()).expect("Error when writing in the file");
-    }
-}
-
 pub fn verify_rowsums(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&SequenceTag, &SequenceTag), rs : (isize, isize, isize, isize)) -> bool {
     let rowsum_0 : isize = match tags.0 {
             SequenceTag::W => rs.0,
@@ -125,7 +133,7 @@ pub fn verify_rowsums(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&Seque
     true
 }
 
-pub fn write_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&SequenceTag, &SequenceTag), seqtype : SequenceType, rs : (isize, isize, isize, isize), p : usize, folder_path : &String, side : EquationSide, match_option : MatchOption) -> u64 {
+pub fn write_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&SequenceTag, &SequenceTag), seqtype : SequenceType, rs : (isize, isize, isize, isize), p : usize, folder_path : &String, side : EquationSide, match_option : MatchOption, filter : SpectralFilter) -> u64 {
     // This function generates the files that end in .pair used for the algorithm
 
     assert!(verify_rowsums(sequences, tags, rs));
@@ -135,95 +143,20 @@ pub fn write_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&Sequ
 
     let f64_tolerance : f64 = f64::EPSILON.sqrt();
 
-    let op = match side {
-        EquationSide::LEFT => {|x : isize| x}
-        EquationSide::RIGHT => {|x : isize| -x}
-    };
-
     // Instead of writing each line one by one n the file, we use a buffer to write them by chunks of 1000 lines
     let mut buffer = "".to_string();
     let mut counter : u64 = 0;
     
-    let mut min_half_int_difference_psd = 1.0;
-    let mut min_half_int_difference_cpsd = 1.0;
+    let mut min_half_int_difference = (1.0, 1.0);
 
-    // We iterate over the couples of sequences, but we filter out some with the dft checks
-    for pair in iter_over_enumerate_filtered_couples_psds(sequences.0, sequences.1, 4.*p as f64){
-        let mut result = "".to_string();
-
-        // We compute the auto and cross correlation values when considered on the other side of the equation
-        match match_option {
-            // For matching via auto/cross correlation
-            MatchOption::CORRELATION => {
-                let autoc_values = compute_auto_correlation_pair_dft(&pair.norm1, pair.seq_enum1.1.len(), &pair.norm2, pair.seq_enum2.1.len());
-                let crossc_values = compute_cross_correlations_dft(&pair.dft1, &pair.dft2, &(tags.0.clone(), tags.1.clone()), pair.seq_enum1.1.len());
-                
-                // Add autocorrelation values to vector
-                for a in autoc_values {
-                    result += &(op(a).to_string() + &"_");
-                }
+    let pruning = SpectralPruning::new(sequences.0, sequences.1, 4.*p as f64, pair_filter(p, match_option, filter));
 
-                // Add crosscorrelation values to vector
-                match seqtype {
-                    SequenceType::QuaternionType => {
-                        for c in crossc_values {
-                            result += &(op(c).to_string() + &"_");
-                        }
-                    },
-                    SequenceType::WilliamsonType => {
-                        if crossc_values.into_iter().any(|val| val != 0) {
-                            continue;
-                        }
-                    },
-                    // Williamson sequences only require symmetry, and the PAF conditions
-                    SequenceType::Williamson => {}
-                    _ => {panic!("Not implemented yet");}
-                }
-            },
-            // For matching via PSD/CPSD
-            MatchOption::PSD => {
-                let psd_values = compute_psd_pair(&pair.norm1, &pair.norm2, p, side);
-                let cpsd_values = compute_cross_psd_pair(pair.dft1, pair.dft2, &(tags.0.clone(), tags.1.clone()), pair.seq_enum1.1.len());
-
-                // We add these values to the current line
-                for a in psd_values {
-                    let difference = (a.fract() - 0.5).abs();
-                    if difference < min_half_int_difference_psd {
-                        min_half_int_difference_psd = difference;
-                    }
-
-                    result += &((a.round() as isize).to_string() + &"_");
-                }
-                
-                // Depending on the sequence type, crosscorrelation values might not need to be stored, or even computed
-                match seqtype {
-                    SequenceType::QuaternionType => {
-                        for c in cpsd_values {
-                            let difference = (c.im.fract() - 0.5).abs();
-                            if difference < min_half_int_difference_cpsd {
-                                min_half_int_difference_cpsd = difference;
-                            }
-
-                            result += &(op(c.im.round() as isize).to_string() + &"_");
-                        }
-                    },
-                    SequenceType::WilliamsonType => {
-                        if cpsd_values.into_iter().any(|val| val.im.abs() > 0.0001) {
-                            continue;
-                        }
-                    },
-                    // Williamson sequences only require symmetry, and the PAF conditions
-                    SequenceType::Williamson => {}
-                    _ => {panic!("Not implemented yet");}
-                }
-            }
-        }
-        
-        if result.len() == 0 {
-            result += &"_";
-        }
-
-        result += &(":_".to_string() + &pair.seq_enum1.0.to_string() + "_" + &pair.seq_enum2.0.to_string() + &"\n");
+    // We iterate over the couples of sequences, but we filter out some with the dft checks
+    for pair in pruning.couples(){
+        let result = match pair_line(&pair, tags, seqtype, p, side, match_option, &mut min_half_int_difference) {
+            Some(result) => result,
+            None => continue
+        };
 
         buffer += &result;
         counter += 1;
@@ -236,13 +169,14 @@ pub fn write_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&Sequ
     }
 
     println!("Generated {} pairs for pairing {}{}", counter, &tags.0.to_string(), &tags.1.to_string());
+    println!("Spectral pruning for pairing {}{}: {}", &tags.0.to_string(), &tags.1.to_string(), pruning.to_string());
 
     // If PSD/CPSD values are very close to a half-integer then print a warning
-    if min_half_int_difference_psd < f64_tolerance {
-        println!("WARNING (pair {}{}): PSD values approximate half-integer with error as small as {}", tags.0.to_string(), tags.1.to_string(), min_half_int_difference_psd);
+    if min_half_int_difference.0 < f64_tolerance {
+        println!("WARNING (pair {}{}): PSD values approximate half-integer with error as small as {}", tags.0.to_string(), tags.1.to_string(), min_half_int_difference.0);
     }
-    if min_half_int_difference_cpsd < f64_tolerance {
-        println!("WARNING (pair {}{}): CPSD values approximate half-integer with error as small as {}", tags.0.to_string(), tags.1.to_string(), min_half_int_difference_cpsd);
+    if min_half_int_difference.1 < f64_tolerance {
+        println!("WARNING (pair {}{}): CPSD values approximate half-integer with error as small as {}", tags.0.to_string(), tags.1.to_string(), min_half_int_difference.1);
     }
     
     f.write(buffer.as_bytes()).expect("Error when writing in the file");
@@ -250,6 +184,181 @@ pub fn write_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&Sequ
     counter
 }
 
+// The spectral filter applied to the pairs of sequences of length p
+fn pair_filter(p : usize, match_option : MatchOption, filter : SpectralFilter) -> SpectralFilter {
+    // The PSD bound only holds at the frequencies of the DFT for periodic sequences, str_to_filter rejects any other grid
+    debug_assert_eq!(filter.oversampling, 1, "The finer grid of frequencies can't be used for periodic sequences");
+
+    // In exact mode, a PSD value of exactly 4p computed with a small error must not filter out the pair
+    match match_option {
+        MatchOption::EXACT => SpectralFilter { slack : filter.slack.max(4.*p as f64 * f64::EPSILON.sqrt()), ..filter },
+        _ => filter
+    }
+}
+
+// Computes the line of the .pair file for a pair of sequences, or None if the pair can't be part of a quadruple.
+// The smallest distances of the PSD and CPSD values to a half-integer are kept in min_half_int_difference
+fn pair_line(pair : &SequencePairData, tags : (&SequenceTag, &SequenceTag), seqtype : SequenceType, p : usize, side : EquationSide, match_option : MatchOption, min_half_int_difference : &mut (f64, f64)) -> Option<String> {
+    let mut result = "".to_string();
+
+    let op = match side {
+        EquationSide::LEFT => {|x : isize| x}
+        EquationSide::RIGHT => {|x : isize| -x}
+    };
+
+    // We compute the auto and cross correlation values when considered on the other side of the equation
+    match match_option {
+        // For matching via auto/cross correlation
+        MatchOption::CORRELATION => {
+            let autoc_values = compute_auto_correlation_pair_dft(pair.norm1, pair.seq_enum1.1.len(), pair.norm2, pair.seq_enum2.1.len());
+            let crossc_values = compute_cross_correlations_dft(pair.dft1, pair.dft2, &(tags.0.clone(), tags.1.clone()), pair.seq_enum1.1.len());
+            
+            // Add autocorrelation values to vector
+            for a in autoc_values {
+                result += &(op(a).to_string() + &"_");
+            }
+
+            // Add crosscorrelation values to vector
+            match seqtype {
+                SequenceType::QuaternionType => {
+                    for c in crossc_values {
+                        result += &(op(c).to_string() + &"_");
+                    }
+                },
+                SequenceType::WilliamsonType => {
+                    if crossc_values.into_iter().any(|val| val != 0) {
+                        return None;
+                    }
+                },
+                // Williamson sequences, good and best matrices only require their structure, and the PAF conditions
+                SequenceType::Williamson | SequenceType::Good | SequenceType::Best => {}
+                _ => {panic!("Not implemented yet");}
+            }
+        },
+        // For matching via PSD/CPSD
+        MatchOption::PSD => {
+            let psd_values = compute_psd_pair(pair.norm1, pair.norm2, p, side);
+            let cpsd_values = compute_cross_psd_pair(pair.dft1, pair.dft2, &(tags.0.clone(), tags.1.clone()), pair.seq_enum1.1.len());
+
+            // We add these values to the current line
+            for a in psd_values {
+                let difference = (a.fract() - 0.5).abs();
+                if difference < min_half_int_difference.0 {
+                    min_half_int_difference.0 = difference;
+                }
+
+                result += &((a.round() as isize).to_string() + &"_");
+            }
+            
+            // Depending on the sequence type, crosscorrelation values might not need to be stored, or even computed
+            match seqtype {
+                SequenceType::QuaternionType => {
+                    for c in cpsd_values {
+                        let difference = (c.im.fract() - 0.5).abs();
+                        if difference < min_half_int_difference.1 {
+                            min_half_int_difference.1 = difference;
+                        }
+
+                        result += &(op(c.im.round() as isize).to_string() + &"_");
+                    }
+                },
+                SequenceType::WilliamsonType => {
+                    if cpsd_values.into_iter().any(|val| val.im.abs() > 0.0001) {
+                        return None;
+                    }
+                },
+                // Williamson sequences, good and best matrices only require their structure, and the PAF conditions
+                SequenceType::Williamson | SequenceType::Good | SequenceType::Best => {}
+                _ => {panic!("Not implemented yet");}
+            }
+        },
+        // For matching via PSD/CPSD computed from the exact correlation values, matching pairs always get the same key
+        MatchOption::EXACT => {
+            let psd_values = compute_exact_psd_pair(pair.seq_enum1.1, pair.seq_enum2.1, side);
+
+            for a in psd_values {
+                result += &((a.round() as isize).to_string() + &"_");
+            }
+
+            match seqtype {
+                SequenceType::QuaternionType => {
+                    for c in compute_exact_cross_psd_pair(pair.seq_enum1.1, pair.seq_enum2.1, &(tags.0.clone(), tags.1.clone()), side) {
+                        result += &((c.round() as isize).to_string() + &"_");
+                    }
+                },
+                SequenceType::WilliamsonType => {
+                    // The CPSD values all vanish exactly when the cross correlation values do
+                    if compute_complementary_cross_correlations(pair.seq_enum1.1, pair.seq_enum2.1, &(tags.0.clone(), tags.1.clone())).into_iter().any(|val| val != 0) {
+                        return None;
+                    }
+                },
+                // Williamson sequences, good and best matrices only require their structure, and the PAF conditions
+                SequenceType::Williamson | SequenceType::Good | SequenceType::Best => {}
+                _ => {panic!("Not implemented yet");}
+            }
+        }
+    }
+    
+    if result.len() == 0 {
+        result += &"_";
+    }
+
+    // The sequences are referred to by their rank, so that the join can reconstruct them from the rowsums alone
+    result += &(":_".to_string() + &rank_sequence(pair.seq_enum1.1).to_string() + "_" + &rank_sequence(pair.seq_enum2.1).to_string() + &"\n");
+    Some(result)
+}
+
+// Estimates the number of pairs of each pairing from the number of sequences satisfying the single-sequence PSD bound,
+// and returns the pairing with the fewest pairs
+pub fn choose_pairing(sequences : [&Vec<Vec<i8>>; 4], p : usize, filter : SpectralFilter) -> RowsumPairing {
+    let bound = 4. * p as f64 + filter.slack;
+    let counts : Vec<u64> = sequences.iter().map(|seqs| seqs.iter().filter(|seq| psd_within_bound(seq, bound)).count() as u64).collect();
+
+    let estimate = |pairing : &RowsumPairing| -> u64 {
+        let (i, j) = get_indices(Some(pairing.clone()), 1).expect("Concrete pairing");
+        let (k, l) = get_indices(Some(pairing.clone()), 2).expect("Concrete pairing");
+        counts[i] * counts[j] + counts[k] * counts[l]
+    };
+
+    // WZ comes first so that it stays the default in case of a tie
+    let pairings = [RowsumPairing::WZ, RowsumPairing::WX, RowsumPairing::WY];
+    for pairing in &pairings {
+        println!("Pairing {} gives at most {} pairs", pairing.to_string(), estimate(pairing));
+    }
+    let best = pairings.into_iter().min_by_key(|pairing| estimate(pairing)).expect("No pairing ?");
+    println!("Chose pairing {}", best.to_string());
+    best
+}
+
+// Chooses the pairing of a rowsum from the sequences of each tag, generated as for the .pair files
+fn choose_rowsum_pairing(seqtype : SequenceType, rs : Quad, p : usize, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) -> RowsumPairing {
+    let tags = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
+    let sequences : Vec<Vec<Vec<i8>>> = [rs.0, rs.1, rs.2, rs.3].iter().zip(&tags).map(|(&r, &tag)| generate_sequences_for_type(r, p, seqtype, tag, compression, shape, filter)).collect();
+    let sequences_w = keep_canonical_first_sequences(sequences[0].clone(), seqtype, shape);
+    choose_pairing([&sequences_w, &sequences[1], &sequences[2], &sequences[3]], p, filter)
+}
+
+// The pairing used is recorded in the rowsum directory, so that join only reads the files of that pairing
+pub fn record_pairing(folder_path : &String, pairing : &Option<RowsumPairing>) {
+    let pairing = pairing.clone().unwrap_or(RowsumPairing::WZ);
+    fs::write(folder_path.clone() + &"/pairing", pairing.to_string()).expect("Error when writing in the file");
+}
+
+// The pairing recorded in the rowsum directory, if any
+fn recorded_pairing(folder_path : &String) -> Option<RowsumPairing> {
+    fs::read_to_string(folder_path.clone() + &"/pairing").ok()
+        .map(|pairing| str_to_rowsum_pairing(&pairing.trim().to_string()).expect("Invalid pairing recorded"))
+}
+
+// Names of the .pair files written for a pairing
+fn pairing_filenames(pairing : &RowsumPairing) -> Vec<String> {
+    let tags = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
+    [1, 2].iter().map(|&pair| {
+        let (i, j) = get_indices(Some(pairing.clone()), pair).expect("Concrete pairing");
+        "pair_".to_string() + &tags[i].to_string() + &tags[j].to_string() + &".pair"
+    }).collect()
+}
+
 pub fn get_indices(pairing: Option<RowsumPairing>, pair: u8) -> Option<(usize, usize)> {
     match (pairing, pair) {
         (Some(RowsumPairing::WX), 1) => Some((0, 1)),     // WX
@@ -262,7 +371,7 @@ pub fn get_indices(pairing: Option<RowsumPairing>, pair: u8) -> Option<(usize, u
     }
 }
 
-pub fn write_pair_single(seqtype : SequenceType, p: usize, match_option : MatchOption, pairing: Option<RowsumPairing>, pair: u8) {
+pub fn write_pair_single(seqtype : SequenceType, p: usize, match_option : MatchOption, pairing: Option<RowsumPairing>, pair: u8, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) {
     // This function is identical to write_pairs(), except for the purpose of running pairs individually on separate processors
     // `pair` should be either a 1 or a 2, which decides whether to look at the first or second pair given by the chosen pairing
 
@@ -276,19 +385,28 @@ pub fn write_pair_single(seqtype : SequenceType, p: usize, match_option : MatchO
     let folder = seqtype.to_string();
 
     for rs in rowsums {
-        write_pair_single_rowsum(folder.clone(), rs, p, match_option, pairing.clone(), pair);
+        write_pair_single_rowsum(folder.clone(), rs, p, match_option, pairing.clone(), pair, filter, compression, shape);
     }
 
 }
 
-pub fn write_pair_single_rowsum(folder : String, rs : (isize, isize, isize, isize), p : usize, match_option : MatchOption, pairing: Option<RowsumPairing>, pair: u8) {
+pub fn write_pair_single_rowsum(folder : String, rs : (isize, isize, isize, isize), p : usize, match_option : MatchOption, pairing: Option<RowsumPairing>, pair: u8, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) {
     let rowsums = vec![rs.0,rs.1,rs.2,rs.3];
     let tags : Vec<SequenceTag> = vec![SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
 
-    let folder_path = "results/pairs/".to_string()+ &folder + &"/find_" + &p.to_string() + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
+    let folder_path = pairs_folder(&folder, p, shape) + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
     fs::create_dir_all(&folder_path).expect("Error when creating the dir");     // This is safe to do concurrently across multiple processes according to the documentation
 
     let seqtype = str_to_seqtype(&folder);
+    let sequence_filter = pair_filter(p, match_option, filter);
+
+    // Both pairs are generated by separate processes, which read the pairing chosen by create.
+    // Without it, they make the same choice of pairing from the same sequences
+    let pairing = match pairing {
+        Some(RowsumPairing::AUTO) => Some(recorded_pairing(&folder_path).unwrap_or_else(|| choose_rowsum_pairing(seqtype, rs, p, sequence_filter, compression, shape))),
+        _ => pairing
+    };
+    record_pairing(&folder_path, &pairing);
 
     let sequences_0: Vec<Vec<i8>>;
     let sequences_1: Vec<Vec<i8>>;
@@ -303,24 +421,13 @@ pub fn write_pair_single_rowsum(folder : String, rs : (isize, isize, isize, isiz
     }
 
     let now = Instant::now();
-    match seqtype {
-        SequenceType::Williamson => {
-            sequences_0 = generate_sequences_with_rowsum(rowsums[pair_indices.0], p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_0.len(), rowsums[pair_indices.0]);
-            sequences_1 = generate_sequences_with_rowsum(rowsums[pair_indices.1], p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_1.len(), rowsums[pair_indices.1]);
-        },
-        _ => {
-            sequences_0 = generate_sequences_with_rowsum(rowsums[pair_indices.0], p);
-            println!("Found {} sequences with rowsum {}", sequences_0.len(), rowsums[pair_indices.0]);
-            sequences_1 = generate_sequences_with_rowsum(rowsums[pair_indices.1], p);
-            println!("Found {} sequences with rowsum {}", sequences_1.len(), rowsums[pair_indices.1]);
-
-        }
-    }
+    sequences_0 = generate_sequences_for_type(rowsums[pair_indices.0], p, seqtype, tags[pair_indices.0], compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_0.len(), rowsums[pair_indices.0]);
+    sequences_1 = generate_sequences_for_type(rowsums[pair_indices.1], p, seqtype, tags[pair_indices.1], compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_1.len(), rowsums[pair_indices.1]);
     
-    write_sequences(&sequences_0, &tags[pair_indices.0], &folder_path);
-    write_sequences(&sequences_1, &tags[pair_indices.1], &folder_path);
+    // The first sequence W is restricted to canonical representatives, which doesn't lose any quadruple up to equivalence
+    let sequences_0 = if pair_indices.0 == 0 {keep_canonical_first_sequences(sequences_0, seqtype, shape)} else {sequences_0};
 
     let elapsed_time = now.elapsed().as_secs();
     println!("Generating all sequences with rowsums {}, {} took {elapsed_time} seconds", rowsums[pair_indices.0], rowsums[pair_indices.1]);
@@ -333,21 +440,37 @@ pub fn write_pair_single_rowsum(folder : String, rs : (isize, isize, isize, isiz
 
 
     let now = Instant::now();
-    let counter : u64 = write_seq_pairs((&sequences_0, &sequences_1), (&tags[pair_indices.0], &tags[pair_indices.1]), str_to_seqtype(&folder), rs, p, &folder_path, side, match_option);
+    let counter : u64 = write_seq_pairs((&sequences_0, &sequences_1), (&tags[pair_indices.0], &tags[pair_indices.1]), str_to_seqtype(&folder), rs, p, &folder_path, side, match_option, filter);
     let elapsed_time = now.elapsed().as_secs_f32();
     println!("Generated {} total pairs", counter);
     println!("Total time to generate .pair files: {:.2} seconds\n", elapsed_time);    
 }
 
-pub fn create_rowsum_dirs(folder : String, p : usize, rs : (isize, isize, isize, isize), pairing: Option<RowsumPairing>) {
-    // This creates the rowsums.quad file as well as the rowsum_x_y_z_w directories, as well as the .pair files
+pub fn create_rowsum_dirs(folder : String, p : usize, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) {
+    // This creates the rowsum_x_y_z_w directories of all the rowsums in the rowsums.quad file, as well as the .pair files
     // For use when directories need to be known/iterated over, but have not been created yet
     // e.g., submitting SLURM jobs with dependencies
 
-    let folder_path = "results/pairs/".to_string()+ &folder + &"/find_" + &p.to_string() + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
+    for class in read_rowsums(&folder, p, shape) {
+        create_rowsum_dir(&folder, p, class.rowsums, pairing.clone(), filter, compression, shape);
+    }
+}
+
+fn create_rowsum_dir(folder : &String, p : usize, rs : Quad, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) {
+    let folder_path = pairs_folder(folder, p, shape) + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
     println!("{}",folder_path);
     fs::create_dir_all(&folder_path).expect("Error when creating the dir");
 
+    // The pairing is chosen once here and recorded, both processes of pair_single then read it
+    if let Some(RowsumPairing::AUTO) = pairing {
+        let chosen = choose_rowsum_pairing(str_to_seqtype(folder), rs, p, filter, compression, shape);
+        record_pairing(&folder_path, &Some(chosen.clone()));
+        for filename in pairing_filenames(&chosen) {
+            File::create(folder_path.clone() + &"/" + &filename).expect("Invalid file ?");
+        }
+        return;
+    }
+
     let (_, indices) = sort(&rs); // we sort the rowsum in decreasing order, and we keep track of their original indices
     let tags : Vec<SequenceTag> = indices.iter().map(|i| index_to_tag(*i)).collect(); // we convert the indices to their respective tags
 
@@ -367,6 +490,7 @@ pub fn create_rowsum_dirs(folder : String, p : usize, rs : (isize, isize, isize,
             path1 = folder_path.clone() + &"/pair_" + &tags[0].to_string() + &tags[3].to_string() + ".pair";
             path2 = folder_path.clone() + &"/pair_" + &tags[1].to_string() + &tags[2].to_string() + ".pair";
         },
+        Some(RowsumPairing::AUTO) => {panic!("The pairing AUTO is chosen before")},
         None => {panic!("Missing pairing arg")}
     };
 
@@ -374,7 +498,7 @@ pub fn create_rowsum_dirs(folder : String, p : usize, rs : (isize, isize, isize,
     File::create(path2).expect("Invalid file ?");    
 }
 
-pub fn write_pairs(p : usize, seqtype : SequenceType, match_option : MatchOption, pairing: Option<RowsumPairing>) {
+pub fn write_pairs(p : usize, seqtype : SequenceType, match_option : MatchOption, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) {
     // This is the starting point of the part of the algorithm that generates the possible sequences
 
     let mut counter : u64 = 0;
@@ -388,10 +512,12 @@ pub fn write_pairs(p : usize, seqtype : SequenceType, match_option : MatchOption
     }
     println!("Generated {} different rowsums\n", rowsums.len());
 
+    // The folder is created even without any rowsum (e.g. best matrices of most lengths), so that join finds it
     let folder = seqtype.to_string();
+    fs::create_dir_all(pairs_folder(&folder, p, shape)).expect("Error when creating the dir");
     for rs in rowsums {
         println!("Generating .pair files for rowsums {:?} ...", rs);
-        counter += write_pairs_rowsum(&folder, rs, p, match_option, pairing.clone());
+        counter += write_pairs_rowsum(&folder, rs, p, match_option, pairing.clone(), filter, compression, shape);
     }
 
     let elapsed = time.elapsed().as_secs_f32();
@@ -399,16 +525,17 @@ pub fn write_pairs(p : usize, seqtype : SequenceType, match_option : MatchOption
     println!("Total time to generate .pair files: {:.2} seconds\n", elapsed);
 }
 
-pub fn write_pairs_rowsum(folder : &str, rs : (isize, isize, isize, isize), p : usize, match_option : MatchOption, pairing: Option<RowsumPairing>) -> u64 {
+pub fn write_pairs_rowsum(folder : &str, rs : (isize, isize, isize, isize), p : usize, match_option : MatchOption, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) -> u64 {
     // This function generates the sequences possible for specific rowsums and stores them
     
     let tags : Vec<SequenceTag> = vec![SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
     
-    let folder_path = "results/pairs/".to_string()+ &folder + &"/find_" + &p.to_string() + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
+    let folder_path = pairs_folder(&folder, p, shape) + &"/rowsum_" + &(rs.0).to_string() + &"_" + &(rs.1).to_string() + &"_" + &(rs.2).to_string() + &"_" + &(rs.3).to_string();
     fs::create_dir_all(&folder_path).expect("Error when creating the dir");
 
     let seqtype = str_to_seqtype(folder);
     
+    let sequence_filter = pair_filter(p, match_option, filter);
     let now = Instant::now();
     // We generate all the sequences possible for each rowsums
     let sequences_0 : Vec<Vec<i8>>;
@@ -416,54 +543,44 @@ pub fn write_pairs_rowsum(folder : &str, rs : (isize, isize, isize, isize), p :
     let sequences_2 : Vec<Vec<i8>>;
     let sequences_3 : Vec<Vec<i8>>;
     
-    match seqtype {
-        SequenceType::Williamson => {
-            sequences_0 = generate_sequences_with_rowsum(rs.0, p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_0.len(), rs.0);
-            sequences_1 = generate_sequences_with_rowsum(rs.1, p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_1.len(), rs.1);
-            sequences_2 = generate_sequences_with_rowsum(rs.2, p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_2.len(), rs.2);
-            sequences_3 = generate_sequences_with_rowsum(rs.3, p).into_iter().filter(|seq| symmetric(seq)).collect();
-            println!("Found {} sequences with rowsum {}", sequences_3.len(), rs.3);
-        },
-        _ => {
-            sequences_0 = generate_sequences_with_rowsum(rs.0, p);
-            println!("Found {} sequences with rowsum {}", sequences_0.len(), rs.0);
-            sequences_1 = generate_sequences_with_rowsum(rs.1, p);
-            println!("Found {} sequences with rowsum {}", sequences_1.len(), rs.1);
-            sequences_2 = generate_sequences_with_rowsum(rs.2, p);
-            println!("Found {} sequences with rowsum {}", sequences_2.len(), rs.2);
-            sequences_3 = generate_sequences_with_rowsum(rs.3, p);
-            println!("Found {} sequences with rowsum {}", sequences_3.len(), rs.3);
-        }
-    }
-    
+    sequences_0 = generate_sequences_for_type(rs.0, p, seqtype, SequenceTag::W, compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_0.len(), rs.0);
+    sequences_1 = generate_sequences_for_type(rs.1, p, seqtype, SequenceTag::X, compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_1.len(), rs.1);
+    sequences_2 = generate_sequences_for_type(rs.2, p, seqtype, SequenceTag::Y, compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_2.len(), rs.2);
+    sequences_3 = generate_sequences_for_type(rs.3, p, seqtype, SequenceTag::Z, compression, shape, sequence_filter);
+    println!("Found {} sequences with rowsum {}", sequences_3.len(), rs.3);
+
+
+    // The first sequence W is restricted to canonical representatives, which doesn't lose any quadruple up to equivalence
+    let sequences_0 = keep_canonical_first_sequences(sequences_0, seqtype, shape);
 
-    write_sequences(&sequences_0, &tags[0], &folder_path);
-    write_sequences(&sequences_1, &tags[1], &folder_path);
-    write_sequences(&sequences_2, &tags[2], &folder_path);
-    write_sequences(&sequences_3, &tags[3], &folder_path);
-    
     let elapsed_time = now.elapsed().as_secs_f32();
     println!("Generating all sequences with rowsums {:?} took {:.2} seconds", rs, elapsed_time);
 
+    let pairing = match pairing {
+        Some(RowsumPairing::AUTO) => Some(choose_pairing([&sequences_0, &sequences_1, &sequences_2, &sequences_3], p, sequence_filter)),
+        _ => pairing
+    };
+    record_pairing(&folder_path, &pairing);
+
     let mut counter : u64 = 0;
     let now = Instant::now();
 
     // Uses sequences to generate .pair files based on chosen pairing (default pairing is XW)
     match pairing {
         Some(RowsumPairing::WX) => {
-            counter += write_seq_pairs((&sequences_0, &sequences_1), (&tags[0], &tags[1]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option);
-            counter += write_seq_pairs((&sequences_2, &sequences_3), (&tags[2], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option);
+            counter += write_seq_pairs((&sequences_0, &sequences_1), (&tags[0], &tags[1]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option, filter);
+            counter += write_seq_pairs((&sequences_2, &sequences_3), (&tags[2], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option, filter);
         },
         Some(RowsumPairing::WY) => {
-            counter += write_seq_pairs((&sequences_0, &sequences_2), (&tags[0], &tags[2]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option);
-            counter += write_seq_pairs((&sequences_1, &sequences_3), (&tags[1], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option);
+            counter += write_seq_pairs((&sequences_0, &sequences_2), (&tags[0], &tags[2]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option, filter);
+            counter += write_seq_pairs((&sequences_1, &sequences_3), (&tags[1], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option, filter);
         },
-        Some(RowsumPairing::WZ) | None => {
-            counter += write_seq_pairs((&sequences_0, &sequences_3), (&tags[0], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option);
-            counter += write_seq_pairs((&sequences_1, &sequences_2), (&tags[1], &tags[2]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option);
+        Some(RowsumPairing::WZ) | Some(RowsumPairing::AUTO) | None => {
+            counter += write_seq_pairs((&sequences_0, &sequences_3), (&tags[0], &tags[3]), seqtype, rs, p, &folder_path, EquationSide::LEFT, match_option, filter);
+            counter += write_seq_pairs((&sequences_1, &sequences_2), (&tags[1], &tags[2]), seqtype, rs, p, &folder_path, EquationSide::RIGHT, match_option, filter);
         }
     };
     
@@ -472,22 +589,199 @@ pub fn write_pairs_rowsum(folder : &str, rs : (isize, isize, isize, isize), p :
     counter
 }
 
-pub fn symmetric(seq : &Vec<i8>) -> bool {
-    // tests if the sequence is symmetric
-    let n = seq.len();
+// * Dry run
+// Projects the disk space and time taken by the pairs stage without writing any file. The pairs passing the spectral
+// filter are only counted, and the lines of a sample of them are computed to measure the proportion of pairs kept,
+// the average length of a line and the time taken to compute it. The sample is spread evenly over all the couples,
+// as the proportion of pairs kept depends on the first sequence
+
+const DRY_RUN_SAMPLE : u64 = 1000;
+
+#[derive(Clone, Copy, Default)]
+pub struct PairsEstimate {
+    pub sequences : u64,            // sequences generated
+    pub surviving : u64,            // sequences satisfying the single-sequence PSD bound
+    pub pairs : u64,                // projected number of lines in the .pair files
+    pub bytes : u64,                // projected size of the .pair files
+    pub largest_pair_file : u64,    // projected size of the largest .pair file, sort needs as much temporary space
+    pub seconds : f64               // projected time to generate the files
+}
+
+impl PairsEstimate {
 
-    for t in 1..=(n/2) { // Trying half the values is sufficient
-        if seq[t] != seq[n-t] {
-            return false;
+    pub fn add(&mut self, other : &PairsEstimate) {
+        self.sequences += other.sequences;
+        self.surviving += other.surviving;
+        self.pairs += other.pairs;
+        self.bytes += other.bytes;
+        self.largest_pair_file = self.largest_pair_file.max(other.largest_pair_file);
+        self.seconds += other.seconds;
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("{} sequences ({} after the DFT filter), {} pairs, {} on disk ({} at most while sorting) and {:.2} seconds",
+            self.sequences, self.surviving, self.pairs, format_bytes(self.bytes), format_bytes(self.bytes + 2 * self.largest_pair_file), self.seconds)
+    }
+
+}
+
+pub fn format_bytes(bytes : u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < units.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    format!("{:.2} {}", size, units[unit])
+}
+
+pub fn estimate_pairs(p : usize, seqtype : SequenceType, match_option : MatchOption, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) -> PairsEstimate {
+    // Counterpart of write_pairs that only projects the size of the files
+
+    let mut total = PairsEstimate::default();
+
+    println!("Generating rowsum decompositions for length {} ...", p);
+    let rowsums = generate_rowsums(p, seqtype);
+    println!("Generated {} different rowsums\n", rowsums.len());
+
+    for rs in rowsums {
+        println!("Estimating .pair files for rowsums {:?} ...", rs);
+        let estimate = estimate_pairs_rowsum(seqtype, rs, p, match_option, pairing.clone(), filter, compression, shape);
+        println!("rowsum_{}_{}_{}_{}: {}\n", rs.0, rs.1, rs.2, rs.3, estimate.to_string());
+        total.add(&estimate);
+    }
+
+    println!("Projected total for length {}: {}", p, total.to_string());
+    total
+}
+
+pub fn estimate_pairs_rowsum(seqtype : SequenceType, rs : (isize, isize, isize, isize), p : usize, match_option : MatchOption, pairing: Option<RowsumPairing>, filter : SpectralFilter, compression : Option<usize>, shape : Option<SequenceShape>) -> PairsEstimate {
+    // Counterpart of write_pairs_rowsum that only projects the size of the files
+
+    let sequence_filter = pair_filter(p, match_option, filter);
+    let now = Instant::now();
+    let sequences = [
+        keep_canonical_first_sequences(generate_sequences_for_type(rs.0, p, seqtype, SequenceTag::W, compression, shape, sequence_filter), seqtype, shape),
+        generate_sequences_for_type(rs.1, p, seqtype, SequenceTag::X, compression, shape, sequence_filter),
+        generate_sequences_for_type(rs.2, p, seqtype, SequenceTag::Y, compression, shape, sequence_filter),
+        generate_sequences_for_type(rs.3, p, seqtype, SequenceTag::Z, compression, shape, sequence_filter)
+    ];
+
+    let mut estimate = PairsEstimate::default();
+    estimate.sequences = sequences.iter().map(|seqs| seqs.len() as u64).sum();
+    estimate.seconds += now.elapsed().as_secs_f64();
+
+    let pairing = match pairing {
+        Some(RowsumPairing::AUTO) => choose_pairing([&sequences[0], &sequences[1], &sequences[2], &sequences[3]], p, sequence_filter),
+        _ => pairing.unwrap_or(RowsumPairing::WZ)
+    };
+
+    let tags = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
+    for (pair, side) in [(1, EquationSide::LEFT), (2, EquationSide::RIGHT)] {
+        let (i, j) = get_indices(Some(pairing.clone()), pair).expect("Concrete pairing");
+        let pair_estimate = estimate_seq_pairs((&sequences[i], &sequences[j]), (&tags[i], &tags[j]), seqtype, p, side, match_option, filter);
+        estimate.surviving += pair_estimate.surviving;
+        estimate.pairs += pair_estimate.pairs;
+        estimate.bytes += pair_estimate.bytes;
+        estimate.largest_pair_file = estimate.largest_pair_file.max(pair_estimate.largest_pair_file);
+        estimate.seconds += pair_estimate.seconds;
+    }
+
+    estimate
+}
+
+pub fn estimate_seq_pairs(sequences : (&Vec<Vec<i8>>, &Vec<Vec<i8>>), tags : (&SequenceTag, &SequenceTag), seqtype : SequenceType, p : usize, side : EquationSide, match_option : MatchOption, filter : SpectralFilter) -> PairsEstimate {
+    // Counterpart of write_seq_pairs that counts the pairs instead of writing them
+
+    let now = Instant::now();
+    let pruning = SpectralPruning::new(sequences.0, sequences.1, 4.*p as f64, pair_filter(p, match_option, filter));
+    let removed = pruning.removed_sequences();
+
+    let mut couples : u64 = 0;
+    let mut sample : u64 = 0;
+    let mut sampled_lines : u64 = 0;
+    let mut sampled_bytes : u64 = 0;
+    let mut sample_time : f64 = 0.;
+    let mut min_half_int_difference = (1.0, 1.0);
+
+    // The couples at every stride-th position of the product of the two lists are sampled (all of them when there are
+    // fewer than DRY_RUN_SAMPLE), and the first couple passing the filter in any case so that the sample isn't empty
+    let stride = ((sequences.0.len() * sequences.1.len()) as u64 / DRY_RUN_SAMPLE).max(1);
+    for pair in pruning.couples() {
+        couples += 1;
+        let position = (pair.seq_enum1.0 * sequences.1.len() + pair.seq_enum2.0) as u64;
+        if position % stride == 0 || sample == 0 {
+            sample += 1;
+            let line_time = Instant::now();
+            if let Some(line) = pair_line(&pair, tags, seqtype, p, side, match_option, &mut min_half_int_difference) {
+                sampled_lines += 1;
+                sampled_bytes += line.len() as u64;
+            }
+            sample_time += line_time.elapsed().as_secs_f64();
         }
     }
-    true
+
+    // The sample is extrapolated to all the couples passing the filter
+    let (pairs, bytes, line_time) = if sample == 0 {
+        (0, 0, 0.)
+    } else {
+        let pairs = (couples as f64 * sampled_lines as f64 / sample as f64).round() as u64;
+        let bytes = if sampled_lines == 0 {0} else {(pairs as f64 * sampled_bytes as f64 / sampled_lines as f64).round() as u64};
+        (pairs, bytes, sample_time / sample as f64)
+    };
+    let seconds = now.elapsed().as_secs_f64() - sample_time + couples as f64 * line_time;
+
+    println!("Pairing {}{}: {} pairs out of {} couples, {}", tags.0.to_string(), tags.1.to_string(), pairs, sequences.0.len() * sequences.1.len(), pruning.to_string());
+
+    PairsEstimate {
+        sequences : (sequences.0.len() + sequences.1.len()) as u64,
+        surviving : (sequences.0.len() - removed.0 + sequences.1.len() - removed.1) as u64,
+        pairs,
+        bytes,
+        largest_pair_file : bytes,
+        seconds
+    }
 }
 
+// Generates the sequences of length p with the given rowsum, only decompressing the admissible m-compressions if a compression factor m is given
+// The compressed sequences are pruned with the same bound and slack as the pairs
+pub fn generate_sequences(rowsum : isize, p : usize, compression : Option<usize>, filter : SpectralFilter) -> Vec<Vec<i8>> {
+    match compression {
+        Some(m) => generate_sequences_with_rowsum_compressed(rowsum, p, m, 4. * p as f64 + filter.slack),
+        None => generate_sequences_with_rowsum(rowsum, p)
+    }
+}
 
+// Generates the sequences of length p with the given rowsum that can appear as the sequence tag of a quadruple of the given type,
+// and that have the given shape. The sequences of a shape are enumerated from their prefixes, unless they are decompressed
+fn generate_sequences_for_type(rowsum : isize, p : usize, seqtype : SequenceType, tag : SequenceTag, compression : Option<usize>, shape : Option<SequenceShape>, filter : SpectralFilter) -> Vec<Vec<i8>> {
+    let shapes : Vec<SequenceShape> = seqtype.shape(&tag).into_iter().chain(shape).collect();
+    let sequences = match (shapes.first(), compression) {
+        (Some(&first), None) => generate_sequences_with_shape(rowsum, p, first),
+        _ => generate_sequences(rowsum, p, compression, filter)
+    };
+    if shapes.is_empty() {
+        return sequences;
+    }
+    sequences.into_iter().filter(|seq| shapes.iter().all(|shape| shape.contains(seq))).collect()
+}
+
+fn keep_canonical_first_sequences(sequences : Vec<Vec<i8>>, seqtype : SequenceType, shape : Option<SequenceShape>) -> Vec<Vec<i8>> {
+    let total = sequences.len();
+    // The equivalences can change the shape of the sequences when the type doesn't impose it, W is then kept whole
+    if let Some(shape) = shape.filter(|&shape| [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].iter().any(|tag| seqtype.shape(tag) != Some(shape))) {
+        println!("Kept the {} sequences for W, the equivalences don't preserve the shape {}", total, shape.to_string());
+        return sequences;
+    }
+    let canonical = canonical_first_sequences(sequences, seqtype);
+    println!("Kept {} of the {} sequences for W, the others are equivalent to them", canonical.len(), total);
+    canonical
+}
 
 
-pub fn join_pairs(p : usize, seqtype : SequenceType) -> Vec<QuadSeq>{
+
+pub fn join_pairs(p : usize, seqtype : SequenceType, shape : Option<SequenceShape>) -> Vec<QuadSeq>{
     // This is the starting point of the part of the algorithm that goes through the sorted files and finds valid QTS
     let time = Instant::now();
 
@@ -495,29 +789,39 @@ pub fn join_pairs(p : usize, seqtype : SequenceType) -> Vec<QuadSeq>{
 
     let folder = seqtype.to_string();
 
-    let find_i = fs::read_dir("./results/pairs/".to_string() + &folder + &"/find_".to_string() + &p.to_string()).unwrap();
+    let find_i = fs::read_dir(pairs_folder(&folder, p, shape)).unwrap();
 
     for rowsum_x_y in find_i {
         let directory = rowsum_x_y.unwrap();
 
         if directory.metadata().unwrap().is_dir() {
-            // We read the files in the directory to get back our 4 sets of sequences
-            let sequences = get_sequences_from_dir(&directory);
+            // The sequences are reconstructed from their ranks and the rowsums of the directory
+            let rowsums = get_rowsums_from_dir(&directory);
     
             let (pathnames, order) = get_order_from_dir(&directory);
     
             println!("Matching files in /{} ...", directory.file_name().into_string().expect("File error"));
-            result.append(&mut join_pairs_files(&pathnames, seqtype, &order, &sequences));
+            result.append(&mut join_pairs_files(&pathnames, seqtype, &order, rowsums, p));
         }
     }
 
     let elapsed = time.elapsed().as_secs_f32();
     println!("Matching took: {:.2} seconds.", elapsed);
 
-    debug_assert!(result.iter().all(|seq| has_sorted_rowsums(&seq)));
+    // Every match has the rowsums of its directory, one of the representatives chosen by generate_rowsums
+    debug_assert!({
+        let rowsums = generate_rowsums(p, seqtype);
+        result.iter().all(|seq| rowsums.contains(&quad_rowsums(seq)))
+    });
 
     println!("\nFound {} {} after matching", result.len(), seqtype.to_string());
 
+    reduce_matches(&result, seqtype)
+}
+
+pub fn reduce_matches(result : &Vec<QuadSeq>, seqtype : SequenceType) -> Vec<QuadSeq> {
+    // Reduces the quadruples found by a search to equivalence, their rowsums must be among the representatives given by
+    // generate_rowsums(p, seqtype), which aren't necessarily sorted
     let time = Instant::now();
     let filtered : Vec<QuadSeq>;
     match seqtype {
@@ -529,6 +833,10 @@ pub fn join_pairs(p : usize, seqtype : SequenceType) -> Vec<QuadSeq>{
             filtered = result.iter().map(|seq| sn_ss_canonical(seq)).unique().collect();
             println!("Filtered with the SN (single negate) and SS (single swap) operations; now filtering {} sequences up to Williamson-type equivalence ...", filtered.len());
         },
+        SequenceType::Good | SequenceType::Best => {
+            filtered = result.iter().cloned().unique().collect();
+            println!("Now filtering {} sequences up to {} equivalence ...", filtered.len(), seqtype.to_string());
+        },
         _ => {panic!("Not implemented yet")}
     }
 
@@ -545,53 +853,17 @@ pub fn join_pairs(p : usize, seqtype : SequenceType) -> Vec<QuadSeq>{
     println!("Reducing to equivalence took {:.2} seconds.\n", elapsed);
 
     reduced
-
-}
-
-
-pub fn get_sequences_from_dir(directory : &DirEntry) -> (Vec<Vec<i8>>,Vec<Vec<i8>>,Vec<Vec<i8>>,Vec<Vec<i8>>) {
-    // This function reads the files from a directory and returns the sequences that are in the files ending in .seq
-
-    let mut sequence_x = vec![];
-    let mut sequence_y = vec![];
-    let mut sequence_z = vec![];
-    let mut sequence_w = vec![];
-
-    for file in fs::read_dir(directory.path().display().to_string()).unwrap() {
-        let f = file.unwrap();
-        let pathname = f.path().display().to_string();
-        if pathname.ends_with(".seq") {
-            // We loop through files with extension .seq
-            // eprintln!("Name: {}", pathname);
-
-            let filename = pathname.split("/").last().expect("No last element ???");
-            // eprintln!("Name: {}", filename);
-            match filename {
-                "seq_W.seq" => {sequence_w = file_to_sequences(&pathname)}
-                "seq_X.seq" => {sequence_x = file_to_sequences(&pathname)}
-                "seq_Y.seq" => {sequence_y = file_to_sequences(&pathname)}
-                "seq_Z.seq" => {sequence_z = file_to_sequences(&pathname)}
-                _ => {panic!("Unexpected file ending in .seq : {filename}")}
-            }
-        }
-    }
-
-    (sequence_w, sequence_x, sequence_y, sequence_z)
 }
 
 
-pub fn file_to_sequences(filename : &String) -> Vec<Vec<i8>> {
-    // This function reads a file storing a set of sequences and returns them
+pub fn get_rowsums_from_dir(directory : &DirEntry) -> Quad {
+    // This function reads the rowsums from the name of a directory rowsum_a_b_c_d
 
-    let mut seq = vec![];
+    let name = directory.file_name().into_string().expect("File error");
+    let rowsums : Vec<isize> = name.strip_prefix("rowsum_").expect("Unexpected directory name").split("_").map(|r| r.parse().expect("Expected a number !")).collect();
+    assert!(rowsums.len() == 4, "Unexpected directory name : {name}");
 
-    for line in read_lines(filename).expect("Error when reading file") {
-        if let Ok(s) = line {
-            seq.push(string_to_sequence(&s));
-        }
-    }
-
-    seq
+    (rowsums[0], rowsums[1], rowsums[2], rowsums[3])
 }
 
 pub fn string_to_sequence(s : &String) -> Vec<i8>{
@@ -617,9 +889,16 @@ pub fn get_order_from_dir(directory : &DirEntry) -> ((String, String), (Sequence
     let mut filenames = vec![];
     let mut pathnames = vec![];
 
+    // Only the files of the recorded pairing are read, if there is one
+    let recorded = recorded_pairing(&directory.path().display().to_string()).map(|pairing| pairing_filenames(&pairing));
+
     for file in fs::read_dir(directory.path().display().to_string()).unwrap() {
         let f = file.unwrap();
         let pathname = f.path().display().to_string();
+        let filename = pathname.split("/").last().expect("No last element ???");
+        if recorded.as_ref().is_some_and(|names| !names.iter().any(|name| name.clone() + ".sorted" == filename)) {
+            continue;
+        }
         if pathname.ends_with(".pair.sorted") {
             // We loop through files with extension .pair
             // eprintln!("Name: {}", pathname);
@@ -666,11 +945,37 @@ pub fn get_tag_from_filename(filename : &str) -> (SequenceTag, SequenceTag) {
 
 
 
-pub fn join_pairs_files(filenames : &(String, String), seqtype : SequenceType, order : &(SequenceTag, SequenceTag, SequenceTag, SequenceTag), sequences : &(Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>)) -> Vec<QuadSeq> {
+pub fn join_pairs_files(filenames : &(String, String), seqtype : SequenceType, order : &(SequenceTag, SequenceTag, SequenceTag, SequenceTag), rowsums : Quad, p : usize) -> Vec<QuadSeq> {
     // This function reads two sorted files of sequences and uses the order to determine what comparisons should be made, and returns the valid QTS
 
     let mut result = vec![];
 
+    let condition: Box<dyn Fn(&QuadSeq) -> bool> = match seqtype {
+        SequenceType::QuaternionType => Box::new(|quad| quad.to_qs().is_perfect()),
+        SequenceType::WilliamsonType => Box::new(|quad| quad.verify_wts()),
+        SequenceType::Williamson => Box::new(|quad| quad.verify_ws()),
+        SequenceType::Good => Box::new(|quad| quad.verify_gm()),
+        SequenceType::Best => Box::new(|quad| quad.verify_bm()),
+        _ => Box::new(|_| false)
+    };
+
+    merge_sorted_pairs(filenames, |indices| {
+        // test if the sequence is of type seqtype, add them to the result files if it is
+        let sequences = get_sequences(rowsums, p, order, indices);
+        let mut quad_seq = QuadSeq::new(p);
+        quad_seq.set_all_values((&sequences.0, &sequences.1, &sequences.2, &sequences.3));
+
+        if condition(&quad_seq) {
+            result.push(quad_seq);
+        }
+    });
+
+    result
+}
+
+pub fn merge_sorted_pairs(filenames : &(String, String), mut on_match : impl FnMut(&(usize, usize, usize, usize))) -> u64 {
+    // This function goes through two sorted files at once, and calls on_match with the ranks of the four sequences of every two lines with the same values
+
     let (file12, file34) = filenames;
 
     let mut lines12 = read_lines(file12).expect("Invalid file somehow ?");
@@ -723,21 +1028,7 @@ pub fn join_pairs_files(filenames : &(String, String), seqtype : SequenceType, o
             // Loop through the possible matches
             for ((i1, i2),(i3, i4)) in iproduct!(possible_matching_12, possible_matching_34) {
                 matches+=1;
-                let indices = (i1, i2, i3, i4);
-                // test if the sequence is of type seqtype, add them to the result files if it is
-                let sequences = get_sequences(sequences, order, &indices);
-                let mut quad_seq = QuadSeq::new(sequences.0.len());
-                quad_seq.set_all_values(sequences);
-                
-                let condition: Box<dyn Fn(&QuadSeq) -> bool> = match seqtype {
-                    SequenceType::QuaternionType => Box::new(|quad| quad.to_qs().is_perfect()),
-                    SequenceType::WilliamsonType => Box::new(|quad| quad.verify_wts()),
-                    SequenceType::Williamson => Box::new(|quad| quad.verify_ws()),
-                    _ => Box::new(|_| false)
-                };
-                if condition(&quad_seq) {
-                    result.push(quad_seq);
-                }
+                on_match(&(i1, i2, i3, i4));
             }
         }
         else if seq12 < seq34 {
@@ -751,7 +1042,7 @@ pub fn join_pairs_files(filenames : &(String, String), seqtype : SequenceType, o
 
     println!("Matches found in {:?} and {:?}: {}\n", file12.split("/").last().expect("Filename read error"), file34.split("/").last().expect("Filename read error"), matches);
 
-    result
+    matches
 }
 
 
@@ -771,34 +1062,36 @@ pub fn get_line_from(seq : &Option<Result<String, Error>>) -> (String, (usize, u
 }
 
 
-pub fn get_sequences<'a>(sequences : &'a (Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>), order : &'a (SequenceTag, SequenceTag, SequenceTag, SequenceTag), indices : &'a (usize, usize, usize, usize)) -> (&'a Vec<i8>, &'a Vec<i8>, &'a Vec<i8>, &'a Vec<i8>) {
-    // This function returns the sequences corresponding to the indices in a specific order
+pub fn get_sequences(rowsums : Quad, p : usize, order : &(SequenceTag, SequenceTag, SequenceTag, SequenceTag), ranks : &(usize, usize, usize, usize)) -> (Vec<i8>, Vec<i8>, Vec<i8>, Vec<i8>) {
+    // This function reconstructs the sequences corresponding to the ranks in a specific order
 
-    let seqw = get_sequence_aux(sequences, order, indices, SequenceTag::W);
-    let seqx = get_sequence_aux(sequences, order, indices, SequenceTag::X);
-    let seqy = get_sequence_aux(sequences, order, indices, SequenceTag::Y);
-    let seqz = get_sequence_aux(sequences, order, indices, SequenceTag::Z);
+    let seqw = get_sequence_aux(rowsums, p, order, ranks, SequenceTag::W);
+    let seqx = get_sequence_aux(rowsums, p, order, ranks, SequenceTag::X);
+    let seqy = get_sequence_aux(rowsums, p, order, ranks, SequenceTag::Y);
+    let seqz = get_sequence_aux(rowsums, p, order, ranks, SequenceTag::Z);
 
     (seqw, seqx, seqy, seqz)
 }
 
 
-fn get_sequence_aux<'a>(sequences : &'a (Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>, Vec<Vec<i8>>), order : &'a (SequenceTag, SequenceTag, SequenceTag, SequenceTag), indices : &(usize, usize, usize, usize), tag : SequenceTag)  -> &'a Vec<i8>{
+fn get_sequence_aux(rowsums : Quad, p : usize, order : &(SequenceTag, SequenceTag, SequenceTag, SequenceTag), ranks : &(usize, usize, usize, usize), tag : SequenceTag) -> Vec<i8> {
 
     let (tag1, tag2, tag3 ,tag4) = order;
 
-    let index = match tag {
-        _ if tag == *tag1 => {indices.0}
-        _ if tag == *tag2 => {indices.1}
-        _ if tag == *tag3 => {indices.2}
-        _ if tag == *tag4 => {indices.3}
+    let rank = match tag {
+        _ if tag == *tag1 => {ranks.0}
+        _ if tag == *tag2 => {ranks.1}
+        _ if tag == *tag3 => {ranks.2}
+        _ if tag == *tag4 => {ranks.3}
         _ => {panic!("Problem with order !")}
     };
 
-    match tag {
-        SequenceTag::W => {&sequences.0[index]},
-        SequenceTag::X => {&sequences.1[index]},
-        SequenceTag::Y => {&sequences.2[index]},
-        SequenceTag::Z => {&sequences.3[index]}
-    }
+    let rowsum = match tag {
+        SequenceTag::W => {rowsums.0},
+        SequenceTag::X => {rowsums.1},
+        SequenceTag::Y => {rowsums.2},
+        SequenceTag::Z => {rowsums.3}
+    };
+
+    unrank_sequence(rank, p, rowsum)
 }
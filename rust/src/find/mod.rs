@@ -1,6 +1,10 @@
 pub mod find_williamson;
 pub mod find_optim;
 pub mod find_naive;
+pub mod find_backtrack;
 pub mod find_with_rowsum;
 pub mod find_unique;
 pub mod find_write;
+pub mod find_exact;
+pub mod find_aperiodic;
+pub mod find_complex;
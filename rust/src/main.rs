@@ -7,20 +7,28 @@ use std::io::{self, BufRead, Write};
 use std::path::Path;
 
 use sequences::matrices::QHM;
+use sequences::mum::{MUM, HMUO};
+use sequences::product::write_products;
+use sequences::constructions::write_constructions;
+use sequences::fourier::SpectralFilter;
+use sequences::sat::SolverBackend;
 use time::*;
 
 mod sequences;
 mod tests;
 mod find;
-use crate::find::find_write::{create_rowsum_dirs, write_pair_single_rowsum, write_pairs, write_pairs_rowsum, write_rowsums, MatchOption};
+use crate::find::find_complex::{complex_folder, join_complex_pairs, write_complex_pairs, write_complex_results};
+use crate::find::find_aperiodic::{aperiodic_folder, join_aperiodic_pairs, write_aperiodic_pairs, write_aperiodic_results, APERIODIC_OVERSAMPLING};
+use crate::find::find_write::{create_rowsum_dirs, estimate_pairs, pairs_folder, write_pair_single_rowsum, write_pairs, write_pairs_rowsum, write_rowsums, MatchOption};
 use crate::find::*;
 use crate::find::find_unique::{/*reduce_to_equivalence,*/reduce_to_canonical_reps};
-use crate::sequences::equivalence::generate_equivalent_quad_seqs;
+use crate::sequences::equivalence::{generate_equivalent_quad_seqs, reduce_odd_perfect};
+use crate::sequences::aperiodic::AperiodicFamily;
 use crate::sequences::{williamson::*, sequence::*, symmetries::*, equivalence::{negated, half_shift}};
-use sequences::matrix_equivalence::hadamard_equivalence_from_file;
+use sequences::matrix_equivalence::{hadamard_equivalence_from_file, reduce_to_qhm_equivalence};
 
 fn find_pqs(symmetry : Option<Symmetry>){
-    for i in 1..18{
+    for i in 1..22{
         find_pqs_of_type(i, &symmetry);
     }
 }
@@ -35,13 +43,54 @@ fn find_pqs_of_type(i : usize, symmetry : &Option<Symmetry>){
     }
 
     let now = Instant::now();
-    let count = find_optim::find(i, symmetry.clone());
+    let count = find_backtrack::find(i, symmetry.clone(), &QPLUS);
     let elapsed_time = now.elapsed().as_seconds_f32();
 
     eprintln!("For n = {i}, the function took: {elapsed_time} seconds and found {count} sequences");
 }
 
 
+// Finds the odd-perfect sequences of length i over QPLUS, or Q24, and writes them up to equivalence
+// to results/pairs/opqs/find_i (or opqs24, and find_i_<symmetry> with a symmetry) in the same format as result.seq and result.qseq
+fn find_odd_pqs_of_type(i : usize, symmetry : &Option<Symmetry>, q24 : bool){
+
+    // As for the perfect sequences, the symmetries II to IV need an even length
+    match symmetry {
+        None | Some(Symmetry::I) => {}
+        _ => {
+            if i % 2 == 1 {
+                eprintln!("The symmetries II to IV need an even length, skipping n = {i}");
+                return
+            }
+        },
+    }
+
+    let alphabet : &Vec<cgmath::Quaternion<f32>> = if q24 {&Q24} else {&QPLUS};
+
+    let now = Instant::now();
+    let sequences = find_backtrack::find_odd_sequences(i, symmetry.clone(), alphabet);
+    let found = sequences.len();
+    let sequences = reduce_odd_perfect(&sequences, alphabet);
+    let elapsed_time = now.elapsed().as_seconds_f32();
+
+    eprintln!("For n = {i}, the function took: {elapsed_time} seconds and found {found} odd-perfect sequences, {} up to equivalence", sequences.len());
+
+    let suffix = symmetry.clone().map_or("".to_string(), |symmetry| "_".to_string() + &SequenceShape::Halves(symmetry).to_string());
+    let folder = "./results/pairs/".to_string() + if q24 {"opqs24"} else {"opqs"} + &"/find_".to_string() + &i.to_string() + &suffix;
+    std::fs::create_dir_all(&folder).expect("Error when creating the dir");
+
+    let mut f_seq = File::create(folder.clone() + &"/result.seq").expect("Invalid file ?");
+    let seq_res_string = sequences.iter().map(|qs| qs.to_string_raw() + &"\n").fold("".to_string(), |s, t| s + &t);
+    f_seq.write(seq_res_string.as_bytes()).expect("Error when writing in the file");
+
+    // Only the sequences over QPLUS correspond to quadruples of binary sequences
+    if !q24 {
+        let mut f_qseq = File::create(folder + &"/result.qseq").expect("Invalid file ?");
+        let qseq_res_string = sequences.iter().map(|qs| QuadSeq::from_pqs(qs).to_string() + &"\n").fold("".to_string(), |s, t| s + &t);
+        f_qseq.write(qseq_res_string.as_bytes()).expect("Error when writing in the file");
+    }
+}
+
 
 fn find_williamson(){
     for i in 1..15{
@@ -89,9 +138,41 @@ fn find_unique_williamson_type_of_size(i : usize){
     f.write(result.as_bytes()).expect("Error when writing in the file");
 }
 
-fn find_write_quad_seq(i : usize, seqtype : SequenceType){
+fn find_with_solver(p : usize, seqtype : SequenceType, backend : &SolverBackend) {
+    let result = find_with_rowsum::find(p, seqtype, backend);
+    write_solver_result(p, seqtype, &result);
+}
 
-    let mut result = find_write::join_pairs(i, seqtype);
+fn write_solver_result(p : usize, seqtype : SequenceType, result : &Vec<QuadSeq>) {
+    // Writes the quadruples found by the solver in result.seq and result.qseq, and compares them with the pair pipeline
+    let folder = find_with_rowsum::equations_folder(p, seqtype);
+    std::fs::create_dir_all(&folder).expect("Error when creating the dir");
+
+    let mut f_seq = File::create(folder.clone() + &"/result.seq").expect("Invalid file ?");
+    let mut f_qseq = File::create(folder.clone() + &"/result.qseq").expect("Invalid file ?");
+    f_seq.write(result.iter().map(|w| w.to_qs().to_string_raw() + &"\n").fold("".to_string(), |s, t| s + &t).as_bytes()).expect("Error when writing in the file");
+    f_qseq.write(result.iter().map(|w| w.to_string() + &"\n").fold("".to_string(), |s, t| s + &t).as_bytes()).expect("Error when writing in the file");
+
+    // The results are written in any case, a difference is reported with the sequences involved
+    match find_with_rowsum::compare_with_pairs(p, seqtype, result) {
+        None => println!("The pair pipeline hasn't been run for this length"),
+        Some((only_pairs, only_solver)) if only_pairs.is_empty() && only_solver.is_empty() => println!("The solver and the pair pipeline found the same sequences"),
+        Some((only_pairs, only_solver)) => {
+            eprintln!("The solver and the pair pipeline found different sequences");
+            for seq in only_pairs {
+                eprintln!("Only found by the pair pipeline: {}", seq);
+            }
+            for seq in only_solver {
+                eprintln!("Only found by the solver: {}", seq);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn find_write_quad_seq(i : usize, seqtype : SequenceType, shape : Option<SequenceShape>){
+
+    let mut result = find_write::join_pairs(i, seqtype, shape);
 
     if matches!(seqtype, SequenceType::QuaternionType) {
         // Check to see if also valid WTS
@@ -103,10 +184,10 @@ fn find_write_quad_seq(i : usize, seqtype : SequenceType){
         }
     }
    
-    let folder = seqtype.to_string();
+    let folder = pairs_folder(&seqtype.to_string(), i, shape);
     
-    let s = &("./results/pairs/".to_string() + &folder + &"/find_".to_string() + &i.to_string() + &"/result.seq");
-    let qs = &("./results/pairs/".to_string() + &folder + &"/find_".to_string() + &i.to_string() + &"/result.qseq");
+    let s = &(folder.clone() + &"/result.seq");
+    let qs = &(folder.clone() + &"/result.qseq");
     
     let path_seq = Path::new(s);
     let path_qseq = Path::new(qs);
@@ -155,8 +236,8 @@ fn find_write_quad_seq(i : usize, seqtype : SequenceType){
         println!("Reducing to equivalence took {:.2} seconds.\n", elapsed);
 
         // Write the enumeration up to QT equivalence to separate result files
-        let s = &("./results/pairs/".to_string() + &folder + &"/find_".to_string() + &i.to_string() + &"/result-qts.seq");
-        let qs = &("./results/pairs/".to_string() + &folder + &"/find_".to_string() + &i.to_string() + &"/result-qts.qseq");
+        let s = &(folder.clone() + &"/result-qts.seq");
+        let qs = &(folder.clone() + &"/result-qts.qseq");
         let path_seq = Path::new(s);
         let path_qseq = Path::new(qs);
         let mut f_seq = File::create(path_seq).expect("Invalid file ?");
@@ -175,18 +256,19 @@ where P: AsRef<Path>, { // compact code to read a file
     Ok(io::BufReader::new(file).lines())
 }
 
-fn convert_qs_to_matrices(seqtype : SequenceType, len : usize) {
+fn convert_qs_to_matrices(seqtype : SequenceType, len : usize, shape : Option<SequenceShape>) {
     let mut num_seq = 0;
     let mut num_non_commutative = 0;
 
-    println!("{}", &("./results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &len.to_string() + &"/result.seq"));
-    if let Ok(lines) = read_lines(&("./results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &len.to_string() + &"/result.seq")) {
+    let folder = pairs_folder(&seqtype.to_string(), len, shape);
+    println!("{}", &(folder.clone() + &"/result.seq"));
+    if let Ok(lines) = read_lines(&(folder.clone() + &"/result.seq")) {
         // Consumes the iterator, returns an (Optional) String
-        let s = &("./results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &len.to_string() + &"/result.qhm");
+        let s = &(folder.clone() + &"/result.qhm");
         let path = Path::new(s);
         let mut f = File::create(path).expect("Invalid file ?");
 
-        let mut result = "".to_string();
+        let mut qhms = vec![];
         for line in lines {
             if let Ok(pqs) = line {
                 num_seq += 1;
@@ -194,23 +276,118 @@ fn convert_qs_to_matrices(seqtype : SequenceType, len : usize) {
                 let mut qhm = QHM::from_pqs(QS::from_str(&pqs));
                 qhm.dephase();
 
-                if qhm.contains_non_commuting_elements() {
-                    num_non_commutative += 1;
-                }
+                qhms.push(qhm);
+            }
+        }
 
-                result += &qhm.to_string();
-                result += &"\n";
+        // Only keep one matrix per equivalence class
+        let inequivalent = reduce_to_qhm_equivalence(&qhms);
+
+        let mut result = "".to_string();
+        for qhm in &inequivalent {
+            if qhm.contains_non_commuting_elements() {
+                num_non_commutative += 1;
             }
+
+            result += &qhm.to_string();
+            result += &"\n";
         }
         f.write(result.as_bytes()).expect("Error when writing in the file");
 
-        println!("converted {num_seq} sequences of size {len}. {num_non_commutative} contained non-commuting elements.");
+        println!("converted {num_seq} sequences of size {len} to {} inequivalent matrices. {num_non_commutative} contained non-commuting elements.", inequivalent.len());
+    }
+}
+
+// Writes the real (order 4n) or complex (order 2n) Hadamard matrices coming from the QHM found for a given length
+fn convert_qhm_to_representation(seqtype : SequenceType, len : usize, complex : bool, shape : Option<SequenceShape>) {
+    let folder = pairs_folder(&seqtype.to_string(), len, shape);
+    let lines = read_lines(&(folder.clone() + &"/result.seq")).expect(&format!("Error reading file. Make sure sequences have already been generated for this length (e.g., {folder}/result.seq should exist and not be empty)"));
+
+    // The matrices are not dephased as dephasing can spread the entries of a column over the three cosets of Q8 in Q24
+    let qhms : Vec<QHM> = lines.map(|line| QHM::from_pqs(QS::from_str(&line.expect("Error reading line")))).collect();
+    let inequivalent = reduce_to_qhm_equivalence(&qhms);
+
+    // The matrices that can't be brought to half unit entries, or whose expansion isn't Hadamard, are reported and not written
+    let (mut num_failed, mut num_invalid) = (0, 0);
+    let mut result = "".to_string();
+    for qhm in &inequivalent {
+        let converted = if complex {
+            qhm.to_complex_matrix().map(|mat| (mat.is_complex_hadamard(), mat.to_string_magma()))
+        } else {
+            qhm.to_real_matrix().map(|mat| (mat.verify(), mat.to_string_magma()))
+        };
+
+        match converted {
+            Some((true, mat)) => {
+                result += &mat;
+                result += &"\n";
+            }
+            Some((false, _)) => {
+                num_invalid += 1;
+                eprintln!("The expansion of this matrix isn't a Hadamard matrix, it isn't a QHM:\n{}", qhm.to_string());
+            }
+            None => {num_failed += 1;}
+        }
+    }
+
+    let path_out = folder + if complex {"/result-complex.mat"} else {"/result-real.mat"};
+    let mut f = File::create(&path_out).expect("Invalid file ?");
+    f.write(result.as_bytes()).expect("Error when writing in the file");
+
+    println!("converted {} inequivalent QHM of size {len} to {} matrices in {path_out}. {num_failed} could not be brought to half unit entries.", inequivalent.len(), inequivalent.len() - num_failed - num_invalid);
+    if num_invalid > 0 {
+        eprintln!("{num_invalid} matrices were not converted as their expansion isn't a Hadamard matrix");
+        std::process::exit(1);
+    }
+}
+
+// Builds the normalised MUM and HMUO of every QHM in result.qhm, checks them and writes them to result.mum
+// Every line of result.mum is a JSON object {"mum": [P_1, ..., P_n], "hmuo": U}, where a matrix is given as a list of rows of [re, im] entries
+fn write_mums(seqtype : SequenceType, len : usize, tolerance : f32, shape : Option<SequenceShape>) {
+    let folder = pairs_folder(&seqtype.to_string(), len, shape);
+    let path_in = folder.clone() + &"/result.qhm";
+    let content = std::fs::read_to_string(&path_in).expect(&format!("Error reading file. Make sure the QHM have already been generated for this length (e.g., {path_in} should exist and not be empty)"));
+
+    // The matrices are separated by empty lines
+    let qhms : Vec<QHM> = content.split("\n\n").filter(|block| !block.trim().is_empty()).map(|block| QHM::from_str(&block.to_string())).collect();
+
+    // The matrices that aren't QHM are reported and skipped
+    let (mut not_qhm, mut not_positive, mut not_complete, mut biased, mut not_unitary) = (0, 0, 0, 0, 0);
+    let mut result = "".to_string();
+    for qhm in &qhms {
+        if !qhm.verify() {
+            not_qhm += 1;
+            eprintln!("This matrix of {path_in} isn't a QHM, it is skipped:\n{}", qhm.to_string());
+            continue;
+        }
+
+        let mum = MUM::from_qhm_normalized(qhm);
+        let hmuo = HMUO::from_qhm_normalized(qhm);
+
+        if !mum.is_positive(tolerance) {not_positive += 1;}
+        if !mum.is_complete(tolerance) {not_complete += 1;}
+        if !mum.is_unbiased(tolerance) {biased += 1;}
+        if !hmuo.is_unitary_with_tolerance(tolerance) {not_unitary += 1;}
+
+        result += &("{\"mum\": ".to_string() + &mum.to_string_json() + &", \"hmuo\": " + &hmuo.to_string_json() + &"}\n");
+    }
+
+    let path_out = folder + &"/result.mum";
+    let mut f = File::create(&path_out).expect("Invalid file ?");
+    f.write(result.as_bytes()).expect("Error when writing in the file");
+
+    println!("wrote {} MUM of size {len} to {path_out} (tolerance {tolerance}).", qhms.len() - not_qhm);
+    println!("{not_qhm} not QHM, {not_positive} not positive, {not_complete} not complete, {biased} not unbiased, {not_unitary} HMUO not unitary.");
+    if not_qhm > 0 {
+        eprintln!("{not_qhm} matrices of {path_in} were skipped as they aren't QHM");
+        std::process::exit(1);
     }
 }
 
 // Write all found QHM of a given order to qhm.mat, using the expanded list including equivalent matrices
-fn qhm_write_all(seqtype : SequenceType, len : usize) {
-    let pathname = "results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &len.to_string() + "/result.seq";
+fn qhm_write_all(seqtype : SequenceType, len : usize, shape : Option<SequenceShape>) {
+    let folder = pairs_folder(&seqtype.to_string(), len, shape);
+    let pathname = folder.clone() + "/result.seq";
     let mut qts : Vec<QuadSeq> = vec![];
 
     for line_res in read_lines(&pathname).expect("Error reading file '{pathname}'") {
@@ -227,14 +404,28 @@ fn qhm_write_all(seqtype : SequenceType, len : usize) {
 
 
     let equ = generate_equivalent_quad_seqs(&qts, seqtype);
-    let path_out = "results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &len.to_string() + &"/qhm_all.mat".to_string();
-    let mut fout = File::create(path_out).expect("Error when trying to create file '{path_out}'");
+    let path_out = folder + &"/qhm_all.mat";
+
+    // All the matrices are checked before writing anything, the sequences that don't give a QHM are reported
+    let qhms : Vec<QHM> = equ.iter().map(|seq| QHM::from_pqs(seq.to_qs()).dephased()).collect();
+    let mut num_failed = 0;
+    for (seq, qhm) in equ.iter().zip(&qhms) {
+        if !qhm.verify() {
+            num_failed += 1;
+            eprintln!("The sequence {} doesn't give a QHM:\n{}", seq.to_qs().to_string(), qhm.to_string());
+        }
+    }
+    if num_failed > 0 {
+        eprintln!("{num_failed} of the {} expanded sequences don't give a QHM, nothing was written to {path_out}", equ.len());
+        std::process::exit(1);
+    }
 
-    for seq in &equ {
-        let qhm = QHM::from_pqs(seq.to_qs()).dephased();
-        //assert!(qhm.verify());
+    let mut fout = File::create(&path_out).expect("Error when trying to create file '{path_out}'");
+    for qhm in &qhms {
         fout.write((qhm.to_string() + &"\n\n".to_string()).as_bytes()).expect("Error when trying to write to file '{path_out}'");
     }
+
+    println!("Wrote {} matrices to {path_out}.", qhms.len());
 }
 
 
@@ -293,6 +484,7 @@ fn str_to_rowsum_pairing(n : &String) -> Option<RowsumPairing> {
         "WX" => Some(RowsumPairing::WX),
         "WY" => Some(RowsumPairing::WY),
         "WZ" => Some(RowsumPairing::WZ),
+        "AUTO" => Some(RowsumPairing::AUTO),
         _ => None
     }
 }
@@ -301,10 +493,74 @@ fn str_to_match_option(n : &str) -> MatchOption {
     match n {
         "correlation" => MatchOption::CORRELATION,
         "psd" => MatchOption::PSD,
+        "exact" => MatchOption::EXACT,
         _ => {panic!("Invalid MatchOption passed")}
     }
 }
 
+// The PSD filter given as <slack> or <slack>,<oversampling> : the slack is added to the bound,
+// and the pairs are also checked at the oversampling*n frequencies of the zero-padded DFT
+fn parse_filter(arg : Option<&String>, default_oversampling : usize) -> SpectralFilter {
+    let Some(arg) = arg else {
+        return SpectralFilter::new(0., default_oversampling);
+    };
+    let (slack, oversampling) = match arg.split_once(',') {
+        Some((slack, oversampling)) => (slack, str_to_usize(oversampling)),
+        None => (arg.as_str(), default_oversampling)
+    };
+    let slack = match str::parse::<f64>(slack) {
+        Ok(a) => {a},
+        Err(_) => {panic!("The slack of the PSD filter isn't a number !")}
+    };
+    SpectralFilter::new(slack, oversampling)
+}
+
+// The PSD filter used when generating the pairs, with an optional slack added to the bound
+fn str_to_filter(arg : Option<&String>) -> SpectralFilter {
+    let filter = parse_filter(arg, 1);
+    assert_eq!(filter.oversampling, 1, "Periodic sequences only satisfy the PSD bound at the frequencies of the DFT, the zero-padded frequencies can only be checked for the aperiodic families");
+    filter
+}
+
+// The PSD filter used for the aperiodic families, whose bound also holds between the frequencies of the DFT
+fn str_to_aperiodic_filter(arg : Option<&String>) -> SpectralFilter {
+    parse_filter(arg, APERIODIC_OVERSAMPLING)
+}
+
+// The compression factor used to generate the sequences, 1 meaning no compression
+fn str_to_compression(factor : Option<&String>) -> Option<usize> {
+    factor.map(|s| str_to_usize(s)).filter(|&m| m > 1)
+}
+
+fn str_to_symmetry(n : Option<&String>) -> Option<Symmetry> {
+    match n.map(|s| s.as_str()) {
+        None => None,
+        Some("I") => Some(Symmetry::I),
+        Some("II") => Some(Symmetry::II),
+        Some("III") => Some(Symmetry::III),
+        Some("IV") => Some(Symmetry::IV),
+        Some(s) => panic!("Unrecognized symmetry {s}, options are I, II, III and IV")
+    }
+}
+
+// The shape imposed on every sequence by the pairs stage, none meaning any sequence
+fn str_to_shape(shape : Option<&String>) -> Option<SequenceShape> {
+    match shape.map(|s| s.as_str()) {
+        None | Some("none") => None,
+        Some("sym") => Some(SequenceShape::Symmetric),
+        Some("skew") => Some(SequenceShape::Skew),
+        symmetry => str_to_symmetry(symmetry.map(|s| s.to_string()).as_ref()).map(SequenceShape::Halves)
+    }
+}
+
+// The aperiodic families are tts for Turyn-type sequences and bs<m> for base sequences of lengths n+m and n
+fn str_to_family(n : &str) -> Option<AperiodicFamily> {
+    match n {
+        "tts" => Some(AperiodicFamily::TurynType),
+        _ => n.strip_prefix("bs").and_then(|m| str::parse::<usize>(m).ok()).map(AperiodicFamily::Base)
+    }
+}
+
 fn str_to_seqtype(n : &str) -> SequenceType {
     match n {
         "qts" => SequenceType::QuaternionType,
@@ -314,6 +570,8 @@ fn str_to_seqtype(n : &str) -> SequenceType {
         "et1" => SequenceType::ExtraTypeI,
         "et2" => SequenceType::ExtraTypeII,
         "et3" => SequenceType::ExtraTypeIII,
+        "gm" => SequenceType::Good,
+        "bm" => SequenceType::Best,
         _ => {panic!("Invalid sequence type passed")}
     }
 }
@@ -332,6 +590,13 @@ fn str_to_isize(source : &str) -> isize {
     }
 }
 
+fn str_to_f32(source : &str) -> f32 {
+    match str::parse::<f32>(source) {
+        Ok(a) => {a},
+        Err(_) => {panic!("argument isn't a number !")}
+    }
+}
+
 fn main() {
     let args : Vec<String> = std::env::args().collect();
 
@@ -348,10 +613,37 @@ fn main() {
     match args[1].as_str() {
         // Write all found QHM of a given order to qhm.mat, using the expanded list including equivalent matrices
         "qhm-all" => {
-            assert_eq!(args.len(), 4, "Invalid args passed");
+            assert!((4..=5).contains(&args.len()), "Invalid args passed");
             let seqtype = str_to_seqtype(&args[2]);
             let p = str_to_usize(&args[3]);
-            qhm_write_all(seqtype, p);
+            let shape = str_to_shape(args.get(4));      // Optional shape of the sequences
+            qhm_write_all(seqtype, p, shape);
+        }
+        // Forms the products of the sequences found for two coprime lengths
+        "product" => {
+            assert!((5..=6).contains(&args.len()), "Invalid args passed");
+            let seqtype = str_to_seqtype(&args[2]);
+            let p = str_to_usize(&args[3]);
+            let q = str_to_usize(&args[4]);
+            let shape = str_to_shape(args.get(5));      // Optional shape of the sequences
+            write_products(p, q, seqtype, shape);
+        }
+        // Applies the classical constructions (doubling, Goethals-Seidel array) to the sequences of a given length
+        "construct" => {
+            assert!((4..=5).contains(&args.len()), "Invalid args passed");
+            let seqtype = str_to_seqtype(&args[2]);
+            let p = str_to_usize(&args[3]);
+            let shape = str_to_shape(args.get(4));      // Optional shape of the sequences
+            write_constructions(seqtype, p, shape);
+        }
+        // Builds and checks the MUM and HMUO of the QHM in result.qhm, optionally with a custom tolerance and a shape
+        "mum" => {
+            assert!((4..=6).contains(&args.len()), "Invalid args passed");
+            let seqtype = str_to_seqtype(&args[2]);
+            let p = str_to_usize(&args[3]);
+            let tolerance = if args.len() >= 5 {str_to_f32(&args[4])} else {f32::EPSILON.sqrt()};
+            let shape = str_to_shape(args.get(5));      // Optional shape of the sequences
+            write_mums(seqtype, p, tolerance, shape);
         }
         // Verifying QTS of a given length satisfy amicability condition (e.g., verifies all QTS are WTS)
         "amicable" => {
@@ -359,35 +651,97 @@ fn main() {
             let p = str_to_usize(&args[2]);
             verify_qts_eq_wts(p);
         },
-        // Generates possible rowsums for length p, writes to .quad file
+        // Searches for the quadruples with a SAT solver, the embedded one or the given solver binary
+        "sat" => {
+            assert!(args.len() == 4 || args.len() == 5, "Invalid args passed");
+            let seqtype = str_to_seqtype(&args[2]);
+            let p = str_to_usize(&args[3]);
+            let backend = args.get(4).map_or(SolverBackend::Embedded, |solver| SolverBackend::External(solver.clone()));
+            find_with_solver(p, seqtype, &backend);
+        },
+        // Same search with the embedded solver, where the partial assignments are checked with the PSDs of the sequences
+        "sat-cas" => {
+            assert!(args.len() == 4, "Invalid args passed");
+            let seqtype = str_to_seqtype(&args[2]);
+            let p = str_to_usize(&args[3]);
+            let result = find_with_rowsum::find_with_cuts(p, seqtype);
+            write_solver_result(p, seqtype, &result);
+        },
+        // Searches directly for the perfect quaternion sequences of a given length, optionally with a symmetry
+        "pqs" => {
+            assert!(args.len() == 3 || args.len() == 4, "Invalid args passed");
+            let p = str_to_usize(&args[2]);
+            let symmetry = str_to_symmetry(args.get(3));
+            find_pqs_of_type(p, &symmetry);
+        },
+        // Searches for the odd-perfect sequences of a given length over QPLUS or Q24, optionally with a symmetry
+        "opqs" => {
+            assert!(args.len() == 4 || args.len() == 5, "Invalid args passed");
+            let p = str_to_usize(&args[2]);
+            let q24 = match args[3].as_str() {
+                "qplus" => false,
+                "q24" => true,
+                _ => panic!("Unrecognized alphabet, options are qplus and q24")
+            };
+            let symmetry = str_to_symmetry(args.get(4));
+            find_odd_pqs_of_type(p, &symmetry, q24);
+        },
+        // Generates possible rowsums for length p up to equivalence, writes them with their multiplicities to .quad file
         "rowsums" => {
-            assert_eq!(args.len(), 4, "Invalid args passed");
+            assert!((4..=5).contains(&args.len()), "Invalid args passed");
             let seqtype = str_to_seqtype(&args[2]);
             let p = str_to_usize(&args[3]);
-            write_rowsums(p, seqtype);
+            let shape = str_to_shape(args.get(4));      // Optional shape of the sequences
+            write_rowsums(p, seqtype, shape);
         }
         // Matches data from sorted .pair files to generate sequences
         "join" => {
-            assert_eq!(args.len(), 4, "Invalid args passed");
+            assert!((4..=5).contains(&args.len()), "Invalid args passed");
+            if let Some(family) = str_to_family(&args[2]) {
+                let n = str_to_usize(&args[3]);
+                let folder = aperiodic_folder(family, n);
+                write_aperiodic_results(&folder, &join_aperiodic_pairs(&folder, family, n));
+                return;
+            }
+            if args[2] == "ccp" {
+                let n = str_to_usize(&args[3]);
+                let folder = complex_folder(n);
+                write_complex_results(&folder, &join_complex_pairs(&folder, n));
+                return;
+            }
             let seqtype = str_to_seqtype(&args[2]);
             let p = str_to_usize(&args[3]);
-            find_write_quad_seq(p, seqtype);
+            let shape = str_to_shape(args.get(4));      // Optional shape of the sequences
+            find_write_quad_seq(p, seqtype, shape);
         }
         // Converts sequences to Hadamard matrices up to Hadamard equivalence
         "convert" => {
-            assert_eq!(args.len(), 5, "Invalid args passed");
+            assert!((5..=6).contains(&args.len()), "Invalid args passed");
             let seqtype = str_to_seqtype(&args[3]);
             let p = str_to_usize(&args[4]);
+            let shape = str_to_shape(args.get(5));      // Optional shape of the sequences
+            let folder = pairs_folder(&seqtype.to_string(), p, shape);
+            // The good and best matrices aren't quaternion-type sequences, their Hadamard matrices come from the Goethals-Seidel array
+            if matches!(seqtype, SequenceType::Good | SequenceType::Best) {
+                eprintln!("The sequences of type {} can't be converted to Hadamard matrices or QHM, use `construct {} {p}` to build their skew-Hadamard matrices through the Goethals-Seidel array", seqtype.to_string(), seqtype.to_string());
+                std::process::exit(1);
+            }
             match args[2].as_str() {
                 "hm" => {
                     if matches!(seqtype, SequenceType::WilliamsonType) {
-                        hadamard_equivalence_from_file("results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &p.to_string() + &"/result-qts.seq".to_string(), seqtype);
+                        hadamard_equivalence_from_file(folder + &"/result-qts.seq", seqtype);
                     } else {
-                        hadamard_equivalence_from_file("results/pairs/".to_string() + &seqtype.to_string() + &"/find_".to_string() + &p.to_string() + &"/result.seq".to_string(), seqtype);
+                        hadamard_equivalence_from_file(folder + &"/result.seq", seqtype);
                     }
                 }
                 "qhm" => {
-                    convert_qs_to_matrices(seqtype, p);
+                    convert_qs_to_matrices(seqtype, p, shape);
+                }
+                "real" => {
+                    convert_qhm_to_representation(seqtype, p, false, shape);
+                }
+                "complex" => {
+                    convert_qhm_to_representation(seqtype, p, true, shape);
                 }
                 _ => {panic!("Invalid arguments passed!");}
             }
@@ -395,16 +749,41 @@ fn main() {
         },
         // Generates .pair files used in algorithm 
         "pairs" => {
-            assert_eq!(args.len(), 6, "Invalid args passed");
+            // With --dry-run, only the projected size of the .pair files and the time to generate them are printed
+            let dry_run = args.iter().any(|arg| arg == "--dry-run");
+            let args : Vec<String> = args.iter().filter(|arg| *arg != "--dry-run").cloned().collect();
+
+            assert!((6..=9).contains(&args.len()), "Invalid args passed");
+            // The aperiodic families are always matched by their exact correlations with the pairing WX, without compression
+            if let Some(family) = str_to_family(&args[2]) {
+                assert!(!dry_run, "The dry run isn't available for the aperiodic families");
+                let n = str_to_usize(&args[3]);
+                write_aperiodic_pairs(&aperiodic_folder(family, n), family, n, str_to_aperiodic_filter(args.get(6)));
+                return;
+            }
+            // The complex complementary pairs are matched by their exact autocorrelations with the pairing WX, without compression
+            if args[2] == "ccp" {
+                assert!(!dry_run, "The dry run isn't available for the complex pairs");
+                let n = str_to_usize(&args[3]);
+                write_complex_pairs(&complex_folder(n), n, str_to_filter(args.get(6)));
+                return;
+            }
             let seqtype = str_to_seqtype(&args[2]);
             let p = str_to_usize(&args[3]);
             let match_option = str_to_match_option(&args[4]);
             let pairing = str_to_rowsum_pairing(&args[5]);
-            write_pairs(p, seqtype, match_option, pairing);
+            let filter = str_to_filter(args.get(6));          // Optional slack of the PSD filter
+            let compression = str_to_compression(args.get(7));    // Optional compression factor
+            let shape = str_to_shape(args.get(8));                // Optional shape of the sequences
+            if dry_run {
+                estimate_pairs(p, seqtype, match_option, pairing, filter, compression, shape);
+            } else {
+                write_pairs(p, seqtype, match_option, pairing, filter, compression, shape);
+            }
         },
         // Generates .pair files corresponding to a single set of rowsums
         "pairs_rowsum" => {
-            assert_eq!(args.len(), 10, "Invalid args passed");
+            assert!((10..=13).contains(&args.len()), "Invalid args passed");
             let folder = str_to_seqtype(&args[2]).to_string();  // verifies seqtype input is correct
             let p = str_to_usize(&args[3]);     // length
             let a = str_to_isize(&args[4]);     // rowsum 1
@@ -414,24 +793,29 @@ fn main() {
 
             let match_option = str_to_match_option(&args[8]);   // Correlation or PSD matching
             let pairing = str_to_rowsum_pairing(&args[9]);      // Rowsum pairing
+            let filter = str_to_filter(args.get(10));           // Optional slack of the PSD filter
+            let compression = str_to_compression(args.get(11)); // Optional compression factor
+            let shape = str_to_shape(args.get(12));             // Optional shape of the sequences
 
-            write_pairs_rowsum(&folder, (a,b,c,d), p, match_option, pairing);
+            write_pairs_rowsum(&folder, (a,b,c,d), p, match_option, pairing, filter, compression, shape);
         },
+        // Creates the directories and .pair files of all the rowsums written by the rowsums command
+        // With the pairing AUTO, the pairing of each rowsum is chosen here from the sequences, generated with the optional arguments of pair_single
         "create" => {
+            assert!((5..=8).contains(&args.len()), "Invalid args passed");
             let folder = str_to_seqtype(&args[2]).to_string();  // verifies seqtype input is correct
             let p = str_to_usize(&args[3]);     // length
-            let a = str_to_isize(&args[4]);     // rowsum 1
-            let b = str_to_isize(&args[5]);     // rowsum 2
-            let c = str_to_isize(&args[6]);     // rowsum 3
-            let d = str_to_isize(&args[7]);     // rowsum 4
 
-            let pairing = str_to_rowsum_pairing(&args[8]);      // Rowsum pairing
+            let pairing = str_to_rowsum_pairing(&args[4]);      // Rowsum pairing
+            let filter = str_to_filter(args.get(5));            // Optional slack of the PSD filter
+            let compression = str_to_compression(args.get(6));  // Optional compression factor
+            let shape = str_to_shape(args.get(7));              // Optional shape of the sequences
 
-            create_rowsum_dirs(folder, p, (a,b,c,d), pairing);
+            create_rowsum_dirs(folder, p, pairing, filter, compression, shape);
         },
         // Generates .pair file for one pair, corresponding to one set of rowsums
         "pair_single" => {
-            assert_eq!(args.len(), 11, "Invalid args passed");
+            assert!((11..=14).contains(&args.len()), "Invalid args passed");
             let folder = str_to_seqtype(&args[2]).to_string();  // verifies seqtype input is correct
             let p = str_to_usize(&args[3]);     // length
             let a = str_to_isize(&args[4]);     // rowsum 1
@@ -448,7 +832,11 @@ fn main() {
                 Err(_) => {panic!("argument isn't an integer !")}
             };
             
-            write_pair_single_rowsum(folder, (a,b,c,d), p, match_option, pairing, pair);
+            let filter = str_to_filter(args.get(11));           // Optional slack of the PSD filter
+            let compression = str_to_compression(args.get(12)); // Optional compression factor
+            let shape = str_to_shape(args.get(13));             // Optional shape of the sequences
+
+            write_pair_single_rowsum(folder, (a,b,c,d), p, match_option, pairing, pair, filter, compression, shape);
         }
 
 
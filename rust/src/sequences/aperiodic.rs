@@ -0,0 +1,102 @@
+use itertools::iproduct;
+
+use super::{rowsum::Quad, williamson::aperiodic_autocorrelation};
+
+// * Aperiodic families
+// Quadruples of sequences of mixed lengths whose weighted aperiodic autocorrelations sum to zero at every nonzero offset.
+// Turyn-type sequences TT(n) have lengths (n, n, n, n-1) and satisfy N_A + N_B + 2N_C + 2N_D = 0,
+// base sequences BS(n+m, n) have lengths (n+m, n+m, n, n) and satisfy N_A + N_B + N_C + N_D = 0.
+// Both feed the Goethals-Seidel array through T-sequences, and the weighted PSDs of their sequences add up to a constant at every frequency
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AperiodicFamily {
+    TurynType,
+    Base(usize)     // the difference m between the lengths of the two pairs
+}
+
+impl AperiodicFamily {
+
+    pub fn lengths(&self, n : usize) -> [usize; 4] {
+        match self {
+            Self::TurynType => {
+                assert!(n >= 2, "Turyn-type sequences have length at least 2");
+                [n, n, n, n-1]
+            },
+            Self::Base(m) => [n+m, n+m, n, n]
+        }
+    }
+
+    pub fn weights(&self) -> [isize; 4] {
+        match self {
+            Self::TurynType => [1, 1, 2, 2],
+            Self::Base(_) => [1, 1, 1, 1]
+        }
+    }
+
+    // The weighted sum of the PSDs at every frequency, which is also the weighted sum of the squared rowsums
+    pub fn psd_total(&self, n : usize) -> isize {
+        self.lengths(n).iter().zip(self.weights()).map(|(&len, w)| w * len as isize).sum()
+    }
+
+    pub fn generate_rowsums(&self, n : usize) -> Vec<Quad> {
+        // generates the rowsums whose weighted squares add up to the PSD total
+        // negating a sequence or swapping the sequences of a pair of the same length gives an equivalent quadruple,
+        // so the rowsums are nonnegative and decreasing within these pairs
+
+        let lengths = self.lengths(n);
+        let weights = self.weights();
+        let total = self.psd_total(n);
+        let range = |len : usize| (0..=len as isize).filter(move |r| (len as isize - r) % 2 == 0);
+
+        iproduct!(range(lengths[0]), range(lengths[1]), range(lengths[2]), range(lengths[3]))
+            .filter(|&(a, b, c, d)| weights[0]*a*a + weights[1]*b*b + weights[2]*c*c + weights[3]*d*d == total)
+            .filter(|&(a, b, c, d)| a >= b && (matches!(self, Self::TurynType) || c >= d))
+            .collect()
+    }
+
+    pub fn verify(&self, quad : &[Vec<i8>; 4]) -> bool {
+        // tests if the sequences have the right lengths and their weighted aperiodic autocorrelations vanish
+        let n = quad[2].len();
+        if n < 1 || quad.iter().map(|seq| seq.len()).ne(self.lengths(n)) {
+            return false;
+        }
+
+        let weights = self.weights();
+        (1..quad[0].len()).all(|offset| quad.iter().zip(weights).map(|(seq, w)| w * aperiodic_autocorrelation(seq, offset)).sum::<isize>() == 0)
+    }
+
+    pub fn canonical(&self, quad : &[Vec<i8>; 4]) -> [Vec<i8>; 4] {
+        // returns the smallest quadruple equivalent to quad, the equivalences being the negation and reversal of any sequence,
+        // the alternation of all sequences and the swaps of the sequences of a pair of the same length
+
+        [false, true].iter().map(|&alternate| {
+            let mut result = quad.clone().map(|seq| {
+                let seq : Vec<i8> = if alternate {seq.iter().enumerate().map(|(i, &elm)| if i % 2 == 1 {-elm} else {elm}).collect()} else {seq};
+                let reversed : Vec<i8> = seq.iter().rev().cloned().collect();
+                let negate = |s : &Vec<i8>| -> Vec<i8> {s.iter().map(|&elm| -elm).collect()};
+                [negate(&seq), negate(&reversed), seq, reversed].into_iter().min().expect("No sequence ?")
+            });
+            if result[0] > result[1] {
+                result.swap(0, 1);
+            }
+            if matches!(self, Self::Base(_)) && result[2] > result[3] {
+                result.swap(2, 3);
+            }
+            result
+        }).min().expect("No quadruple ?")
+    }
+
+}
+
+impl ToString for AperiodicFamily {
+    fn to_string(&self) -> String {
+        match self {
+            Self::TurynType => "tts".to_string(),
+            Self::Base(m) => "bs".to_string() + &m.to_string()
+        }
+    }
+}
+
+pub fn aperiodic_quad_to_string(quad : &[Vec<i8>; 4]) -> String {
+    quad.iter().map(|seq| seq.iter().map(|&elm| if elm == 1 {'+'} else {'-'}).collect::<String>()).collect::<Vec<String>>().join(" ")
+}
@@ -0,0 +1,233 @@
+use itertools::iproduct;
+use num_complex::Complex;
+
+use super::{equivalence::coprime, mum::Operator, rowsum::Quad};
+
+// * Complex sequences over {1, -1, i, -i}
+// Every value c satisfies (1+i)c = a + ib with a, b = ±1, so a complex sequence of length n is a pair of ±1 sequences (a, b).
+// Its periodic autocorrelations are R_c(t) = (R_a(t) + R_b(t))/2 + i(sum b_k a_{k+t} - sum a_k b_{k+t})/2, thus a complementary
+// pair (c, d) gives four ±1 sequences (a, b, e, f) whose periodic autocorrelations add up to 0, like the quadruples of
+// sequences, and whose squared rowsums add up to 4n. The pair also gives a complex Hadamard matrix of order 2n
+
+pub type Gaussian = Complex<isize>;
+
+pub static CS_VALUES : [Gaussian; 4] = [Complex {re : 1, im : 0}, Complex {re : -1, im : 0}, Complex {re : 0, im : 1}, Complex {re : 0, im : -1}];
+pub static CS_STRING : [&str; 4] = ["+", "-", "i", "I"];       // the same characters as for Q24
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CS {
+    size : usize,
+    values : Vec<Gaussian>
+}
+
+impl CS {
+
+    pub fn new(size : usize) -> CS {
+        CS {
+            size,
+            values : vec![CS_VALUES[0]; size]
+        }
+    }
+
+    pub fn from_values(values : Vec<Gaussian>) -> CS {
+        assert!(values.iter().all(|elm| CS_VALUES.contains(elm)), "The values must be 1, -1, i or -i");
+        CS {
+            size : values.len(),
+            values
+        }
+    }
+
+    pub fn from_str(s : &String) -> CS {
+        let values = s.chars().map(|char| {
+            match CS_STRING.iter().position(|c| *c == char.to_string()) {
+                None => {panic!("Unrecognized String!")}
+                Some(i) => {CS_VALUES[i]}
+            }
+        }).collect();
+
+        CS::from_values(values)
+    }
+
+    // The sequence c such that (1+i)c = a + ib
+    pub fn from_pair(a : &Vec<i8>, b : &Vec<i8>) -> CS {
+        assert!(a.len() == b.len());
+        let values = a.iter().zip(b.iter()).map(|(&x, &y)| Complex::new((x + y) as isize / 2, (y - x) as isize / 2)).collect();
+        CS::from_values(values)
+    }
+
+    // The sequences a and b such that (1+i)c = a + ib
+    pub fn to_pair(&self) -> (Vec<i8>, Vec<i8>) {
+        self.values.iter().map(|elm| (((elm.re - elm.im) as i8), ((elm.re + elm.im) as i8))).unzip()
+    }
+
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    pub const fn values(&self) -> &Vec<Gaussian> {
+        &self.values
+    }
+
+    pub fn set_value(&mut self, value : Gaussian, index : usize) {
+        assert!(CS_VALUES.contains(&value), "The values must be 1, -1, i or -i");
+        self.values[index] = value;
+    }
+
+    pub fn rowsum(&self) -> Gaussian {
+        self.values.iter().sum()
+    }
+
+    pub fn periodic_autocorrelation(&self, t : usize) -> Gaussian {
+        // computes the periodic auto-correlation
+        (0..self.size).map(|i| self.values[i] * self.values[(i+t) % self.size].conj()).sum()
+    }
+
+    pub fn aperiodic_autocorrelation(&self, t : usize) -> Gaussian {
+        // computes the aperiodic auto-correlation, the terms that would wrap around are dropped
+        (0..self.size.saturating_sub(t)).map(|i| self.values[i] * self.values[i+t].conj()).sum()
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        // tests if the sequence is perfect, the autocorrelations of the second half are the conjugates of the first half
+        (1..=self.size/2).all(|t| self.periodic_autocorrelation(t) == Complex::new(0, 0))
+    }
+
+    pub fn conjugate(&self) -> CS {
+        CS::from_values(self.values.iter().map(|elm| elm.conj()).collect())
+    }
+
+    // Sends the value of index i to the index -i mod n
+    pub fn transposed(&self) -> CS {
+        CS::from_values((0..self.size).map(|i| self.values[(self.size - i) % self.size]).collect())
+    }
+
+    pub fn shifted(&self, offset : usize) -> CS {
+        CS::from_values((0..self.size).map(|i| self.values[(i + offset) % self.size]).collect())
+    }
+
+    pub fn multiplied(&self, unit : Gaussian) -> CS {
+        CS::from_values(self.values.iter().map(|elm| elm * unit).collect())
+    }
+
+    // Applies the automorphism i -> ki of the cyclic group to the indices
+    pub fn decimated(&self, k : usize) -> CS {
+        CS::from_values((0..self.size).map(|i| self.values[(i * k) % self.size]).collect())
+    }
+
+    // The circulant matrix whose first row is the sequence
+    pub fn circulant(&self) -> Operator {
+        let n = self.size;
+        Operator::from_values((0..n).map(|row| (0..n).map(|col| to_complex_f32(self.values[(col + n - row) % n])).collect()).collect())
+    }
+
+    fn key(&self) -> Vec<usize> {
+        self.values.iter().map(|elm| CS_VALUES.iter().position(|v| v == elm).expect("Not a value")).collect()
+    }
+
+    pub fn to_string_raw(&self) -> String {
+        self.values.iter().map(|elm| CS_STRING[CS_VALUES.iter().position(|v| v == elm).expect("Not a value")]).collect()
+    }
+
+}
+
+impl ToString for CS {
+    fn to_string(&self) -> String {
+        "[".to_string() + &self.to_string_raw() + &"]"
+    }
+}
+
+fn to_complex_f32(elm : Gaussian) -> Complex<f32> {
+    Complex::new(elm.re as f32, elm.im as f32)
+}
+
+
+// * Complementary sequences
+
+pub fn is_periodic_complementary(sequences : &[CS]) -> bool {
+    // tests if the periodic autocorrelations of the sequences (e.g. a pair or a quadruple) add up to 0 at every nonzero offset
+    let n = sequences.first().map_or(0, |seq| seq.size());
+    assert!(sequences.iter().all(|seq| seq.size() == n));
+    (1..=n/2).all(|t| sequences.iter().map(|seq| seq.periodic_autocorrelation(t)).sum::<Gaussian>() == Complex::new(0, 0))
+}
+
+pub fn is_aperiodic_complementary(sequences : &[CS]) -> bool {
+    // tests if the aperiodic autocorrelations of the sequences add up to 0 at every nonzero offset (complex Golay sequences for a pair)
+    let n = sequences.iter().map(|seq| seq.size()).max().unwrap_or(0);
+    (1..n).all(|t| sequences.iter().map(|seq| seq.aperiodic_autocorrelation(t)).sum::<Gaussian>() == Complex::new(0, 0))
+}
+
+// Complex Hadamard matrix of order 2n from a periodic complementary pair (c, d), C and D being their circulant matrices
+//  C     D
+// -D^*   C^*
+// The circulant matrices commute, so the rows of the two blocks are orthogonal
+pub fn complex_hadamard(pair : &(CS, CS)) -> Option<Operator> {
+    let (c, d) = pair;
+    if !is_periodic_complementary(&[c.clone(), d.clone()]) {
+        return None;
+    }
+
+    let n = c.size();
+    let (mat_c, mat_d) = (c.circulant(), d.circulant());
+    let (adj_c, adj_d) = (mat_c.conjugate_transpose(), mat_d.conjugate_transpose());
+
+    let mut values = vec![vec![Complex::new(0., 0.); 2*n]; 2*n];
+    for (row, col) in iproduct!(0..n, 0..n) {
+        values[row][col] = mat_c.get(row, col);
+        values[row][n + col] = mat_d.get(row, col);
+        values[n + row][col] = -adj_d.get(row, col);
+        values[n + row][n + col] = adj_c.get(row, col);
+    }
+
+    let hm = Operator::from_values(values);
+    hm.is_complex_hadamard().then_some(hm)
+}
+
+
+// * Rowsums and equivalences of complementary pairs
+// Multiplying c by i sends (a, b) to (-b, a), so up to the units the rowsums (r_a, r_b) of the pair of c satisfy r_a > 0 and r_b >= 0,
+// or are both 0. Each sequence can also be shifted, and both can be swapped, conjugated, transposed or decimated together
+
+pub fn generate_complex_rowsums(n : usize) -> Vec<Quad> {
+    // generates the rowsums (r_a, r_b, r_e, r_f) of the ±1 sequences of a complementary pair, whose squares add up to 4n
+    let range = || (0..=n as isize).filter(move |r| (n as isize - r) % 2 == 0);
+    let admissible = |x : isize, y : isize| x > 0 || y == 0;
+
+    iproduct!(range(), range(), range(), range())
+        .filter(|&(a, b, e, f)| a*a + b*b + e*e + f*f == 4 * n as isize)
+        .filter(|&(a, b, e, f)| admissible(a, b) && admissible(e, f) && (a, b) >= (e, f))
+        .collect()
+}
+
+// The shifts keep the rowsums, so the search only needs the sequences that are the smallest of their shifts
+pub fn is_smallest_shift(seq : &CS) -> bool {
+    let key = seq.key();
+    (1..seq.size()).all(|offset| seq.shifted(offset).key() >= key)
+}
+
+// The smallest sequence obtained from seq by a shift and the multiplication by a unit
+fn canonical_sequence(seq : &CS) -> CS {
+    iproduct!(0..seq.size(), CS_VALUES.iter()).map(|(offset, unit)| seq.shifted(offset).multiplied(*unit))
+        .min_by_key(|s| s.key()).expect("Empty sequence")
+}
+
+pub fn canonical_pair(pair : &(CS, CS)) -> (CS, CS) {
+    // returns the smallest pair equivalent to pair
+    let n = pair.0.size();
+
+    let mut images = vec![];
+    for (conjugate, transpose, k) in iproduct!([false, true], [false, true], (1..=n).filter(|&k| coprime(k, n))) {
+        let apply = |seq : &CS| -> CS {
+            let seq = if conjugate {seq.conjugate()} else {seq.clone()};
+            let seq = if transpose {seq.transposed()} else {seq};
+            canonical_sequence(&seq.decimated(k))
+        };
+        let (c, d) = (apply(&pair.0), apply(&pair.1));
+        images.push(if c.key() <= d.key() {(c, d)} else {(d, c)});
+    }
+
+    images.into_iter().min_by_key(|(c, d)| (c.key(), d.key())).expect("No image ?")
+}
+
+pub fn complex_pair_to_string(pair : &(CS, CS)) -> String {
+    pair.0.to_string_raw() + &" " + &pair.1.to_string_raw()
+}
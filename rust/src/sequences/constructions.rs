@@ -0,0 +1,133 @@
+use std::{fs::{self, File}, io::Write};
+
+use crate::{find::{find_unique::reduce_to_canonical_reps, find_write::pairs_folder}, read_lines};
+
+use super::{matrices::HM, product::sequence_product, sequence::{QS, QPLUS}, symmetries::{SequenceShape, SequenceType}, williamson::{skew_type, QuadSeq, SequenceTag}};
+
+
+// * Classical constructions combining quadruples of sequences into longer ones
+// Every construction checks its output with QuadSeq::verify and returns None when it fails
+
+// For n odd, Z_2n is isomorphic to Z_n x Z_2 through i -> (i mod n, i mod 2)
+// The sequence built from (seq1, seq2) takes its even positions from seq1 and its odd positions from seq2
+fn interleave(seq1 : &Vec<i8>, seq2 : &Vec<i8>) -> Vec<i8> {
+    let n = seq1.len();
+    (0..2*n).map(|i| if i % 2 == 0 {seq1[i % n]} else {seq2[i % n]}).collect()
+}
+
+fn negated(seq : &Vec<i8>) -> Vec<i8> {
+    seq.iter().map(|elm| -elm).collect()
+}
+
+// Doubles the length of a quadruple of odd length n : (A, B, C, D) gives ((A,B), (A,-B), (C,D), (C,-D)) seen as sequences of Z_2n
+// The periodic autocorrelations at even offsets are sums of autocorrelations of A, B, C and D, and the cross correlations at odd offsets cancel out
+pub fn double(seq : &QuadSeq, seqtype : SequenceType) -> Option<QuadSeq> {
+    if seq.size() % 2 == 0 {
+        return None;
+    }
+
+    let (a, b, c, d) = seq.sequences();
+    let mut result = QuadSeq::new(2*seq.size());
+    result.set_all_values((&interleave(&a, &b), &interleave(&a, &negated(&b)), &interleave(&c, &d), &interleave(&c, &negated(&d))));
+
+    result.verify(seqtype).then_some(result)
+}
+
+// Product of quadruples of coprime lengths through the product of the corresponding perfect quaternion sequences
+// The product is always perfect, but it only gives a quadruple when all of its entries are in QPLUS
+pub fn product(seq1 : &QuadSeq, seq2 : &QuadSeq, seqtype : SequenceType) -> Option<QuadSeq> {
+    let product = sequence_product(&seq1.to_qs(), &seq2.to_qs());
+
+    if !product.values().iter().all(|elm| QPLUS.contains(elm)) {
+        return None;
+    }
+
+    let result = QuadSeq::from_pqs(&product);
+    result.verify(seqtype).then_some(result)
+}
+
+// Hadamard matrix of order 4n from the Goethals-Seidel array, which only needs the sequences to be periodic complementary
+//  A     BR    CR    DR
+// -BR    A     D^TR -C^TR
+// -CR   -D^TR  A     B^TR
+// -DR    C^TR -B^TR  A
+// where R is the back diagonal identity matrix
+pub fn goethals_seidel(seq : &QuadSeq) -> Option<HM> {
+    if !seq.is_periodic_complementary() {
+        return None;
+    }
+
+    let n = seq.size();
+    let circulants = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].map(|tag| HM::from_sequence(&seq.sequence(tag)));
+    // (block, sign, transposed, times R) for every block of the array
+    let array = [[(0, 1, false, false), (1, 1, false, true), (2, 1, false, true), (3, 1, false, true)],
+                 [(1, -1, false, true), (0, 1, false, false), (3, 1, true, true), (2, -1, true, true)],
+                 [(2, -1, false, true), (3, -1, true, true), (0, 1, false, false), (1, 1, true, true)],
+                 [(3, -1, false, true), (2, 1, true, true), (1, -1, true, true), (0, 1, false, false)]];
+
+    let mut hm = HM::new(4*n);
+    for (block_row, line) in array.iter().enumerate() {
+        for (block_col, (index, sign, transposed, times_r)) in line.iter().enumerate() {
+            for (row, col) in itertools::iproduct!(0..n, 0..n) {
+                // Multiplying by R on the right reverses the order of the columns
+                let col_source = if *times_r {n - 1 - col} else {col};
+                let value = if *transposed {circulants[*index].get(col_source, row)} else {circulants[*index].get(row, col_source)};
+                hm.set_value(block_row*n + row, block_col*n + col, sign * value);
+            }
+        }
+    }
+
+    hm.verify().then_some(hm)
+}
+
+// Skew-Hadamard matrix of order 4n from the Goethals-Seidel array, when A is skew-type (good and best matrices)
+// For any circulant X, XR is symmetric, so the blocks off the diagonal are opposite to their transposes and H + H^T = 2I
+pub fn skew_hadamard(seq : &QuadSeq) -> Option<HM> {
+    if !skew_type(&seq.sequence(SequenceTag::W)) {
+        return None;
+    }
+
+    goethals_seidel(seq).filter(|hm| hm.is_skew())
+}
+
+
+// Applies the constructions to the sequences found for length n : the doubled sequences (for n odd) are reduced to equivalence
+// and written to find_2n/double_n.seq, and the Goethals-Seidel matrices are written to find_n/result-gs.mat
+// (these are skew-Hadamard for good and best matrices). With a shape, the folders are those of the searches restricted to it
+pub fn write_constructions(seqtype : SequenceType, n : usize, shape : Option<SequenceShape>) {
+    let folder = |len : usize| pairs_folder(&seqtype.to_string(), len, shape);
+    let pathname = folder(n) + &"/result.seq";
+
+    let sequences : Vec<QuadSeq> = read_lines(&pathname).expect(&format!("Error reading file. Make sure sequences have already been generated for this length (e.g., {} should exist and not be empty)", pathname))
+        .map(|line| QuadSeq::from_pqs(&QS::from_str(&line.expect("Error reading line")))).collect();
+
+    let matrices : Vec<HM> = sequences.iter().filter_map(goethals_seidel).collect();
+    let mut result = "".to_string();
+    for mat in &matrices {
+        result += &mat.to_string_magma();
+        result += &"\n";
+    }
+    let mut f = File::create(folder(n) + &"/result-gs.mat").expect("Invalid file ?");
+    f.write(result.as_bytes()).expect("Error when writing in the file");
+    println!("{} of the {} sequences of length {n} give a Hadamard matrix of order {} through the Goethals-Seidel array.", matrices.len(), sequences.len(), 4*n);
+
+    if matches!(seqtype, SequenceType::Good | SequenceType::Best) {
+        let skew = sequences.iter().filter_map(skew_hadamard).count();
+        println!("{} of them are skew-Hadamard matrices.", skew);
+    }
+
+    if n % 2 == 1 {
+        let doubled : Vec<QuadSeq> = sequences.iter().filter_map(|seq| double(seq, seqtype)).collect();
+        let reps = if doubled.is_empty() {vec![]} else {reduce_to_canonical_reps(&doubled, seqtype)};
+
+        fs::create_dir_all(folder(2*n)).expect("Error when creating the dir");
+        let mut result = "".to_string();
+        for seq in &reps {
+            result += &seq.to_qs().to_string_raw();
+            result += &"\n";
+        }
+        let mut f = File::create(folder(2*n) + &format!("/double_{n}.seq")).expect("Invalid file ?");
+        f.write(result.as_bytes()).expect("Error when writing in the file");
+        println!("{} of the {} sequences of length {n} could be doubled, giving {} inequivalent sequences of length {}.", doubled.len(), sequences.len(), reps.len(), 2*n);
+    }
+}
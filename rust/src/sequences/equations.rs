@@ -1,370 +1,157 @@
-use super::{williamson::{SequenceTag, periodic_autocorrelation, cross_correlation}, symmetries::SequenceType, rowsum::Quad};
+use std::collections::BTreeMap;
+use itertools::iproduct;
 
+use super::{williamson::SequenceTag, symmetries::SequenceType, rowsum::Quad, sat::{Formula, Lit}, equivalence::coprime};
 
 
-pub enum OpType { // Type to know what operator to use when computing the values for the equations
-    LeftPlus, LeftMinus, RightPlus, RightMinus
-}
+// * Formulas of the quadruples for the solvers
+// The value at index k of the sequence of index i (0 for W to 3 for Z) is the variable i*n + k + 1, true standing for +1.
+// All the conditions on the quadruples are sums of products of two values that must be equal to some value, every product
+// being the literal which is true iff both values are equal. A sum of m such products is then equal to v iff exactly
+// (m + v)/2 of the literals are true, which is a cardinality constraint
 
-pub enum AddType {
-    Plus, Minus
+pub fn quad_variable(index : usize, k : usize, n : usize) -> Lit {
+    (index * n + k + 1) as Lit
 }
 
-
-pub fn generate_equations(seq1 : &Vec<i8>, tag1 : &SequenceTag, seq2 : &Vec<i8>, tag2 : &SequenceTag, seqtype : &SequenceType, rowsum : &Quad) -> String{
-    // generates all the equations given by two sequences and their positions, so that they return a specific type of sequence.
-
-    match seqtype {
-        SequenceType::QuaternionType => {
-            equations_williamson_type(seq1, tag1, seq2, tag2, rowsum)
-        }
-        _ => {panic!()/* TODO */}
+fn add_product_sum(formula : &mut Formula, terms : &Vec<(isize, Lit, Lit)>, value : isize) {
+    // forces the sum of the terms c * x_a * x_b to be equal to value, the terms of the same product being gathered first
+    let mut coefficients : BTreeMap<(Lit, Lit), isize> = BTreeMap::new();
+    for &(c, a, b) in terms {
+        *coefficients.entry((a.min(b), a.max(b))).or_insert(0) += c;
     }
-}
 
-
-fn equations_williamson_type(seq1 : &Vec<i8>, tag1 : &SequenceTag, seq2 : &Vec<i8>, tag2 : &SequenceTag, rowsum : &Quad) -> String {
-    // generates the equations given by two sequences and their positions so that that they return Williamson-type sequences
-    let mut result = "".to_string();
-    
-    match (tag1, tag2) { // ! In case of multiple sequences, make sure the same sequences are on the same side !!!
-        (SequenceTag::W, SequenceTag::X) => {// x1 ... is D, ... xn is C
-            result += &additional_comment(&SequenceTag::Z, &SequenceTag::Y);
-            result += &equations_crosscorrelation(&seq1, OpType::RightMinus, &seq2, OpType::LeftMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq2, OpType::RightMinus, &seq1, OpType::RightMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::LeftMinus, OpType::LeftMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.3, rowsum.2);
-        }
-        (SequenceTag::W, SequenceTag::Y) => {// x1 ... is D, ... xn is B
-            result += &additional_comment(&SequenceTag::Z, &SequenceTag::X);
-            result += &equations_crosscorrelation(&seq1, OpType::RightMinus, &seq2, OpType::RightMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq2, OpType::RightMinus, &seq1, OpType::LeftMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::RightMinus, OpType::LeftMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.3, rowsum.1);
-        }
-        (SequenceTag::W, SequenceTag::Z) => {// x1 ... is B, ... xn is C
-            result += &additional_comment(&SequenceTag::X, &SequenceTag::Y);
-            result += &equations_crosscorrelation(&seq2, OpType::LeftMinus, &seq1, OpType::RightMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq1, OpType::LeftMinus, &seq2, OpType::LeftMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::RightMinus, OpType::LeftMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.1, rowsum.2);
-        }
-        (SequenceTag::X, SequenceTag::Y) => {// x1 ... is D, ... xn is A
-            result += &additional_comment(&SequenceTag::Z, &SequenceTag::W);
-            result += &equations_crosscorrelation(&seq1, OpType::RightMinus, &seq2, OpType::LeftMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq2, OpType::RightMinus, &seq1, OpType::RightMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::LeftMinus, OpType::LeftMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.3, rowsum.0);
-        }
-        (SequenceTag::X, SequenceTag::Z) => {// x1 ... is A, ... xn is C
-            result += &additional_comment(&SequenceTag::W, &SequenceTag::Y);
-            result += &equations_crosscorrelation(&seq2, OpType::LeftMinus, &seq1, OpType::LeftMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq1, OpType::RightMinus, &seq2, OpType::LeftMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::RightMinus, OpType::RightMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.0, rowsum.2);
-        }
-        (SequenceTag::Y, SequenceTag::Z) => {// x1 ... is A, ... xn is B
-            result += &additional_comment(&SequenceTag::W, &SequenceTag::X);
-            result += &equations_crosscorrelation(&seq2, OpType::LeftMinus, &seq1, OpType::RightMinus, AddType::Plus);
-            result += &equations_crosscorrelation(&seq1, OpType::LeftMinus, &seq2, OpType::LeftMinus, AddType::Plus);
-            result += &equations_nonlinear_crosscorrelation(&seq1, &seq2, OpType::RightMinus, OpType::LeftMinus, AddType::Plus);
-            result += &equations_rowsum(seq1.len(), rowsum.0, rowsum.1);
-        }
-        _ => {panic!("incorrect tags entered !")}
+    let mut literals = vec![];
+    for ((a, b), c) in coefficients {
+        let product = formula.add_xnor(a, b);
+        literals.extend(vec![if c > 0 {product} else {-product}; c.unsigned_abs()]);
     }
 
-    result += &equations_autocorrelation(&seq1, &seq2);
-
-    result
+    let total = literals.len() as isize;
+    if (total + value) % 2 != 0 || value.abs() > total {
+        formula.add_clause(vec![]);
+        return;
+    }
+    formula.add_exactly(literals, ((total + value) / 2) as usize);
 }
 
+fn cross_correlation_terms(index1 : usize, index2 : usize, offset : usize, n : usize, c : isize) -> Vec<(isize, Lit, Lit)> {
+    // the terms of c times the periodic cross correlation of the sequences, as in williamson::cross_correlation
+    (0..n).map(|k| (c, quad_variable(index1, k, n), quad_variable(index2, (k + n - offset) % n, n))).collect()
+}
 
+pub fn generate_formula(seqtype : SequenceType, n : usize, rowsum : &Quad) -> Formula {
+    // generates the formula whose models are the quadruples of type seqtype with the given rowsums, up to the symmetries below
+    let mut formula = Formula::new(4 * n);
+    let x = |index : usize, k : usize| quad_variable(index, k % n, n);
+    let rowsums = [rowsum.0, rowsum.1, rowsum.2, rowsum.3];
 
- // TODO refactor these functions
-fn op_left_plus(seq : &Vec<i8>, k : usize, t : usize, n : usize) -> i8{
-    seq[((k+n) as isize - t as isize) as usize % n] + seq[(k+t) % n]
-}
+    // A sequence of rowsum r has (n + r)/2 values equal to +1
+    for index in 0..4 {
+        let count = n as isize + rowsums[index];
+        if count % 2 != 0 || count < 0 || count > 2 * n as isize {
+            formula.add_clause(vec![]);
+            continue;
+        }
+        formula.add_exactly((0..n).map(|k| x(index, k)).collect(), (count / 2) as usize);
+    }
 
-fn op_right_plus(seq : &Vec<i8>, k : usize, t : usize, n : usize) -> i8{
-    seq[(k+t) % n] + seq[((k+n) as isize - t as isize) as usize % n]
+    add_quad_conditions(&mut formula, seqtype, n);
+    formula
 }
 
-fn op_left_minus(seq : &Vec<i8>, k : usize, t : usize, n : usize) -> i8{
-    seq[((k+n) as isize - t as isize) as usize % n] - seq[(k+t) % n]
-}
-fn op_right_minus(seq : &Vec<i8>, k : usize, t : usize, n : usize) -> i8{
-    seq[(k+t) % n] - seq[((k+n) as isize - t as isize) as usize % n]
+pub fn generate_incremental_formula(seqtype : SequenceType, n : usize) -> (Formula, Vec<Vec<Lit>>) {
+    // generates the formula of generate_formula without the rowsums, and the outputs of a counter of the +1 values of
+    // each sequence, from which rowsum_assumptions gives the assumptions for every rowsum
+    let mut formula = Formula::new(4 * n);
+    let counters = (0..4).map(|index| formula.add_counter((0..n).map(|k| quad_variable(index, k, n)).collect())).collect();
+    add_quad_conditions(&mut formula, seqtype, n);
+    (formula, counters)
 }
 
-
-
-pub fn equations_crosscorrelation(seq1 : &Vec<i8>, op_type1 : OpType, seq2 : &Vec<i8>, op_type2 : OpType, add_type : AddType) -> String {
-    // generates the equations given by applying the crosscorrelation properties on each sequence with their specific operator: + or -
-
-    let op1 = match op_type1 { // op1 is a function corresponding to either a + or a - operator
-        OpType::LeftPlus => { op_left_plus}
-        OpType::LeftMinus => { op_left_minus}
-        OpType::RightPlus => { op_right_plus}
-        OpType::RightMinus => { op_right_minus}
-    };
-
-    let op2 = match (op_type2, add_type) { // op2 is a function corresponding to either a + or a - operator
-        (OpType::LeftPlus, AddType::Plus) => { |s,k,i,n| op_left_plus(s,k,i,n)}
-        (OpType::LeftMinus, AddType::Plus) => { |s,k,i,n| op_left_minus(s,k,i,n)}
-        (OpType::RightPlus, AddType::Plus) => { |s,k,i,n| op_right_plus(s,k,i,n)}
-        (OpType::RightMinus, AddType::Plus) => { |s,k,i,n| op_right_minus(s,k,i,n)}
-        (OpType::LeftPlus, AddType::Minus) => { |s,k,i,n|  -op_left_plus(s,k,i,n)}
-        (OpType::LeftMinus, AddType::Minus) => { |s,k,i,n| -op_left_minus(s,k,i,n)}
-        (OpType::RightPlus, AddType::Minus) => { |s,k,i,n| -op_right_plus(s,k,i,n)}
-        (OpType::RightMinus, AddType::Minus) => { |s,k,i,n| -op_right_minus(s,k,i,n)}
-    };
-
-    let n = seq1.len();
-    let mut values = vec![0 ; 2*n];
-    let mut result = "".to_string();
-
-    for t in 0..n { 
-        // computes the n equations given by the crosscorrelation property
-        
-        for k in 0..n {
-            values[k] = op1(seq1,k,t,n); 
+pub fn rowsum_assumptions(counters : &Vec<Vec<Lit>>, n : usize, rowsum : &Quad) -> Option<Vec<Lit>> {
+    // the outputs of the counters that force the sequences to have the rowsums, None if a rowsum is impossible
+    let mut assumptions = vec![];
+    for (index, r) in [rowsum.0, rowsum.1, rowsum.2, rowsum.3].into_iter().enumerate() {
+        let count = n as isize + r;
+        if count % 2 != 0 || count < 0 || count > 2 * n as isize {
+            return None;
         }
-        
-        for k in 0..n {
-            values[k+n] = op2(seq2,k,t,n); 
+        let count = (count / 2) as usize;
+        if count > 0 {
+            assumptions.push(counters[index][count - 1]);
         }
-        
-        let mut rightside_value = 0;
-        for k in 0..2*n {
-            // we convert the variables from {+-1} to {0,1} by applying x -> 2x-1
-            // Then we divide all the values by 2
-            rightside_value += (values[k]/2) as isize;
+        if count < n {
+            assumptions.push(-counters[index][count]);
         }
-
-        result += &generate_equation_from(&values, rightside_value);
     }
-
-    result
+    Some(assumptions)
 }
 
+fn add_quad_conditions(formula : &mut Formula, seqtype : SequenceType, n : usize) {
+    let x = |index : usize, k : usize| quad_variable(index, k % n, n);
+    let tags = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
 
-pub fn generate_equation_from(coefficients : &Vec<i8>, rightside_value : isize) -> String{
-    // generates the equation given by the specific values
-    // The value on the right side of the equation is computed automatically unless specified otherwise
-
-
-    let mut result = "".to_string();
-    let mut variable_counter = 1; // keeps track of the number of variable
-    let mut all_zero_coeff = true;
-
-
-    for &elm in coefficients.iter() {
-        if elm > 0 {
-            if !all_zero_coeff {
-                result += "+";
+    // The symmetric and skew-type sequences, a skew-type sequence having an odd length
+    let skew = seqtype.skew_sequences();
+    for index in 0..4 {
+        let is_skew = skew.contains(&tags[index]);
+        if !is_skew && !matches!(seqtype, SequenceType::Williamson | SequenceType::Good | SequenceType::Best) {
+            continue;
+        }
+        if is_skew {
+            formula.add_clause(vec![x(index, 0)]);
+            if n % 2 == 0 {
+                formula.add_clause(vec![]);
             }
-            result += &(elm.to_string() + " x" + &variable_counter.to_string() + " ");
-            all_zero_coeff = false;
         }
-        if elm < 0 {
-            result += &(elm.to_string() + " x" + &variable_counter.to_string() + " ");
-            all_zero_coeff = false;
+        for t in (1..n).filter(|&t| t < n - t) {
+            formula.add_equal(x(index, t), if is_skew {-x(index, n - t)} else {x(index, n - t)});
         }
-        variable_counter += 1;
-    }
-
-    if all_zero_coeff {
-        // the values were all 0, so nothing can be learned from it
-        return "".to_string();
-    }
-
-    result += &("= ".to_owned() + &rightside_value.to_string() + &";"  + &"\n");
-
-
-    result
-}
-
-
-
-
-fn equations_rowsum(n : usize, rs1 : isize, rs2 : isize) -> String {
-    let mut result = "".to_string();
-    let mut coeff1 = vec![0;2*n];
-    let mut coeff2 = vec![0;2*n];
-    for i in 0..n {
-        coeff1[i] = 2;
-        coeff2[2*n-1-i] = 2;
-    }
-
-    result += &(generate_equation_from(&coeff1, rs1 + n as isize));
-    result += &(generate_equation_from(&coeff2, rs2 + n as isize));
-
-    result
-}
-
-
-
-
-
-
-
-fn equations_autocorrelation(seq1 : &Vec<i8>, seq2 : &Vec<i8>) -> String {
-    let mut result = "".to_string();
-    let n = seq1.len();
-
-    for offset in 1..n {
-        let rightside_value = periodic_autocorrelation(seq1, offset) + periodic_autocorrelation(seq2, offset);   
-        result += &generate_equation_autocorrelation(n, offset, -rightside_value);
     }
 
-    result
-
-}
-
-fn generate_equation_autocorrelation(size : usize, offset : usize, mut rightside_value : isize) -> String {
-    let mut result = "".to_string();
-    let mut first_time = true;
-    for index in 1..=size {
-
-        if !first_time {
-            result += "+";
-        }
-
-        let index_offset = (index + offset) % size;
-        result += &("4 x".to_string() + &(index).to_string() + &" x" + &index_offset.to_string() + " ");
-        result += &("-2 x".to_string() + &(index).to_string() + &" -2 x" + &index_offset.to_string() + " ");
-
-        result += &("+4 x".to_string() + &(index + size).to_string() + &" x" + &(index_offset + size).to_string() + " ");
-        result += &("-2 x".to_string() + &(index + size).to_string() + &" -2 x" + &(index_offset + size).to_string() + " ");
-
-        rightside_value += -2;
-
-        first_time = false;
+    // The sequences are periodic complementary
+    for t in 1..=n/2 {
+        let terms = (0..4).flat_map(|index| (0..n).map(move |k| (1, x(index, k), x(index, k + t)))).collect();
+        add_product_sum(formula, &terms, 0);
     }
-    result += &("= ".to_string() + &rightside_value.to_string() + &";\n");
-
-    result
-}
-
-
-
-
-
-
-
-
-fn op_minus(seqa : &Vec<i8>, seqb : &Vec<i8>, offset: usize) -> isize{
-    cross_correlation(seqa, seqb, offset) - cross_correlation(seqa, seqb, offset)
-}
-
-fn op_plus(seqa : &Vec<i8>, seqb : &Vec<i8>, offset: usize) -> isize{
-    cross_correlation(seqa, seqb, offset) + cross_correlation(seqa, seqb, offset)
-}
-
 
-
-fn equations_nonlinear_crosscorrelation(seq1 : &Vec<i8>, seq2 : &Vec<i8>, op_type1 : OpType, op_type2 : OpType, add_type : AddType) -> String {
-    let mut result = "".to_string();
-    let n = seq1.len();
-
-
-    let op = match op_type1 {
-        OpType::LeftPlus | OpType::RightPlus => {|seq1, seq2, offset| op_plus(seq1, seq2, offset)}
-        OpType::LeftMinus => {|seq1, seq2, offset| op_minus(seq1, seq2, offset)}
-        OpType::RightMinus => {|seq1, seq2, offset| op_minus(seq2, seq1, offset)}
-    };
-    let op2 = match add_type {
-        AddType::Minus => { |x : isize| -x}
-        AddType::Plus => { |x : isize| x}
-    };
-
-
-
-    for offset in 1..n {
-        let rightside_value = op2(-op(seq1,seq2, offset));   
-        result += &generate_equation_non_linear_crosscorrelation(n, offset, &op_type2, -rightside_value);
+    // The conditions on the cross correlations, see QuadSeq::verify_cross_correlation and QuadSeq::is_amicable
+    match seqtype {
+        SequenceType::QuaternionType => {
+            for t in 0..n {
+                for (a, b, c, d) in [(0, 1, 3, 2), (0, 2, 1, 3), (0, 3, 2, 1)] {
+                    // CC(a, b) - CC(b, a) = CC(c, d) - CC(d, c)
+                    let terms = [cross_correlation_terms(a, b, t, n, 1), cross_correlation_terms(b, a, t, n, -1),
+                                 cross_correlation_terms(c, d, t, n, -1), cross_correlation_terms(d, c, t, n, 1)].concat();
+                    add_product_sum(formula, &terms, 0);
+                }
+            }
+        },
+        SequenceType::WilliamsonType => {
+            for (t, a, b) in iproduct!(1..n, 0..4, 0..4) {
+                if a < b {
+                    let terms = [cross_correlation_terms(a, b, t, n, 1), cross_correlation_terms(b, a, t, n, -1)].concat();
+                    add_product_sum(formula, &terms, 0);
+                }
+            }
+        },
+        SequenceType::Williamson | SequenceType::Good | SequenceType::Best => {},
+        _ => {panic!("The formulas aren't implemented for {}", seqtype.to_string())}
     }
 
-    result
-
-}
-
-
-
-
-fn generator_left_minus(index : usize, offset : usize, size : usize) -> String {
-    let index_offset = (index + offset) % size;
-
-    let mut result = "".to_string();
-    result += &("4 x".to_string() + &(index).to_string() + &" x" + &(index_offset + size).to_string() + " ");
-    result += &("-2 x".to_string() + &(index).to_string() + &" -2 x" + &(index_offset + size).to_string() + " ");
-
-    result += &("-4 x".to_string() + &(index + size).to_string() + &" x" + &index_offset.to_string() + " ");
-    result += &("+2 x".to_string() + &(index + size).to_string() + &" +2 x" + &index_offset.to_string() + " ");
-
-    result
-}
-
-fn generator_right_minus(index : usize, offset : usize, size : usize) -> String {
-    let index_offset = (index + offset) % size;
-
-    let mut result = "".to_string();
-    result += &("4 x".to_string() + &(index + size).to_string() + &" x" + &index_offset.to_string() + " ");
-    result += &("-2 x".to_string() + &(index + size).to_string() + &" -2 x" + &index_offset.to_string() + " ");
-
-    result += &("-4 x".to_string() + &(index).to_string() + &" x" + &(index_offset + size).to_string() + " ");
-    result += &("+2 x".to_string() + &(index).to_string() + &" +2 x" + &(index_offset + size).to_string() + " ");
-
-    result
-}
-
-fn generator_plus(index : usize, offset : usize, size : usize) -> String {
-    let index_offset = (index + offset) % size;
-
-    let mut result = "".to_string();
-    result += &("4 x".to_string() + &(index).to_string() + &" x" + &(index_offset + size).to_string() + " ");
-    result += &("-2 x".to_string() + &(index).to_string() + &" -2 x" + &(index_offset + size).to_string() + " ");
-
-    result += &("+4 x".to_string() + &(index + size).to_string() + &" x" + &index_offset.to_string() + " ");
-    result += &("-2 x".to_string() + &(index + size).to_string() + &" -2 x" + &index_offset.to_string() + " ");
-
-    result
-}
-
-
-
-
-
-fn generate_equation_non_linear_crosscorrelation(size : usize, offset : usize, op_type : &OpType, rightside_value : isize) -> String {
-    let mut result = "".to_string();
-    let mut first_time = true;
-
-    let line_generator = match *op_type {
-        OpType::LeftMinus => {generator_left_minus}
-        OpType::RightMinus => {generator_right_minus}
-        OpType::LeftPlus => {generator_plus}
-        OpType::RightPlus => {generator_plus}
+    // The permutations of the indices k -> a*k + s below act on the four sequences at once and keep every condition
+    // and the rowsums, so W can be assumed to be lexicographically smaller than its images
+    let shifts : Vec<usize> = match seqtype {
+        SequenceType::QuaternionType | SequenceType::WilliamsonType => (0..n).collect(),
+        SequenceType::Williamson if n % 2 == 0 => vec![0, n/2],
+        _ => vec![0]
     };
-
-
-    for index in 1..=size {
-
-        if !first_time {
-            result += "+";
+    let w : Vec<Lit> = (0..n).map(|k| x(0, k)).collect();
+    for (a, s) in iproduct!((1..=n).filter(|&a| coprime(a, n)), shifts) {
+        if (a, s) != (1, 0) {
+            let image : Vec<Lit> = (0..n).map(|k| x(0, a * k + s)).collect();
+            formula.add_lex_leq(&w, &image);
         }
-
-        result += &line_generator(index, offset, size);
-
-        first_time = false;
     }
-    result += &("= ".to_string() + &rightside_value.to_string() + &";\n");
-
-    result
 }
-
-
-
-
-fn additional_comment(tag1 : &SequenceTag, tag2 : &SequenceTag) -> String {
-    "\n* x1 ... xn is ".to_string() + &tag1.to_string() + &", xn+1 ... x2n is " + &tag2.to_string() + &"\n"
-}
\ No newline at end of file
@@ -6,6 +6,8 @@ use itertools::iproduct;
 use crate::{find::find_unique::reduce_to_canonical_reps, sequences::{rowsum::has_sorted_rowsums, symmetries::SequenceType}};
 
 use super::williamson::{QuadSeq, SequenceTag};
+use super::{hurwitz::Hurwitz, sequence::{QS, Q24}};
+use cgmath::Quaternion;
 
 
 
@@ -137,6 +139,9 @@ pub fn equiv(seq1 : &QuadSeq, seq2 : &QuadSeq, seqtype : SequenceType) -> bool {
 
             reduce_to_canonical_reps(&vec![sn_ss_canon1, sn_ss_canon2], seqtype).len() == 1
         }
+        SequenceType::Good | SequenceType::Best => {
+            generate_canonical_representative(seq1, seqtype) == generate_canonical_representative(seq2, seqtype)
+        }
         _ => {false}
     }
 }
@@ -244,6 +249,41 @@ pub fn generate_equivalence_class_fast(seq : &QuadSeq, symmetries : &HashSet<Qua
 
 
 
+// * Canonical first sequences
+// The equivalences preserving the rowsums act on the first sequence W alone, so every quadruple is equivalent
+// to one with the same rowsums whose W is the smallest of its orbit. The search can thus be restricted to these W
+
+// Returns the permutations of the entries of W induced by the equivalences preserving the rowsums
+pub fn first_sequence_permutations(len : usize, seqtype : SequenceType) -> Vec<Vec<usize>> {
+    // Every sequence is labelled by the indices of its entries, the equivalences we use never mix the sequences
+    let indices : Vec<i8> = (1..=len).map(|i| i as i8).collect();
+    let mut labels = QuadSeq::new(len);
+    labels.set_all_values((&indices, &indices, &indices, &indices));
+
+    let permutations : HashSet<Vec<usize>> = generate_equivalence_class(&labels, seqtype, &seqtype.rowsum_preserving_equivalences(), true).iter()
+        .map(|quad| quad.sequence(SequenceTag::W).iter().map(|&elm| elm as usize - 1).collect())
+        .collect();
+    permutations.into_iter().collect()
+}
+
+pub fn is_canonical_first_sequence(seq : &Vec<i8>, permutations : &Vec<Vec<usize>>) -> bool {
+    permutations.iter().all(|perm| {
+        let image : Vec<i8> = perm.iter().map(|&i| seq[i]).collect();
+        !seq_less_than(&image, seq)
+    })
+}
+
+// Keeps the sequences that are the smallest of their orbit under the equivalences preserving the rowsums
+pub fn canonical_first_sequences(sequences : Vec<Vec<i8>>, seqtype : SequenceType) -> Vec<Vec<i8>> {
+    let permutations = match sequences.first() {
+        Some(seq) => first_sequence_permutations(seq.len(), seqtype),
+        None => return sequences
+    };
+    sequences.into_iter().filter(|seq| is_canonical_first_sequence(seq, &permutations)).collect()
+}
+
+
+
 pub fn generate_equivalent_quad_seqs(quad_seq_list : &Vec<QuadSeq>, seqtype : SequenceType) -> Vec<QuadSeq> {
 
     let mut result = HashSet::new();
@@ -465,6 +505,30 @@ pub fn equivalent_reorder(seq : &QuadSeq, seqtype : SequenceType, symmetry_group
     res
 }
 
+// Reorder the skew-type sequences among themselves, and the symmetric ones among themselves (good and best matrices)
+pub fn equivalent_reorder_same_kind(seq : &QuadSeq, seqtype : SequenceType, symmetry_group : bool) -> HashSet<QuadSeq> {
+    let mut res : HashSet<QuadSeq> = HashSet::new();
+    res.insert(seq.clone());
+
+    let skew = seqtype.skew_sequences();
+    let couples = [(SequenceTag::W, SequenceTag::X), (SequenceTag::W, SequenceTag::Y), (SequenceTag::W, SequenceTag::Z), (SequenceTag::X, SequenceTag::Y), (SequenceTag::X, SequenceTag::Z), (SequenceTag::Y, SequenceTag::Z)];
+
+    for couple in couples.into_iter().filter(|(tag1, tag2)| skew.contains(tag1) == skew.contains(tag2)) {
+        let mut new_seq = seq.clone();
+
+        swap(&mut new_seq, couple.0, couple.1);
+
+        // Don't want to verify sequence properties of symmetry groups, as they will not meet them
+        if !symmetry_group {
+            debug_assert!(new_seq.verify(seqtype), "equivalent_reorder_same_kind function produced invalid {}", seqtype.to_string());
+        }
+
+        res.insert(new_seq);
+    }
+
+    res
+}
+
 // Swap any two pairs of A, B, C, D
 pub fn equivalent_double_reorder(seq : &QuadSeq, seqtype : SequenceType, symmetry_group : bool) -> HashSet<QuadSeq> {
     // computes all equivalent sequences by reordering, two swaps at a time
@@ -623,6 +687,30 @@ pub fn equivalent_reverse(seq : &QuadSeq, seqtype : SequenceType, symmetry_group
 }
 
 
+// Transpose the circulant matrix of any of A, B, C, or D, i.e. send the entry of index i to the index -i mod n
+// The periodic autocorrelations of a sequence don't change, and a skew-type sequence stays skew-type
+pub fn equivalent_transpose(seq : &QuadSeq, seqtype : SequenceType, symmetry_group : bool) -> HashSet<QuadSeq> {
+    let mut res : HashSet<QuadSeq> = HashSet::new();
+    res.insert(seq.clone());
+    let n = seq.size();
+
+    for tag in [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z] {
+        let tag_seq = seq.sequence(tag);
+        let transposed : Vec<i8> = (0..n).map(|index| tag_seq[(n - index) % n]).collect();
+        let mut new_seq = seq.clone();
+        new_seq.set_sequence(&transposed, &tag);
+
+        // Don't want to verify sequence properties of symmetry groups, as they will not meet them
+        if !symmetry_group {
+            debug_assert!(new_seq.verify(seqtype), "equivalent_transpose function produced invalid {}", seqtype.to_string());
+        }
+
+        res.insert(new_seq);
+    }
+
+    res
+}
+
 
 pub fn negated(seq : &Vec<i8>) -> Vec<i8> {
     let mut s = vec![];
@@ -677,6 +765,27 @@ pub fn equivalent_negate(seq : &QuadSeq, seqtype : SequenceType, symmetry_group
     res
 }
 
+// Negate all the entries of any of the symmetric sequences (good and best matrices), a negated skew-type sequence isn't skew-type anymore
+pub fn equivalent_negate_symmetric(seq : &QuadSeq, seqtype : SequenceType, symmetry_group : bool) -> HashSet<QuadSeq> {
+    let mut res : HashSet<QuadSeq> = HashSet::new();
+    res.insert(seq.clone());
+
+    let skew = seqtype.skew_sequences();
+    for tag in [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].into_iter().filter(|tag| !skew.contains(tag)) {
+        let mut new_seq = seq.clone();
+        new_seq.set_sequence(&negated(&seq.sequence(tag)), &tag);
+
+        // Don't want to verify sequence properties of symmetry groups, as they will not meet them
+        if !symmetry_group {
+            debug_assert!(new_seq.verify(seqtype), "equivalent_negate_symmetric function produced invalid {}", seqtype.to_string());
+        }
+
+        res.insert(new_seq);
+    }
+
+    res
+}
+
 // Negate any two sequences of A, B, C, or D
 pub fn equivalent_double_negate(seq : &QuadSeq, seqtype : SequenceType, symmetry_group : bool) -> HashSet<QuadSeq> {
     // computes all equivalent sequences by negation of two sequences
@@ -858,3 +967,144 @@ pub fn equivalent_disjoint_swaps(seq : &QuadSeq, seqtype : SequenceType, symmetr
 
     res
 }
+
+
+
+// * Equivalences of odd-perfect quaternion sequences
+// The odd periodic autocorrelation of (q_0, ..., q_{n-1}) is half the periodic autocorrelation of its negaperiodic
+// extension e = (q_0, ..., q_{n-1}, -q_0, ..., -q_{n-1}), so the following operations preserve odd-perfectness :
+//  * the negacyclic shift (q_1, ..., q_{n-1}, -q_0), whose n-th power is the negation
+//  * the multiplication of every value by a unit on the left, which conjugates the autocorrelations by the unit,
+//    or on the right, which keeps them
+//  * the reversal, which conjugates the autocorrelations
+//  * the decimations q_i -> e_{ki mod 2n} for k odd and coprime with n, which permute the shifts of e
+// The classes are computed over the 24 Hurwitz units, which are closed under these operations, and only their
+// sequences over the alphabet of the search are kept
+
+fn negacyclic_shift(seq : &Vec<Hurwitz>) -> Vec<Hurwitz> {
+    let mut result : Vec<Hurwitz> = seq[1..].to_vec();
+    result.push(-seq[0]);
+    result
+}
+
+fn decimate(seq : &Vec<Hurwitz>, k : usize) -> Vec<Hurwitz> {
+    let n = seq.len();
+    (0..n).map(|i| {
+        let j = (k * i) % (2 * n);
+        if j < n {seq[j]} else {-seq[j - n]}
+    }).collect()
+}
+
+// The sequences obtained from seq by one of the operations above
+pub fn odd_perfect_equivalents(seq : &Vec<Hurwitz>) -> Vec<Vec<Hurwitz>> {
+    let n = seq.len();
+    let units : Vec<Hurwitz> = Q24.iter().map(Hurwitz::from_quaternion).collect();
+
+    let mut result = vec![negacyclic_shift(seq), seq.iter().rev().cloned().collect()];
+    for unit in &units {
+        result.push(seq.iter().map(|&q| *unit * q).collect());
+        result.push(seq.iter().map(|&q| q * *unit).collect());
+    }
+    for k in (1..2*n).step_by(2).filter(|&k| coprime(k, n)) {
+        result.push(decimate(seq, k));
+    }
+    result
+}
+
+pub fn odd_perfect_class(seq : &Vec<Hurwitz>) -> HashSet<Vec<Hurwitz>> {
+    let mut class : HashSet<Vec<Hurwitz>> = HashSet::new();
+    let mut to_visit = vec![seq.clone()];
+    class.insert(seq.clone());
+
+    while let Some(current) = to_visit.pop() {
+        for equivalent in odd_perfect_equivalents(&current) {
+            if class.insert(equivalent.clone()) {
+                to_visit.push(equivalent);
+            }
+        }
+    }
+    class
+}
+
+pub fn reduce_odd_perfect(sequences : &Vec<QS>, alphabet : &Vec<Quaternion<f32>>) -> Vec<QS> {
+    // Reduces a list of odd-perfect sequences over the alphabet to one representative per class,
+    // the smallest sequence over the alphabet for the order of Q24
+    let representatives = reduce_to_class_minimums(sequences, alphabet, odd_perfect_class);
+    debug_assert!(representatives.iter().all(|qs| qs.is_odd_perfect()));
+    representatives
+}
+
+fn reduce_to_class_minimums(sequences : &Vec<QS>, alphabet : &Vec<Quaternion<f32>>, class_of : fn(&Vec<Hurwitz>) -> HashSet<Vec<Hurwitz>>) -> Vec<QS> {
+    let alphabet : Vec<Hurwitz> = alphabet.iter().map(Hurwitz::from_quaternion).collect();
+    let key = |seq : &Vec<Hurwitz>| -> Vec<usize> {
+        seq.iter().map(|q| Q24.iter().position(|u| Hurwitz::from_quaternion(u) == *q).expect("Not a unit")).collect()
+    };
+
+    let mut seen : HashSet<Vec<Hurwitz>> = HashSet::new();
+    let mut representatives = vec![];
+    for qs in sequences {
+        let seq : Vec<Hurwitz> = qs.values().iter().map(Hurwitz::from_quaternion).collect();
+        if seen.contains(&seq) {
+            continue;
+        }
+
+        let class : Vec<Vec<Hurwitz>> = class_of(&seq).into_iter().filter(|s| s.iter().all(|q| alphabet.contains(q))).collect();
+        let representative = class.iter().min_by_key(|s| key(s)).expect("Empty class").clone();
+        seen.extend(class);
+
+        let mut qs = QS::new(representative.len(), None);
+        qs.set_values(representative.iter().map(|q| q.to_quaternion()).collect());
+        representatives.push(qs);
+    }
+    representatives
+}
+
+
+
+// * Equivalences of perfect quaternion sequences
+// The same operations as above with the cyclic shift instead of the negacyclic one, and the decimations
+// q_i -> q_{ki mod n} for k coprime with n, preserve perfectness. They apply to the sequences over Q24
+// which don't come from a quadruple of binary sequences
+
+fn cyclic_shift(seq : &Vec<Hurwitz>) -> Vec<Hurwitz> {
+    let mut result : Vec<Hurwitz> = seq[1..].to_vec();
+    result.push(seq[0]);
+    result
+}
+
+pub fn perfect_equivalents(seq : &Vec<Hurwitz>) -> Vec<Vec<Hurwitz>> {
+    let n = seq.len();
+    let units : Vec<Hurwitz> = Q24.iter().map(Hurwitz::from_quaternion).collect();
+
+    let mut result = vec![cyclic_shift(seq), seq.iter().rev().cloned().collect()];
+    for unit in &units {
+        result.push(seq.iter().map(|&q| *unit * q).collect());
+        result.push(seq.iter().map(|&q| q * *unit).collect());
+    }
+    for k in (2..n).filter(|&k| coprime(k, n)) {
+        result.push((0..n).map(|i| seq[(k * i) % n]).collect());
+    }
+    result
+}
+
+pub fn perfect_class(seq : &Vec<Hurwitz>) -> HashSet<Vec<Hurwitz>> {
+    let mut class : HashSet<Vec<Hurwitz>> = HashSet::new();
+    let mut to_visit = vec![seq.clone()];
+    class.insert(seq.clone());
+
+    while let Some(current) = to_visit.pop() {
+        for equivalent in perfect_equivalents(&current) {
+            if class.insert(equivalent.clone()) {
+                to_visit.push(equivalent);
+            }
+        }
+    }
+    class
+}
+
+pub fn reduce_perfect(sequences : &Vec<QS>, alphabet : &Vec<Quaternion<f32>>) -> Vec<QS> {
+    // Reduces a list of perfect sequences over the alphabet to one representative per class
+    let representatives = reduce_to_class_minimums(sequences, alphabet, perfect_class);
+    debug_assert!(representatives.iter().all(|qs| qs.is_perfect()));
+    representatives
+}
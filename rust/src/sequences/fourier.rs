@@ -4,17 +4,19 @@ use fftw::plan::*;
 use fftw::types::*;
 use itertools::iproduct;
 use num_complex::Complex;
-use std::sync::{OnceLock, Mutex};
+use std::{cell::Cell, collections::HashMap, sync::{OnceLock, Mutex}};
 
-static R2C_PLAN : OnceLock<Mutex<R2CPlan64>> = OnceLock::new();
-static C2R_PLAN : OnceLock<Mutex<C2RPlan64>> = OnceLock::new();
+// The FFTW plans are created once for each length, the zero-padded DFT uses other lengths than the sequences
+static R2C_PLAN : OnceLock<Mutex<HashMap<usize, R2CPlan64>>> = OnceLock::new();
+static C2R_PLAN : OnceLock<Mutex<HashMap<usize, C2RPlan64>>> = OnceLock::new();
 
 pub fn dft_sequence(seq : &Vec<i8>) -> Vec<Complex<f64>>{
     // returns the dft of the sequence
     let n = seq.len();
     
-    let mutex = R2C_PLAN.get_or_init(|| Mutex::new(R2CPlan::aligned(&[n], Flag::MEASURE).expect("Failed to create Mutex for FFTW plan")));
-    let mut plan = mutex.lock().unwrap();
+    let mutex = R2C_PLAN.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut plans = mutex.lock().unwrap();
+    let plan = plans.entry(n).or_insert_with(|| R2CPlan::aligned(&[n], Flag::MEASURE).expect("Failed to create FFTW plan"));
 
     let mut a = AlignedVec::new(n);
     let mut b = AlignedVec::new(n/2+1);
@@ -27,8 +29,9 @@ pub fn dft_sequence(seq : &Vec<i8>) -> Vec<Complex<f64>>{
 }
 
 pub fn inverse_dft(freq: &Vec<Complex<f64>>, n: usize) -> Vec<f64> {
-    let mutex = C2R_PLAN.get_or_init(|| Mutex::new(C2RPlan::aligned(&[n], Flag::MEASURE).expect("Failed to create Mutex for FFTW plan")));
-    let mut plan = mutex.lock().unwrap();
+    let mutex = C2R_PLAN.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut plans = mutex.lock().unwrap();
+    let plan = plans.entry(n).or_insert_with(|| C2RPlan::aligned(&[n], Flag::MEASURE).expect("Failed to create FFTW plan"));
     
     //let plan = C2R_PLAN.get_or_init(|| C2RPlan::aligned(&[n], Flag::MEASURE).expect("Failed to create FFT plan"));
     let mut a = AlignedVec::new(n / 2 + 1);
@@ -43,19 +46,6 @@ pub fn inverse_dft(freq: &Vec<Complex<f64>>, n: usize) -> Vec<f64> {
 }
 
 
-pub fn iter_over_filtered_dft<'a>(sequences : &'a Vec<Vec<i8>>, bound : f64) -> impl std::iter::Iterator<Item = &'a Vec<i8>> {
-    let bound_sqr = bound * bound;
-    sequences.iter()
-        .filter(move |seq| {
-            let dft = dft_sequence(seq);
-            for elm in dft {
-                if elm.norm_sqr() > bound_sqr {return false;}
-            }
-            true
-        })
-}
-
-
 pub fn iter_over_filtered_couples<'a>(sequences1 : &'a Vec<Vec<i8>>, sequences2 : &'a Vec<Vec<i8>>, bound : f64) -> impl std::iter::Iterator<Item = (&'a Vec<i8>, &'a Vec<i8>)> {
     let couples = iproduct!(sequences1, sequences2);
     couples
@@ -87,35 +77,173 @@ pub fn iter_over_enumerate_filtered_couples<'a>(sequences1 : &'a Vec<Vec<i8>>, s
 pub struct SequencePairData<'a> {
     pub seq_enum1: (usize, &'a Vec<i8>),
     pub seq_enum2: (usize, &'a Vec<i8>),
-    pub dft1: Vec<Complex<f64>>,
-    pub norm1: Vec<f64>,
-    pub dft2: Vec<Complex<f64>>,
-    pub norm2: Vec<f64>,
+    pub dft1: &'a Vec<Complex<f64>>,
+    pub norm1: &'a Vec<f64>,
+    pub dft2: &'a Vec<Complex<f64>>,
+    pub norm2: &'a Vec<f64>,
+}
+
+
+// * Spectral pruning
+// The PSDs of the four sequences of a quadruple add up to 4p at every frequency, so a pair can only be completed if
+// |DFT1|^2 + |DFT2|^2 <= 4p at every frequency, and each sequence alone must satisfy |DFT|^2 <= 4p.
+// The PSDs are computed once per sequence, the sequences failing the single bound are removed before forming the pairs,
+// and the pairs are then checked against the pair bound. The frequency 0 is skipped since the rowsums are already fixed.
+// Sequences of different lengths are padded with zeros to the longest length, so that their PSDs are compared at the same frequencies
+
+#[derive(Clone, Copy)]
+pub struct SpectralFilter {
+    pub slack : f64,            // added to the bound, a positive slack guards against floating point errors
+    pub oversampling : usize    // with k > 1, the pairs are also checked at the k*n frequencies of the zero-padded DFT
 }
 
-pub fn iter_over_enumerate_filtered_couples_psds<'a>(sequences1: &'a Vec<Vec<i8>>, sequences2: &'a Vec<Vec<i8>>, bound: f64,) -> impl Iterator<Item = SequencePairData<'a>> {
-    let couples = iproduct!(sequences1.iter().enumerate(), sequences2.iter().enumerate());
+impl SpectralFilter {
 
-    couples.filter_map(move |(seq_enum1, seq_enum2)| {
-        let dft1 = dft_sequence(seq_enum1.1);
-        let dft2 = dft_sequence(seq_enum2.1);
+    pub fn new(slack : f64, oversampling : usize) -> SpectralFilter {
+        assert!(oversampling >= 1, "The oversampling factor must be positive");
+        SpectralFilter { slack, oversampling }
+    }
 
-        let norm1: Vec<f64> = dft1.iter().map(|elm| elm.norm_sqr()).collect();
-        let norm2: Vec<f64> = dft2.iter().map(|elm| elm.norm_sqr()).collect();
-        
-        let bound_exceeded = norm1.iter().enumerate().zip(norm2.iter().enumerate()).any(|((i1, n1), (i2, n2))| i1 != 0 && i2 != 0 && n1 + n2 > bound);
+    // The filter for periodic sequences : the bound only holds at the n frequencies of the DFT
+    pub fn periodic(slack : f64) -> SpectralFilter {
+        SpectralFilter::new(slack, 1)
+    }
+
+}
+
+struct SequenceSpectrum<'a> {
+    index : usize,
+    seq : &'a Vec<i8>,
+    dft : Vec<Complex<f64>>,
+    psd : Vec<f64>,
+    fine_psd : Vec<f64>         // PSD at the frequencies of the zero-padded DFT that are not frequencies of the DFT
+}
+
+// PSD of the sequence padded with zeros to length k*len, at the frequencies m/(k*len) with m not divisible by k
+fn fine_psd(seq : &Vec<i8>, len : usize, oversampling : usize) -> Vec<f64> {
+    padded_dft_sequence(seq, oversampling * len).iter().enumerate().filter(|(m, _)| m % oversampling != 0).map(|(_, elm)| elm.norm_sqr()).collect()
+}
+
+// Checks the single-sequence bound on the PSD of the sequence, at every frequency but 0
+pub fn psd_within_bound(seq : &Vec<i8>, bound : f64) -> bool {
+    dft_sequence(seq).iter().skip(1).all(|elm| elm.norm_sqr() <= bound)
+}
+
+fn exceeds(psd1 : &[f64], psd2 : &[f64], bound : f64) -> bool {
+    psd1.iter().zip(psd2.iter()).any(|(n1, n2)| n1 + n2 > bound)
+}
+
+pub struct SpectralPruning<'a> {
+    spectra1 : Vec<SequenceSpectrum<'a>>,
+    spectra2 : Vec<SequenceSpectrum<'a>>,
+    bound : f64,
+    removed_sequences : (usize, usize),
+    removed_pairs : Cell<u64>,
+    removed_fine_pairs : Cell<u64>
+}
+
+impl<'a> SpectralPruning<'a> {
+
+    pub fn new(sequences1 : &'a Vec<Vec<i8>>, sequences2 : &'a Vec<Vec<i8>>, bound : f64, filter : SpectralFilter) -> SpectralPruning<'a> {
+        let bound = bound + filter.slack;
+        let len = sequences1.iter().chain(sequences2.iter()).map(|seq| seq.len()).max().unwrap_or(0);
+
+        let spectra = |sequences : &'a Vec<Vec<i8>>| -> Vec<SequenceSpectrum<'a>> {
+            sequences.iter().enumerate().filter_map(|(index, seq)| {
+                let dft = padded_dft_sequence(seq, len);
+                let psd : Vec<f64> = dft.iter().map(|elm| elm.norm_sqr()).collect();
+                if psd.iter().skip(1).any(|&elm| elm > bound) {
+                    return None;
+                }
+                let fine_psd = if filter.oversampling > 1 {fine_psd(seq, len, filter.oversampling)} else {vec![]};
+                if fine_psd.iter().any(|&elm| elm > bound) {
+                    return None;
+                }
+                Some(SequenceSpectrum { index, seq, dft, psd, fine_psd })
+            }).collect()
+        };
+
+        let spectra1 = spectra(sequences1);
+        let spectra2 = spectra(sequences2);
+        let removed_sequences = (sequences1.len() - spectra1.len(), sequences2.len() - spectra2.len());
+
+        SpectralPruning { spectra1, spectra2, bound, removed_sequences, removed_pairs : Cell::new(0), removed_fine_pairs : Cell::new(0) }
+    }
+
+    // Iterates over the pairs of remaining sequences, with their indices in the original lists, that satisfy the pair bound
+    pub fn couples<'b>(&'b self) -> impl Iterator<Item = SequencePairData<'b>> + 'b {
+        iproduct!(self.spectra1.iter(), self.spectra2.iter()).filter_map(move |(spec1, spec2)| {
+            if exceeds(&spec1.psd[1..], &spec2.psd[1..], self.bound) {
+                self.removed_pairs.set(self.removed_pairs.get() + 1);
+                return None;
+            }
+            if exceeds(&spec1.fine_psd, &spec2.fine_psd, self.bound) {
+                self.removed_fine_pairs.set(self.removed_fine_pairs.get() + 1);
+                return None;
+            }
 
-        if bound_exceeded {
-            None
-        } else {
             Some(SequencePairData {
-                seq_enum1,
-                seq_enum2,
-                dft1,
-                norm1,
-                dft2,
-                norm2
+                seq_enum1 : (spec1.index, spec1.seq),
+                seq_enum2 : (spec2.index, spec2.seq),
+                dft1 : &spec1.dft,
+                norm1 : &spec1.psd,
+                dft2 : &spec2.dft,
+                norm2 : &spec2.psd
             })
-        }
-    })
+        })
+    }
+
+    pub fn removed_sequences(&self) -> (usize, usize) {
+        self.removed_sequences
+    }
+
+    pub fn removed_pairs(&self) -> u64 {
+        self.removed_pairs.get()
+    }
+
+    pub fn removed_fine_pairs(&self) -> u64 {
+        self.removed_fine_pairs.get()
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("removed {} + {} sequences with the single bound, {} pairs with the pair bound and {} pairs on the finer grid",
+            self.removed_sequences.0, self.removed_sequences.1, self.removed_pairs(), self.removed_fine_pairs())
+    }
+
+}
+
+
+// * Aperiodic and negaperiodic spectra
+// The aperiodic autocorrelations of a sequence of length n are the periodic autocorrelations of the sequence padded with zeros
+// to any length at least 2n-1, so the PSDs of aperiodic complementary sequences add up to a constant at every frequency of the padded DFT.
+// The negaperiodic autocorrelations correspond to the odd frequencies (2k+1)/2n, that is the odd indices of the DFT padded to 2n.
+// As for dft_sequence, the sequences are real so only the first half of the spectrum is returned
+
+pub fn padded_dft_sequence(seq : &Vec<i8>, len : usize) -> Vec<Complex<f64>> {
+    // returns the dft of the sequence padded with zeros to length len
+    assert!(len >= seq.len(), "The padded length must be at least the length of the sequence");
+    let mut padded = seq.clone();
+    padded.resize(len, 0);
+    dft_sequence(&padded)
+}
+
+pub fn aperiodic_psd(seq : &Vec<i8>, len : usize) -> Vec<f64> {
+    padded_dft_sequence(seq, len).iter().map(|elm| elm.norm_sqr()).collect()
+}
+
+pub fn aperiodic_autocorrelations_dft(seq : &Vec<i8>) -> Vec<isize> {
+    // returns the aperiodic autocorrelations of the sequence for the offsets 0..n, computed through the padded DFT
+    let n = seq.len();
+    let len = 2*n;
+    let psd : Vec<Complex<f64>> = aperiodic_psd(seq, len).iter().map(|&elm| Complex::new(elm, 0.)).collect();
+    inverse_dft(&psd, len).iter().take(n).map(|elm| elm.round() as isize).collect()
+}
+
+pub fn odd_dft_sequence(seq : &Vec<i8>) -> Vec<Complex<f64>> {
+    // returns the dft of the sequence at the odd frequencies (2k+1)/2n for 2k+1 <= n
+    padded_dft_sequence(seq, 2*seq.len()).into_iter().skip(1).step_by(2).collect()
+}
+
+pub fn negaperiodic_psd(seq : &Vec<i8>) -> Vec<f64> {
+    odd_dft_sequence(seq).iter().map(|elm| elm.norm_sqr()).collect()
 }
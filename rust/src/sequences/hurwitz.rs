@@ -0,0 +1,109 @@
+use std::ops;
+
+use cgmath::Quaternion;
+
+
+// * Exact arithmetic on Hurwitz quaternions
+// A Hurwitz quaternion has either four integer coordinates or four half-integer coordinates,
+// so we store its coordinates doubled, which gives four integers of the same parity.
+// The 24 units of the Hurwitz order are exactly the elements of Q24
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Hurwitz {
+    doubled : [i64; 4]      // (2s, 2x, 2y, 2z) for s + xi + yj + zk
+}
+
+pub const H0 : Hurwitz = Hurwitz { doubled : [0, 0, 0, 0] };
+pub const H1 : Hurwitz = Hurwitz { doubled : [2, 0, 0, 0] };
+
+
+impl Hurwitz {
+
+    pub fn from_doubled(doubled : [i64; 4]) -> Hurwitz {
+        assert!(doubled.iter().all(|c| (c - doubled[0]) % 2 == 0), "{:?} are not the doubled coordinates of a Hurwitz quaternion", doubled);
+        Hurwitz { doubled }
+    }
+
+    pub fn from_integer(n : i64) -> Hurwitz {
+        Hurwitz { doubled : [2*n, 0, 0, 0] }
+    }
+
+    // Panics if the quaternion is not a Hurwitz quaternion
+    pub fn from_quaternion(quat : &Quaternion<f32>) -> Hurwitz {
+        let doubled = [quat.s, quat.v.x, quat.v.y, quat.v.z].map(|c| {
+            let doubled = 2. * c;
+            assert!(doubled == doubled.round(), "{:?} is not a Hurwitz quaternion", quat);
+            doubled as i64
+        });
+        Hurwitz::from_doubled(doubled)
+    }
+
+    pub fn to_quaternion(&self) -> Quaternion<f32> {
+        let [s, x, y, z] = self.doubled.map(|c| c as f32 / 2.);
+        Quaternion::new(s, x, y, z)
+    }
+
+    pub fn doubled(&self) -> [i64; 4] {
+        self.doubled
+    }
+
+    pub fn conjugate(&self) -> Hurwitz {
+        let [s, x, y, z] = self.doubled;
+        Hurwitz { doubled : [s, -x, -y, -z] }
+    }
+
+    pub fn norm(&self) -> i64 {
+        // The sum of the squares of the doubled coordinates is 4 times the norm, and the norm of a Hurwitz quaternion is an integer
+        self.doubled.iter().map(|c| c * c).sum::<i64>() / 4
+    }
+
+    pub fn is_unit(&self) -> bool {
+        self.norm() == 1
+    }
+
+    // True for the units (+-1 +-i +-j +-k)/2, the ones with half-integer coordinates
+    pub fn is_half(&self) -> bool {
+        self.doubled[0] % 2 != 0
+    }
+
+}
+
+
+impl ops::Add<Hurwitz> for Hurwitz {
+    type Output = Hurwitz;
+
+    fn add(self, rhs: Hurwitz) -> Self::Output {
+        let mut doubled = self.doubled;
+        for (c, d) in doubled.iter_mut().zip(rhs.doubled) {
+            *c += d;
+        }
+        Hurwitz { doubled }
+    }
+}
+
+impl ops::Neg for Hurwitz {
+    type Output = Hurwitz;
+
+    fn neg(self) -> Self::Output {
+        Hurwitz { doubled : self.doubled.map(|c| -c) }
+    }
+}
+
+impl ops::Mul<Hurwitz> for Hurwitz {
+    type Output = Hurwitz;
+
+    fn mul(self, rhs: Hurwitz) -> Self::Output {
+        let [a1, b1, c1, d1] = self.doubled;
+        let [a2, b2, c2, d2] = rhs.doubled;
+
+        // Product of the doubled quaternions, which is 4 times the product
+        let product = [a1*a2 - b1*b2 - c1*c2 - d1*d2,
+                       a1*b2 + b1*a2 + c1*d2 - d1*c2,
+                       a1*c2 - b1*d2 + c1*a2 + d1*b2,
+                       a1*d2 + b1*c2 - c1*b2 + d1*a2];
+
+        // The Hurwitz quaternions are closed under multiplication, so the coordinates are all even
+        debug_assert!(product.iter().all(|c| c % 2 == 0));
+        Hurwitz::from_doubled(product.map(|c| c / 2))
+    }
+}
@@ -122,9 +122,9 @@ pub fn compute_cross_correlations_dft(dft1 : &Vec<Complex<f64>>, dft2 : &Vec<Com
 }
 
 
-pub fn compute_cross_psd_pair(dft1 : Vec<Complex<f64>>, dft2 : Vec<Complex<f64>>, tags : &(SequenceTag, SequenceTag), len : usize) -> Vec<Complex<f64>> {
-    let cross_psd1 = compute_cross_psd(&dft1, &dft2);
-    let cross_psd2 = compute_cross_psd(&dft2, &dft1);
+pub fn compute_cross_psd_pair(dft1 : &Vec<Complex<f64>>, dft2 : &Vec<Complex<f64>>, tags : &(SequenceTag, SequenceTag), len : usize) -> Vec<Complex<f64>> {
+    let cross_psd1 = compute_cross_psd(dft1, dft2);
+    let cross_psd2 = compute_cross_psd(dft2, dft1);
 
     let cross_at_offset : Box<dyn Fn(usize) -> Complex<f64>> = match tags {
         (SequenceTag::Z, _) | (SequenceTag::W, SequenceTag::X) | (SequenceTag::X, SequenceTag::Y) | (SequenceTag::Y, SequenceTag::W) => {
@@ -155,6 +155,50 @@ pub fn compute_cross_psd(dft1 : &Vec<Complex<f64>>, dft2 : &Vec<Complex<f64>>) -
 }
 
 
+// * Exact PSD/CPSD
+// The PSD and CPSD of a pair are linear in its periodic auto and cross correlations, which are integers.
+// We compute these integers exactly and evaluate the PSD/CPSD from them with a fixed table and summation order,
+// so that two pairs that match (same correlation values on both sides of the equation) always get bit-identical values,
+// and thus identical keys after rounding, no matter how close the values are to a half-integer
+
+// Evaluates sum_j values[j] cos(2 pi j k / n) for the frequencies k in 1..=n/2
+fn evaluate_cosines(values : &Vec<isize>) -> Vec<f64> {
+    let n = values.len();
+    let table : Vec<f64> = (0..n).map(|j| (2. * std::f64::consts::PI * j as f64 / n as f64).cos()).collect();
+    (1..=(n/2)).map(|k| values.iter().enumerate().map(|(j, &v)| v as f64 * table[(j * k) % n]).sum()).collect()
+}
+
+// Evaluates - sum_j values[j] sin(2 pi j k / n) for the frequencies k in 1..=n/2
+fn evaluate_sines(values : &Vec<isize>) -> Vec<f64> {
+    let n = values.len();
+    let table : Vec<f64> = (0..n).map(|j| (2. * std::f64::consts::PI * j as f64 / n as f64).sin()).collect();
+    (1..=(n/2)).map(|k| -values.iter().enumerate().map(|(j, &v)| v as f64 * table[(j * k) % n]).sum::<f64>()).collect()
+}
+
+// Same values as compute_psd_pair, computed from the exact autocorrelations of the sequences
+pub fn compute_exact_psd_pair(seq1 : &Vec<i8>, seq2 : &Vec<i8>, side : EquationSide) -> Vec<f64> {
+    // On the left we have PAF1 + PAF2, and on the right 4n - (PAF1 + PAF2) at offset 0 and -(PAF1 + PAF2) elsewhere.
+    // Both give 2n at offset 0
+    let mut autoc = vec![2 * seq1.len() as isize];
+    let complementary = compute_complementary_auto_correlations(seq1, seq2);
+    match side {
+        EquationSide::LEFT => autoc.extend(complementary.into_iter().map(|a| -a)),
+        EquationSide::RIGHT => autoc.extend(complementary)
+    }
+    evaluate_cosines(&autoc)
+}
+
+// Same values as the imaginary part of compute_cross_psd_pair, negated on the right side of the equation, computed from the exact cross correlations of the sequences
+pub fn compute_exact_cross_psd_pair(seq1 : &Vec<i8>, seq2 : &Vec<i8>, tags : &(SequenceTag, SequenceTag), side : EquationSide) -> Vec<f64> {
+    let complementary = compute_complementary_cross_correlations(seq1, seq2, tags);
+    let crossc = match side {
+        EquationSide::LEFT => complementary.into_iter().map(|c| -c).collect(),
+        EquationSide::RIGHT => complementary
+    };
+    evaluate_sines(&crossc)
+}
+
+
 pub fn compute_complementary_auto_correlations(seq1 : &Vec<i8>, seq2 : &Vec<i8>) -> Vec<isize> {
     let mut res = vec![];
     
@@ -1,19 +1,22 @@
 use cgmath::Quaternion;
+use itertools::iproduct;
+use num_complex::Complex;
 
-use super::{sequence::{QS, quaternion_to_string}, williamson::QuadSeq, symmetries::SequenceType};
+use super::{sequence::{QS, quaternion_to_string, QQ}, williamson::QuadSeq, symmetries::SequenceType, mum::{Operator, quaternion_to_operator}, hurwitz::{Hurwitz, H0, H1}};
 
 
 
+// The entries are stored as Hurwitz quaternions so that all the computations on the matrix are exact
 pub struct QHM {
     size : usize,
-    matrix : Vec<Vec<Quaternion<f32>>>
+    matrix : Vec<Vec<Hurwitz>>
 }
 
 
 impl QHM {
 
     pub fn new(size : usize) -> QHM {
-        let matrix = vec![vec![Quaternion::new(1.,0.,0.,0.); size]; size];
+        let matrix = vec![vec![H1; size]; size];
         QHM {
             size,
             matrix
@@ -22,18 +25,26 @@ impl QHM {
 
 
     pub fn get(&self, i : usize, j : usize) -> Quaternion<f32> {
+        self.matrix[i][j].to_quaternion()
+    }
+
+    pub fn get_hurwitz(&self, i : usize, j : usize) -> Hurwitz {
         self.matrix[i][j]
     }
 
+    pub fn set_value(&mut self, row : usize, col : usize, value : Quaternion<f32>) {
+        self.matrix[row][col] = Hurwitz::from_quaternion(&value);
+    }
+
     // Returns reference to specified row (0 indexed)
     pub fn row(&self, row : usize) -> Vec<Quaternion<f32>> {
-        self.matrix[row].clone()
+        self.matrix[row].iter().map(|elm| elm.to_quaternion()).collect()
     }
 
     pub fn col(&self, col : usize) -> Vec<Quaternion<f32>> {
         let mut col_vec = Vec::new();
         for i in 0..self.size() {
-            col_vec.push(self.matrix[i][col]);
+            col_vec.push(self.get(i, col));
         }
 
         col_vec
@@ -51,7 +62,7 @@ impl QHM {
         for row in 0..size {
             let mut row_vec = vec![];
             for col in 0..size {
-                row_vec.push(pqs.values()[(col + size - row) % size].clone())
+                row_vec.push(Hurwitz::from_quaternion(&pqs.values()[(col + size - row) % size]))
             }
 
             matrix.push(row_vec);
@@ -60,6 +71,19 @@ impl QHM {
         QHM {size, matrix}
     }
 
+    // Reads a matrix in the format of to_string, one row per line
+    pub fn from_str(s : &String) -> QHM {
+        let matrix : Vec<Vec<Hurwitz>> = s.lines().filter(|line| !line.trim().is_empty()).map(|line| {
+            let symbols : String = line.split_whitespace().filter(|elm| *elm != "|").collect();
+            QS::from_str(&symbols).values().iter().map(Hurwitz::from_quaternion).collect()
+        }).collect();
+
+        let size = matrix.len();
+        assert!(matrix.iter().all(|row| row.len() == size), "The matrix is not square");
+
+        QHM {size, matrix}
+    }
+
     pub fn size(&self) -> usize{
         self.size
     }
@@ -68,38 +92,29 @@ impl QHM {
 
 
     pub fn dephase(&mut self) {
-        // dephase the columns
-        for col in 0..self.size {
-            for inv_row in 0..self.size {
-                let row = self.size - 1 - inv_row;
-                self.matrix[row][col] = self.matrix[row][col]*self.matrix[0][col].conjugate();
-            }
-        }
-        // dephase the rows
-        for row in 0..self.size {
-            for inv_col in 0..self.size {
-                let col = self.size - 1 - inv_col;
-                self.matrix[row][col] = self.matrix[row][0].conjugate()*self.matrix[row][col];
-            }
-        }
+        *self = self.dephased();
     }
 
 
+    // Multiplies every column on the right by the inverse of its first entry, then every row on the left by the inverse of its first entry
+    // The inverse of a unit is its conjugate, so the first row and column of the result are exactly 1
     pub fn dephased(&self) -> QHM{
-        let mut new_mat = vec![vec![Quaternion::<f32>::new(0.,0.,0.,0.);self.size]; self.size];
+        assert!(self.matrix.iter().flatten().all(|elm| elm.is_unit()), "Can only dephase a matrix whose entries are units");
+
+        let mut new_mat = self.matrix.clone();
 
         // dephase the columns
         for col in 0..self.size {
-            for inv_row in 0..self.size {
-                let row = self.size - 1 - inv_row;
-                new_mat[row][col] = self.matrix[row][col]*self.matrix[0][col].conjugate();
+            let unit = self.matrix[0][col].conjugate();
+            for row in 0..self.size {
+                new_mat[row][col] = new_mat[row][col]*unit;
             }
         }
         // dephase the rows
         for row in 0..self.size {
-            for inv_col in 0..self.size {
-                let col = self.size - 1 - inv_col;
-                new_mat[row][col] = new_mat[row][0].conjugate()*new_mat[row][col];
+            let unit = new_mat[row][0].conjugate();
+            for col in 0..self.size {
+                new_mat[row][col] = unit*new_mat[row][col];
             }
         }
 
@@ -108,18 +123,18 @@ impl QHM {
 
 
     pub fn contains_non_commuting_elements(&self) -> bool {
-        let mut unique_elements : Vec<Quaternion<f32>> = Vec::new();
+        let mut unique_elements : Vec<Hurwitz> = Vec::new();
         
         // Iterate over elements in matrix
         for i in 1..self.size() {
             for j in 1..self.size() {
-                let new = self.get(i,j);
+                let new = self.matrix[i][j];
                 // Check if we have encountered this element before
                 if !unique_elements.contains(&new) {
                     // If this is a new element, check if it commutes with all of the previous elements we have found
                     for elm in &unique_elements {
-                        let left = new * elm;
-                        let right = elm * new;
+                        let left = new * *elm;
+                        let right = *elm * new;
 
                         if left != right {
                             return true;
@@ -136,41 +151,27 @@ impl QHM {
         false
     }
 
-    // Verifies QHM property
+    // Verifies QHM property, i.e. that the entries are units and Q * Q^* = nI, with exact arithmetic
     pub fn verify(&self) -> bool {
         let n = self.size();
-        let f32_tolerance : f32 = f32::EPSILON.sqrt();
 
-        // Take complex inner product of each row with each other row. Should get n when taking product with a row and itself, 0 otherwise.
+        if !self.matrix.iter().flatten().all(|elm| elm.is_unit()) {
+            return false;
+        }
+
+        // Take quaternion inner product of each row with each other row. Should get n when taking product with a row and itself, 0 otherwise.
         for row1 in 0..n {
             for row2 in 0..n {
-                // Get two rows
-                let row1_vec = self.row(row1);
-                let row2_vec = self.row(row2);
-
-                // Perform inner product
-                let mut result = Quaternion::<f32>::new(0.0,0.0,0.0,0.0);
+                let mut result = H0;
 
                 for i in 0..n {
-                    result = result + (row1_vec[i] * (row2_vec[i].conjugate()));
+                    result = result + self.matrix[row1][i] * self.matrix[row2][i].conjugate();
                 }
 
-                // Check if the real component is what it should be
-                let real_match = if row1 == row2 {
-                    (result.s - n as f32).abs() < f32_tolerance
-                } else {
-                    result.s.abs() < f32_tolerance
-                };
-
-                // Check that the imaginary components are all 0
-                let imaginary_match = {
-                    result.v.x.abs() < f32_tolerance &&
-                    result.v.y.abs() < f32_tolerance &&
-                    result.v.z.abs() < f32_tolerance
-                };
-
-                // If either are incorrect, then matrix is not QHM
-                if !(real_match && imaginary_match) {
+                let expected = if row1 == row2 {Hurwitz::from_integer(n as i64)} else {H0};
+
+                // If the inner product is incorrect, then matrix is not QHM
+                if result != expected {
                     return false;
                 }
             }
@@ -181,6 +182,56 @@ impl QHM {
     }
 
 
+    // Right multiplies every column by a unit so that all the entries are of the form (+-1 +-i +-j +-k)/2
+    // This is an equivalence operation, and it is possible as long as no column meets the three cosets of Q8 in Q24
+    pub fn with_half_unit_entries(&self) -> Option<QHM> {
+        let mut qhm = QHM::new(self.size);
+        let q = Hurwitz::from_quaternion(&QQ);
+
+        for col in 0..self.size {
+            let unit = [H1, q, q*q].into_iter().find(|u| (0..self.size).all(|row| (self.matrix[row][col]*(*u)).is_half()))?;
+            for row in 0..self.size {
+                qhm.matrix[row][col] = self.matrix[row][col]*unit;
+            }
+        }
+
+        Some(qhm)
+    }
+
+    // Real Hadamard matrix of order 4n obtained by replacing every entry q by 2 times the matrix of the left multiplication by q
+    // Returns None if the matrix can't be brought to half unit entries
+    pub fn to_real_matrix(&self) -> Option<HM> {
+        let qhm = self.with_half_unit_entries()?;
+        let mut hm = HM::new(4*self.size);
+
+        for (row, col) in iproduct!(0..self.size, 0..self.size) {
+            let block = doubled_left_multiplication_matrix(&qhm.matrix[row][col]);
+            for (i, j) in iproduct!(0..4, 0..4) {
+                hm.set_value(4*row + i, 4*col + j, block[i][j] as i8);
+            }
+        }
+
+        Some(hm)
+    }
+
+    // Complex Hadamard matrix of order 2n with entries in {+-1, +-i}, obtained by replacing every entry q by
+    // (1 - i) times the 2x2 complex matrix of q
+    // Returns None if the matrix can't be brought to half unit entries
+    pub fn to_complex_matrix(&self) -> Option<Operator> {
+        let qhm = self.with_half_unit_entries()?;
+        let mut values = vec![vec![Complex::new(0.,0.); 2*self.size]; 2*self.size];
+
+        for (row, col) in iproduct!(0..self.size, 0..self.size) {
+            let block = quaternion_to_operator(&qhm.get(row, col));
+            for (i, j) in iproduct!(0..2, 0..2) {
+                values[2*row + i][2*col + j] = block.get(i, j) * Complex::new(1., -1.);
+            }
+        }
+
+        Some(Operator::from_values(values))
+    }
+
+
     pub fn to_string(&self) -> String {
 
         let mut result = "".to_string();
@@ -188,7 +239,7 @@ impl QHM {
         for row in 0..self.size {
             result += "| ";
             for col in 0..self.size {
-                result += &(quaternion_to_string(&self.matrix[row][col]) + &" ");
+                result += &(quaternion_to_string(&self.get(row, col)) + &" ");
             }
             result += "|\n";
 
@@ -201,6 +252,16 @@ impl QHM {
 }
 
 
+// Matrix of the left multiplication by quat in the basis 1, i, j, k, with doubled coefficients
+fn doubled_left_multiplication_matrix(quat : &Hurwitz) -> [[i64; 4]; 4] {
+    let [a, b, c, d] = quat.doubled();
+    [[a, -b, -c, -d],
+     [b,  a, -d,  c],
+     [c,  d,  a, -b],
+     [d, -c,  b,  a]]
+}
+
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct HM {
     size : usize,
@@ -322,6 +383,24 @@ impl HM {
         self.size
     }
 
+    // Verifies that H * H^T = nI
+    pub fn verify(&self) -> bool {
+        for row1 in 0..self.size {
+            for row2 in 0..self.size {
+                let product : isize = (0..self.size).map(|col| (self.matrix[row1][col] * self.matrix[row2][col]) as isize).sum();
+                if product != if row1 == row2 {self.size as isize} else {0} {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Verifies that H + H^T = 2I, i.e. H is skew-Hadamard when it is Hadamard
+    pub fn is_skew(&self) -> bool {
+        (0..self.size).all(|row| (0..self.size).all(|col| self.matrix[row][col] + self.matrix[col][row] == if row == col {2} else {0}))
+    }
+
 
 
 
@@ -357,7 +436,7 @@ impl HM {
         for inv_col in 0..self.size {
             for row in 0..self.size {
                 let col = self.size - 1 - inv_col;
-                new_mat[row][col] = self.matrix[row][0]*self.matrix[row][col];
+                new_mat[row][col] = new_mat[row][0]*new_mat[row][col];
             }
         }
 
@@ -1,12 +1,13 @@
 use std::{collections::{HashMap, HashSet}, fs::File, io::Write, path::Path};
 
+use cgmath::Quaternion;
 use itertools::Itertools;
-use petgraph::{graph::NodeIndex, Graph, Undirected};
+use petgraph::{graph::NodeIndex, Directed, Graph, Undirected};
 
 //use crate::{read_lines, sequences::{equivalence::ns_canonical, equivalence::negated, symmetries::SequenceType, williamson::QuadSeq, williamson::SequenceTag}};
 use crate::{read_lines, sequences::{equivalence::ns_canonical, symmetries::SequenceType, williamson::{QuadSeq/*, SequenceTag*/}}};
 
-use super::{matrices::HM, sequence::QS};
+use super::{matrices::{HM, QHM}, matrix_invariants::HadamardInvariants, sequence::{QS, Q24, QI, QQ}};
 
 use rayon::{iter::*};
 
@@ -59,6 +60,82 @@ pub fn reduce_to_hadamard_equivalence( mats : &Vec<HM>) -> Vec<&HM> {
     mats.iter().unique_by(|mat|{canon_hm(mat)}).collect_vec()
 }
 
+
+// * Equivalence of quaternion Hadamard matrices
+// Two QHM are equivalent if one can be obtained from the other by permuting rows and columns,
+// multiplying rows on the left by units of Q24 and multiplying columns on the right by units of Q24
+
+fn q24_index(q : &Quaternion<f32>) -> usize {
+    Q24.iter().position(|elm| elm == q).expect("Unrecognized quaternion!")
+}
+
+// Multiplication table of Q24 in terms of indices in Q24
+fn q24_table() -> Vec<Vec<usize>> {
+    Q24.iter().map(|a| Q24.iter().map(|b| q24_index(&(a*b))).collect()).collect()
+}
+
+// Every row r of the matrix is expanded into the 24 vertices (r,g) standing for the row multiplied on the left by g,
+// and every column c into the 24 vertices (c,h) standing for the column multiplied on the right by h.
+// There is an edge (r,g) -> (c,h) when g * H[r][c] * h = 1.
+// The action of Q24 on each fibre is encoded with edges g -> i*g and g -> q*g for rows (g*i and g*q for columns),
+// the edges for q going through an extra vertex so they can't be mistaken for the edges for i.
+// Since i and q generate Q24, an isomorphism of these graphs acts on each fibre as a multiplication by a unit, so it is exactly an equivalence of matrices
+pub fn graph_from_qhm(mat : &QHM) -> Graph<i32,i32,Directed> {
+    let mut g = Graph::new();
+
+    let size = mat.size();
+    let order = Q24.len();
+    let table = q24_table();
+    let inverse : Vec<usize> = (0..order).map(|a| (0..order).position(|b| table[a][b] == 0).unwrap()).collect();
+    let (i, q) = (q24_index(&QI), q24_index(&QQ));
+
+    let row_node = |row : usize, elm : usize| NodeIndex::new(row*order + elm);
+    let col_node = |col : usize, elm : usize| NodeIndex::new((size + col)*order + elm);
+    let row_q_node = |row : usize, elm : usize| NodeIndex::new((2*size + row)*order + elm);
+    let col_q_node = |col : usize, elm : usize| NodeIndex::new((3*size + col)*order + elm);
+
+    for index in 0..4*size*order {
+        let node = g.add_node(0);
+        // Self loops distinguish the rows from the columns
+        if index < size*order {
+            g.add_edge(node, node, 0);
+        }
+    }
+
+    for row in 0..size {
+        for col in 0..size {
+            let entry = q24_index(&mat.get(row, col));
+            for (elm, products) in table.iter().enumerate() {
+                g.add_edge(row_node(row, elm), col_node(col, inverse[products[entry]]), 0);
+            }
+        }
+    }
+
+    for index in 0..size {
+        for elm in 0..order {
+            g.add_edge(row_node(index, elm), row_node(index, table[i][elm]), 0);
+            g.add_edge(row_node(index, elm), row_q_node(index, elm), 0);
+            g.add_edge(row_q_node(index, elm), row_node(index, table[q][elm]), 0);
+
+            g.add_edge(col_node(index, elm), col_node(index, table[elm][i]), 0);
+            g.add_edge(col_node(index, elm), col_q_node(index, elm), 0);
+            g.add_edge(col_q_node(index, elm), col_node(index, table[elm][q]), 0);
+        }
+    }
+
+    g
+}
+
+fn canon_qhm(mat : &QHM) -> CanonLabeling {
+    graph_canon::CanonLabeling::new(&graph_from_qhm(mat))
+}
+
+pub fn reduce_to_qhm_equivalence(mats : &Vec<QHM>) -> Vec<&QHM> {
+    let labels : Vec<CanonLabeling> = mats.par_iter().map(canon_qhm).collect();
+    let mut seen = HashSet::new();
+    mats.iter().zip(labels).filter_map(|(mat, label)| seen.insert(label).then_some(mat)).collect_vec()
+}
+
 pub fn reduce_to_ns_equivalence(sequences : &Vec<QuadSeq>) -> Vec<QuadSeq> {
     let set : HashSet<QuadSeq> = sequences.iter().map(|seq| ns_canonical(seq)).collect();
 
@@ -70,6 +147,8 @@ fn is_ns_canonical(seq : &QuadSeq) -> bool {
 }
 
 pub fn hadamard_equivalence_from_file(pathname : String, seqtype : SequenceType) {
+    // The matrices are built as for quaternion-type sequences, which isn't Hadamard for good and best matrices
+    assert!(!matches!(seqtype, SequenceType::Good | SequenceType::Best), "Good and best matrices give Hadamard matrices through the Goethals-Seidel array");
 
     let mut seqs = vec![];
 
@@ -90,14 +169,32 @@ pub fn hadamard_equivalence_from_file(pathname : String, seqtype : SequenceType)
         assert!(quad_seq.verify(seqtype));
     }
 
-    // Reduce via graph isomorphism checking
-    println!("Reducing matrices to equivalence via graph isomorphism...");
-    let canon_reps : HashMap<CanonLabeling, HM> = quad_seq_list.par_iter().map(|seq| {
+    // Bucket the matrices by their invariants, matrices with different invariants are never equivalent
+    println!("Computing invariants of the matrices...");
+    let matrices : Vec<(HadamardInvariants, HM)> = quad_seq_list.par_iter().map(|seq| {
         let hmat = HM::from_williamson(seq, SequenceType::QuaternionType);
-        (canon_hm(&hmat), hmat)
+        (HadamardInvariants::from_hm(&hmat), hmat)
+    }).collect();
+
+    let mut buckets : HashMap<HadamardInvariants, Vec<HM>> = HashMap::new();
+    for (invariants, hmat) in matrices {
+        buckets.entry(invariants).or_insert(vec![]).push(hmat);
+    }
+
+    let to_canonise : usize = buckets.values().filter(|mats| mats.len() > 1).map(|mats| mats.len()).sum();
+    println!("Found {} distinct sets of invariants, {} matrices share their invariants with another matrix", buckets.len(), to_canonise);
+
+    // Reduce via graph isomorphism checking, only within buckets of more than one matrix
+    println!("Reducing matrices to equivalence via graph isomorphism...");
+    let equ : Vec<(HadamardInvariants, HM)> = buckets.into_par_iter().flat_map_iter(|(invariants, mats)| {
+        let reps : Vec<HM> = if mats.len() == 1 {
+            mats
+        } else {
+            let canon_reps : HashMap<CanonLabeling, HM> = mats.into_iter().map(|hmat| (canon_hm(&hmat), hmat)).collect();
+            canon_reps.into_values().collect()
+        };
+        reps.into_iter().map(move |hmat| (invariants.clone(), hmat))
     }).collect();
-    
-    let equ = canon_reps.into_iter().map(|(_,v)| v).collect::<Vec<_>>();
 
     let count = equ.len();
 
@@ -112,12 +209,20 @@ pub fn hadamard_equivalence_from_file(pathname : String, seqtype : SequenceType)
     let result_path = input_file.parent().expect("Invalid file").join("result.mat");
     let mut result_file = File::create(result_path).expect("Invalid file ?");
 
+    // The invariants of each matrix are written on the same line number as the matrix in result.mat
+    let invariants_path = input_file.parent().expect("Invalid file").join("result.inv");
+    let mut invariants_file = File::create(invariants_path).expect("Invalid file ?");
+
     let mut result_string = "".to_string();
-    for mat in equ {
+    let mut invariants_string = "".to_string();
+    for (invariants, mat) in equ {
         result_string += &mat.to_string_magma();
         result_string += &"\n";
+        invariants_string += &invariants.to_string();
+        invariants_string += &"\n";
     }
 
     result_file.write(result_string.as_bytes()).expect("Error when writing in the file");
+    invariants_file.write(invariants_string.as_bytes()).expect("Error when writing in the file");
 
-}
+}
\ No newline at end of file
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use super::matrices::HM;
+
+
+// * Invariants of Hadamard matrices under Hadamard equivalence (row/column permutations and negations)
+// They are cheap compared to graph canonisation, and two matrices with different invariants can never be equivalent
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct HadamardInvariants {
+    profile : Vec<(usize, usize)>,              // (|inner product of 4 rows|, number of 4-sets of rows reaching it)
+    transposed_profile : Vec<(usize, usize)>,   // same thing for the columns
+    k_boxes : Vec<([usize; 8], usize)>,         // (K-box type of 4 rows, number of 4-sets of rows of that type)
+    transposed_k_boxes : Vec<([usize; 8], usize)>,
+    smith_normal_form : Vec<(usize, usize)>,    // (invariant factor, multiplicity)
+    rank_gf2 : usize,
+    rank_gf3 : usize
+}
+
+
+impl HadamardInvariants {
+
+    pub fn from_hm(mat : &HM) -> HadamardInvariants {
+        let n = mat.size();
+        let rows : Vec<Vec<u64>> = (0..n).map(|i| to_bits((0..n).map(|j| mat.get(i, j)))).collect();
+        let cols : Vec<Vec<u64>> = (0..n).map(|j| to_bits((0..n).map(|i| mat.get(i, j)))).collect();
+
+        let (profile, k_boxes) = four_set_invariants(&rows, n);
+        let (transposed_profile, transposed_k_boxes) = four_set_invariants(&cols, n);
+
+        HadamardInvariants {
+            profile,
+            transposed_profile,
+            k_boxes,
+            transposed_k_boxes,
+            smith_normal_form : smith_normal_form(mat),
+            rank_gf2 : rank_gf2(mat),
+            rank_gf3 : rank_mod_p(mat, 3)
+        }
+    }
+
+    pub fn profile(&self) -> &Vec<(usize, usize)> {
+        &self.profile
+    }
+
+    pub fn transposed_profile(&self) -> &Vec<(usize, usize)> {
+        &self.transposed_profile
+    }
+
+    pub fn k_boxes(&self) -> &Vec<([usize; 8], usize)> {
+        &self.k_boxes
+    }
+
+    pub fn smith_normal_form(&self) -> &Vec<(usize, usize)> {
+        &self.smith_normal_form
+    }
+
+    pub fn rank_gf2(&self) -> usize {
+        self.rank_gf2
+    }
+
+    pub fn rank_gf3(&self) -> usize {
+        self.rank_gf3
+    }
+
+    pub fn to_string(&self) -> String {
+        let profile_to_string = |profile : &Vec<(usize, usize)>| profile.iter().map(|(m, count)| m.to_string() + &":" + &count.to_string()).join(" ");
+        let k_boxes_to_string = |k_boxes : &Vec<([usize; 8], usize)>| k_boxes.iter().map(|(k_box, count)| format!("{:?}x{}", k_box, count)).join(" ");
+
+        "profile: ".to_string() + &profile_to_string(&self.profile)
+            + &" | transposed profile: " + &profile_to_string(&self.transposed_profile)
+            + &" | snf: " + &self.smith_normal_form.iter().map(|(d, mult)| d.to_string() + &"^" + &mult.to_string()).join(" ")
+            + &" | rank_2: " + &self.rank_gf2.to_string()
+            + &" | rank_3: " + &self.rank_gf3.to_string()
+            + &" | k-boxes: " + &k_boxes_to_string(&self.k_boxes)
+            + &" | transposed k-boxes: " + &k_boxes_to_string(&self.transposed_k_boxes)
+    }
+}
+
+
+// Packs a +-1 vector into bits, with a bit set for every -1 entry
+fn to_bits(entries : impl Iterator<Item = i8>) -> Vec<u64> {
+    let mut bits = vec![];
+    for (index, elm) in entries.enumerate() {
+        if index % 64 == 0 {
+            bits.push(0);
+        }
+        if elm == -1 {
+            bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bits
+}
+
+fn xor(v1 : &Vec<u64>, v2 : &Vec<u64>) -> Vec<u64> {
+    v1.iter().zip(v2.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+
+// Computes the 4-profile and the K-box types of a set of +-1 vectors of length n given as bits
+// The 4-profile counts the 4-sets {a,b,c,d} by |sum_k a_k b_k c_k d_k|
+// The K-box type of a 4-set is the sorted list of multiplicities of the 8 column patterns of the 4 x n submatrix, columns taken up to sign
+fn four_set_invariants(vectors : &Vec<Vec<u64>>, n : usize) -> (Vec<(usize, usize)>, Vec<([usize; 8], usize)>) {
+    let mut profile : BTreeMap<usize, usize> = BTreeMap::new();
+    let mut k_boxes : BTreeMap<[usize; 8], usize> = BTreeMap::new();
+
+    // The padding bits of the last word are always 0, so they all fall in the class of the constant pattern which we correct for below
+    let padding = vectors.first().map_or(0, |v| 64 * v.len() - n);
+
+    // Normalising the columns so that vector a is all +1, the pattern of a column is given by the bits of b, c and d
+    let normalised : Vec<Vec<Vec<u64>>> = vectors.iter().map(|a| vectors.iter().map(|b| xor(a, b)).collect()).collect();
+
+    for (a, b, c, d) in (0..vectors.len()).tuple_combinations() {
+        let (x, y, z) = (&normalised[a][b], &normalised[a][c], &normalised[a][d]);
+
+        let mut counts = [0; 8];
+        for word in 0..x.len() {
+            for pattern in 0..8 {
+                let bx = if pattern & 1 == 0 {!x[word]} else {x[word]};
+                let by = if pattern & 2 == 0 {!y[word]} else {y[word]};
+                let bz = if pattern & 4 == 0 {!z[word]} else {z[word]};
+                counts[pattern] += (bx & by & bz).count_ones() as usize;
+            }
+        }
+        counts[0] -= padding;
+
+        // The product of the four entries of a column is -1 exactly when an odd number of the bits of the pattern are set
+        let negative : usize = (0..8).filter(|p : &usize| p.count_ones() % 2 == 1).map(|p| counts[p]).sum();
+        *profile.entry((n as isize - 2 * negative as isize).unsigned_abs()).or_insert(0) += 1;
+
+        counts.sort();
+        *k_boxes.entry(counts).or_insert(0) += 1;
+    }
+
+    (profile.into_iter().collect(), k_boxes.into_iter().collect())
+}
+
+
+fn prime_factors(mut n : usize) -> Vec<(usize, u32)> {
+    let mut factors = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        let mut exponent = 0;
+        while n % p == 0 {
+            n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+fn valuation(mut a : i64, p : i64, cap : u32) -> u32 {
+    if a == 0 {
+        return cap;
+    }
+    let mut v = 0;
+    while a % p == 0 && v < cap {
+        a /= p;
+        v += 1;
+    }
+    v
+}
+
+fn inverse_mod(a : i64, m : i64) -> i64 {
+    // extended euclidean algorithm, a is assumed to be invertible modulo m
+    let (mut r0, mut r1) = (m, a.rem_euclid(m));
+    let (mut t0, mut t1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    debug_assert_eq!(r0, 1, "{a} is not invertible modulo {m}");
+    t0.rem_euclid(m)
+}
+
+
+// Computes the invariant factors of the Smith normal form of a Hadamard matrix over the integers
+// Since H * H^T = nI, every invariant factor divides n, so it is enough to find, for every prime p dividing n,
+// the p-adic valuations of the invariant factors, which we do by elimination over Z/p^(e+1)Z where p^e is the largest power of p dividing n
+pub fn smith_normal_form(mat : &HM) -> Vec<(usize, usize)> {
+    let n = mat.size();
+    let mut invariant_factors = vec![1; n];
+
+    for (p, e) in prime_factors(n) {
+        let p = p as i64;
+        let modulus = p.pow(e + 1);
+        let mut a : Vec<Vec<i64>> = (0..n).map(|i| (0..n).map(|j| (mat.get(i, j) as i64).rem_euclid(modulus)).collect()).collect();
+        let mut valuations = vec![];
+
+        for t in 0..n {
+            // The pivot is the remaining entry of smallest valuation, it divides every other entry
+            let (mut pivot_row, mut pivot_col, mut v) = (t, t, e + 1);
+            for (i, j) in (t..n).cartesian_product(t..n) {
+                let vij = valuation(a[i][j], p, e + 1);
+                if vij < v {
+                    (pivot_row, pivot_col, v) = (i, j, vij);
+                }
+            }
+            valuations.push(v);
+            if v > e {
+                // Only happens for singular matrices, all remaining invariant factors vanish modulo p^(e+1)
+                valuations.extend(vec![e + 1; n - t - 1]);
+                break;
+            }
+
+            a.swap(t, pivot_row);
+            for row in a.iter_mut() {
+                row.swap(t, pivot_col);
+            }
+
+            let unit_inverse = inverse_mod(a[t][t] / p.pow(v), modulus);
+            for i in (t+1)..n {
+                let factor = (a[i][t] / p.pow(v)) * unit_inverse % modulus;
+                for j in t..n {
+                    a[i][j] = (a[i][j] - factor * a[t][j]).rem_euclid(modulus);
+                }
+            }
+            // The column operations only change row t since column t is now zero below the pivot
+            for j in (t+1)..n {
+                a[t][j] = 0;
+            }
+        }
+
+        valuations.sort();
+        for (d, v) in invariant_factors.iter_mut().zip(valuations) {
+            *d *= (p as usize).pow(v);
+        }
+    }
+
+    invariant_factors.into_iter().dedup_with_count().map(|(mult, d)| (d, mult)).collect()
+}
+
+
+// Rank over GF(p) of a +-1 matrix, for p odd
+pub fn rank_mod_p(mat : &HM, p : i64) -> usize {
+    let n = mat.size();
+    let a : Vec<Vec<i64>> = (0..n).map(|i| (0..n).map(|j| (mat.get(i, j) as i64).rem_euclid(p)).collect()).collect();
+    rank_mod_p_aux(a, p)
+}
+
+// Rank over GF(2) of the (0,1)-matrix (J - H)/2 where H is the dephased matrix
+// A +-1 matrix reduced modulo 2 is always the all-one matrix, so this is the meaningful 2-rank. It doesn't depend on the choice of dephasing
+pub fn rank_gf2(mat : &HM) -> usize {
+    let dephased = mat.dephased();
+    let n = dephased.size();
+    let a : Vec<Vec<i64>> = (0..n).map(|i| (0..n).map(|j| if dephased.get(i, j) == -1 {1} else {0}).collect()).collect();
+    rank_mod_p_aux(a, 2)
+}
+
+fn rank_mod_p_aux(mut a : Vec<Vec<i64>>, p : i64) -> usize {
+    let rows = a.len();
+    let cols = a.first().map_or(0, |r| r.len());
+    let mut rank = 0;
+
+    for col in 0..cols {
+        if let Some(pivot) = (rank..rows).find(|&row| a[row][col] != 0) {
+            a.swap(rank, pivot);
+            let pivot_inverse = inverse_mod(a[rank][col], p);
+            for row in (rank+1)..rows {
+                let factor = a[row][col] * pivot_inverse % p;
+                if factor != 0 {
+                    for j in col..cols {
+                        a[row][j] = (a[row][j] - factor * a[rank][j]).rem_euclid(p);
+                    }
+                }
+            }
+            rank += 1;
+        }
+    }
+
+    rank
+}
@@ -1,12 +1,18 @@
 pub mod sequence;
 pub mod symmetries;
 pub mod williamson;
+pub mod aperiodic;
+pub mod complex;
 pub mod rowsum;
 pub mod fourier;
 pub mod equations;
+pub mod sat;
 pub mod matrices;
 pub mod equivalence;
 pub mod matching;
 pub mod product;
 pub mod mum;
-pub mod matrix_equivalence;
\ No newline at end of file
+pub mod hurwitz;
+pub mod matrix_equivalence;
+pub mod matrix_invariants;
+pub mod constructions;
\ No newline at end of file
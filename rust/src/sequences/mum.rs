@@ -1,7 +1,7 @@
 use std::ops;
 
 use cgmath::Quaternion;
-use itertools::iproduct;
+use itertools::{iproduct, Itertools};
 use num_complex::Complex;
 
 use super::matrices::QHM;
@@ -58,6 +58,40 @@ impl Operator {
         Operator { size, values : vec![vec![Complex::new(0.,0.); size]; size]}
     }
 
+    pub fn identity(size : usize) -> Operator {
+        let mut values = vec![vec![Complex::new(0.,0.); size]; size];
+        for (i, line) in values.iter_mut().enumerate() {
+            line[i] = Complex::new(1.,0.);
+        }
+        Operator { size, values }
+    }
+
+    pub fn from_values(values : Vec<Vec<Complex<f32>>>) -> Operator {
+        Operator { size : values.len(), values }
+    }
+
+    pub fn get(&self, i : usize, j : usize) -> Complex<f32> {
+        self.values[i][j]
+    }
+
+    // Verifies that all entries have modulus 1 and that M * M^* = nI
+    pub fn is_complex_hadamard(&self) -> bool {
+        let f32_tolerance : f32 = f32::EPSILON.sqrt();
+
+        if self.values.iter().flatten().any(|elm| (elm.norm() - 1.).abs() > f32_tolerance) {
+            return false;
+        }
+
+        for (row1, row2) in iproduct!(0..self.size, 0..self.size) {
+            let product : Complex<f32> = (0..self.size).map(|col| self.values[row1][col] * self.values[row2][col].conj()).sum();
+            let expected = if row1 == row2 {self.size as f32} else {0.};
+            if (product - Complex::new(expected, 0.)).norm() > f32_tolerance {
+                return false;
+            }
+        }
+        true
+    }
+
     pub fn conjugate_transpose(&self) -> Operator {
         let mut values = vec![];
         
@@ -72,6 +106,24 @@ impl Operator {
         Operator {size : self.size(), values}
     }
 
+    pub fn trace(&self) -> Complex<f32> {
+        (0..self.size).map(|i| self.values[i][i]).sum()
+    }
+
+    // AB - BA
+    pub fn commutator(&self, other : &Operator) -> Operator {
+        self.clone() * other - &(other.clone() * self)
+    }
+
+    pub fn frobenius_norm(&self) -> f32 {
+        self.values.iter().flatten().map(|elm| elm.norm_sqr()).sum::<f32>().sqrt()
+    }
+
+    // Equality up to the given tolerance on the Frobenius norm of the difference
+    pub fn approx_eq(&self, other : &Operator, tolerance : f32) -> bool {
+        self.size == other.size && (self.clone() - other).frobenius_norm() <= tolerance
+    }
+
     pub fn tensor(&self, other : &Operator) -> Operator {
         let size = self.size * other.size();
         let mut values = vec![vec![Complex::new(0.,0.); size]; size];
@@ -102,6 +154,25 @@ impl Operator {
 
     }
 
+    // JSON array of the rows, every entry being given as [re, im]
+    pub fn to_string_json(&self) -> String {
+        "[".to_string() + &self.values.iter().map(|row| "[".to_string() + &row.iter().map(|elm| format!("[{},{}]", elm.re, elm.im)).join(",") + &"]").join(",") + &"]"
+    }
+
+    // Magma format for operators with gaussian integer entries, on a single line
+    pub fn to_string_magma(&self) -> String {
+        let entry_to_string = |elm : &Complex<f32>| match (elm.re.round() as i32, elm.im.round() as i32) {
+            (re, 0) => re.to_string(),
+            (0, 1) => "i".to_string(),
+            (0, -1) => "-i".to_string(),
+            (re, im) => format!("{re}+{im}*i")
+        };
+
+        "K<i> := CyclotomicField(4); M := Matrix(K, [".to_string()
+            + &self.values.iter().map(|row| "[".to_string() + &row.iter().map(entry_to_string).join(",") + &"]").join(",")
+            + &"]);"
+    }
+
 }
 
 impl ops::Add<&Operator> for Operator {
@@ -120,6 +191,31 @@ impl ops::Add<&Operator> for Operator {
     }
 }
 
+impl ops::Sub<&Operator> for Operator {
+    type Output = Operator;
+    
+    fn sub(self, rhs: &Operator) -> Self::Output {
+        self + &(rhs.clone() * Complex::new(-1., 0.))
+    }
+}
+
+impl ops::Mul<&Operator> for Operator {
+    type Output = Operator;
+    
+    fn mul(self, rhs: &Operator) -> Self::Output {
+        assert_eq!(self.size(), rhs.size(), "Can only multiply operators of the same size");
+        let mut values = vec![];
+        for i in 0..self.size() {
+            let mut line = vec![];
+            for j in 0..self.size() {
+                line.push((0..self.size()).map(|k| self.values[i][k] * rhs.values[k][j]).sum())
+            }
+            values.push(line);
+        }
+        Operator { size : self.size, values}
+    }
+}
+
 impl ops::Mul<Complex<f32>> for Operator {
     type Output = Operator;
     
@@ -189,6 +285,97 @@ impl MUM {
         MUM {matrix_size, sequence}
     }
 
+    // Verifies that the operators are, up to a common factor l, orthogonal projectors summing to the identity :
+    // Q_b is hermitian, Q_b * Q_c = l * delta_bc * Q_b and sum_b Q_b = l * I
+    // The factor is recovered from the traces, so this doesn't depend on the normalisation of the operators
+    pub fn verify(&self) -> bool {
+        let f32_tolerance : f32 = f32::EPSILON.sqrt();
+
+        let sum = self.sequence.iter().fold(Operator::empty(self.matrix_size), |acc, op| acc + op);
+        let factor = sum.trace() / self.matrix_size as f32;
+        // Tolerance relative to the size of the entries we are comparing
+        let tolerance = f32_tolerance * factor.norm() * self.matrix_size as f32;
+
+        if factor.norm() < f32_tolerance || !sum.approx_eq(&(Operator::identity(self.matrix_size) * factor), tolerance) {
+            return false;
+        }
+
+        for (b, op1) in self.sequence.iter().enumerate() {
+            if !op1.approx_eq(&op1.conjugate_transpose(), tolerance) {
+                return false;
+            }
+            for (c, op2) in self.sequence.iter().enumerate() {
+                let expected = if b == c {op1.clone() * factor} else {Operator::empty(self.matrix_size)};
+                if !(op1.clone() * op2).approx_eq(&expected, tolerance * factor.norm()) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    // The b-th operator is the projector onto the span of the b-th column of the complex embedding of the QHM, normalized by 1/n
+    // so that the operators sum to the identity. Since Q^* Q = nI, the columns (and not the rows used by from_qhm) are orthogonal
+    // for the product conj(q_jb) * q_jc, so these are orthogonal projectors whatever the QHM, and not only for circulant ones
+    pub fn from_qhm_normalized(qhm : &QHM) -> MUM {
+        let matrix_size = qhm.size()*2;
+        let mut sequence = vec![];
+
+        for b in 0..qhm.size() {
+            let mut q_b = Operator::empty(matrix_size);
+
+            for (j,k) in iproduct!(0..qhm.size(), 0..qhm.size()) {
+                let quat = qhm.get(j,b) * qhm.get(k,b).conjugate();
+                let basis_elm = computational_basis(qhm.size(), j, k);
+                q_b = q_b + &quaternion_to_operator(&quat).tensor(&basis_elm);
+            }
+            sequence.push(q_b * Complex::new(1./qhm.size() as f32, 0.));
+        }
+
+        MUM {matrix_size, sequence}
+    }
+
+    fn sum(&self) -> Operator {
+        self.sequence.iter().fold(Operator::empty(self.matrix_size), |acc, op| acc + op)
+    }
+
+    // The following checks are for normalized operators, unlike verify
+    // Orthogonal projectors, which we check through P^* = P and P_b * P_c = delta_bc * P_b
+    // This forces the eigenvalues to be 0 or 1, hence the positivity
+    pub fn is_positive(&self, tolerance : f32) -> bool {
+        for (b, op1) in self.sequence.iter().enumerate() {
+            if !op1.approx_eq(&op1.conjugate_transpose(), tolerance) {
+                return false;
+            }
+            for (c, op2) in self.sequence.iter().enumerate() {
+                let expected = if b == c {op1.clone()} else {Operator::empty(self.matrix_size)};
+                if !(op1.clone() * op2).approx_eq(&expected, tolerance) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // The operators sum to the identity
+    pub fn is_complete(&self, tolerance : f32) -> bool {
+        self.sum().approx_eq(&Operator::identity(self.matrix_size), tolerance)
+    }
+
+    // Unbiasedness with respect to the measurement in the computational basis : tr(P_b |m><m|) = tr(P_b) / d for every b and m
+    pub fn is_unbiased(&self, tolerance : f32) -> bool {
+        self.sequence.iter().all(|op| {
+            let expected = op.trace() / self.matrix_size as f32;
+            (0..self.matrix_size).all(|m| (op.get(m, m) - expected).norm() <= tolerance)
+        })
+    }
+
+    // JSON array of the operators
+    pub fn to_string_json(&self) -> String {
+        "[".to_string() + &self.sequence.iter().map(|op| op.to_string_json()).join(",") + &"]"
+    }
+
     pub fn to_string(&self) -> String {
         let mut result = "".to_string();
 
@@ -239,6 +426,45 @@ impl HMUO {
     }
 
 
+    // Verifies that U * U^* = U^* * U = l * I for some positive l, i.e. that U is unitary up to normalisation
+    pub fn is_unitary(&self) -> bool {
+        let f32_tolerance : f32 = f32::EPSILON.sqrt();
+
+        let product = self.operator.clone() * &self.operator.conjugate_transpose();
+        let factor = product.trace() / self.matrix_size as f32;
+        let tolerance = f32_tolerance * factor.norm() * self.matrix_size as f32;
+        let expected = Operator::identity(self.matrix_size) * factor;
+
+        factor.norm() > f32_tolerance
+            && product.approx_eq(&expected, tolerance)
+            && (self.operator.conjugate_transpose() * &self.operator).approx_eq(&expected, tolerance)
+    }
+
+    // Complex embedding of Q^* (the transpose of the matrix of from_qhm), normalized by 1/sqrt(n) so that it is unitary
+    pub fn from_qhm_normalized(qhm : &QHM) -> HMUO {
+        let mut mat = Operator::empty(2*qhm.size());
+
+        for (j,k) in iproduct!(0..qhm.size(), 0..qhm.size()) {
+            let quat = qhm.get(k,j).conjugate();
+            let basis_elm = computational_basis(qhm.size(), j, k);
+            mat = mat + &basis_elm.tensor(&quaternion_to_operator(&quat));
+        }
+
+        HMUO {matrix_size : 2*qhm.size(), operator : mat * Complex::new(1./(qhm.size() as f32).sqrt(), 0.)}
+    }
+
+    // Verifies that U * U^* = U^* * U = I, for normalized operators
+    pub fn is_unitary_with_tolerance(&self, tolerance : f32) -> bool {
+        let identity = Operator::identity(self.matrix_size);
+
+        (self.operator.clone() * &self.operator.conjugate_transpose()).approx_eq(&identity, tolerance)
+            && (self.operator.conjugate_transpose() * &self.operator).approx_eq(&identity, tolerance)
+    }
+
+    pub fn to_string_json(&self) -> String {
+        self.operator.to_string_json()
+    }
+
     pub fn tensor(&self, other : &HMUO) -> HMUO {
         HMUO {matrix_size : self.matrix_size + other.matrix_size, operator : self.operator.tensor(&other.operator)}
     }
@@ -1,4 +1,8 @@
-use super::{sequence::QS, mum::MUM};
+use std::{collections::HashSet, fs::{self, File}, io::Write};
+
+use crate::{find::{find_unique::reduce_to_canonical_reps, find_write::pairs_folder}, read_lines};
+
+use super::{sequence::{QS, QPLUS, Q24}, mum::MUM, equivalence::{coprime, reduce_perfect}, symmetries::{SequenceShape, SequenceType}, williamson::QuadSeq};
 
 
 pub fn sequence_product(qs1 : &QS, qs2 : &QS) -> QS{
@@ -42,3 +46,100 @@ pub fn mum_product(mum1 : &MUM, mum2 : &MUM) -> MUM{
 
     MUM::new(mum1.matrix_size() * mum2.matrix_size(), &values)
 }
+
+
+
+fn read_sequences(seqtype : SequenceType, len : usize, shape : Option<SequenceShape>) -> Option<Vec<QS>> {
+    let pathname = pairs_folder(&seqtype.to_string(), len, shape) + &"/result.seq";
+    let lines = read_lines(&pathname).ok()?;
+    Some(lines.map(|line| QS::from_str(&line.expect("Error reading line"))).collect())
+}
+
+// The products of two lists of sequences of coprime lengths
+pub struct Products {
+    pub quad_seqs : Vec<QuadSeq>,   // products corresponding to a quadruple of the type, up to equivalence
+    pub q24_seqs : Vec<QS>,         // perfect products with entries outside of QPLUS, up to equivalence
+    pub num_other_type : usize,     // perfect products over QPLUS whose quadruple isn't of the type
+    pub num_not_perfect : usize     // products which aren't perfect, when some of the sequences aren't
+}
+
+pub fn products(seqs1 : &Vec<QS>, seqs2 : &Vec<QS>, seqtype : SequenceType) -> Products {
+    let mut quad_products = vec![];
+    let mut q24_products = vec![];
+    let (mut num_other_type, mut num_not_perfect) = (0, 0);
+
+    for (qs1, qs2) in itertools::iproduct!(seqs1, seqs2) {
+        let product = sequence_product(qs1, qs2);
+        if !product.is_perfect() {
+            num_not_perfect += 1;
+        } else if !product.values().iter().all(|elm| QPLUS.contains(elm)) {
+            q24_products.push(product);
+        } else {
+            let quad_seq = QuadSeq::from_pqs(&product);
+            if quad_seq.verify(seqtype) {
+                quad_products.push(quad_seq);
+            } else {
+                num_other_type += 1;
+            }
+        }
+    }
+
+    Products {
+        quad_seqs : reduce_to_canonical_reps(&quad_products, seqtype),
+        q24_seqs : reduce_perfect(&q24_products, &Q24),
+        num_other_type,
+        num_not_perfect
+    }
+}
+
+// Forms the products of the sequences found for the coprime lengths p and q, reduces them to equivalence
+// and writes them to find_pq/product_p_q.seq. The products which don't correspond to a quadruple of sequences
+// (some entries are outside of QPLUS) are still perfect, and are written to find_pq/product_p_q-q24.seq
+// With a shape, the sequences are those of the searches restricted to it, and the products are written to find_pq_<shape>
+pub fn write_products(p : usize, q : usize, seqtype : SequenceType, shape : Option<SequenceShape>) {
+    assert!(coprime(p, q), "The lengths {p} and {q} are not coprime");
+
+    let seqs1 = read_sequences(seqtype, p, shape).expect(&format!("Error reading the sequences of length {p}. Make sure they have already been generated"));
+    let seqs2 = read_sequences(seqtype, q, shape).expect(&format!("Error reading the sequences of length {q}. Make sure they have already been generated"));
+
+    let products = products(&seqs1, &seqs2, seqtype);
+    let classes : HashSet<QuadSeq> = products.quad_seqs.iter().cloned().collect();
+
+    println!("Formed {} products of sequences of lengths {p} and {q} : {} inequivalent sequences of length {}, {} inequivalent perfect products not coming from a quadruple, {} perfect products whose quadruple isn't {}, {} products not perfect.",
+        seqs1.len() * seqs2.len(), classes.len(), p*q, products.q24_seqs.len(), products.num_other_type, seqtype.to_string(), products.num_not_perfect);
+
+    // Comparing with the classes found by the search at length pq, if there are any
+    match read_sequences(seqtype, p*q, shape) {
+        Some(known) => {
+            let known_quad_seqs : Vec<QuadSeq> = known.iter().map(QuadSeq::from_pqs).collect();
+            let known_classes : HashSet<QuadSeq> = reduce_to_canonical_reps(&known_quad_seqs, seqtype).into_iter().collect();
+            let reachable = known_classes.intersection(&classes).count();
+            println!("Of the {} known classes of length {}, {reachable} are reachable by products and {} are primitive.", known_classes.len(), p*q, known_classes.len() - reachable);
+            if classes.len() > reachable {
+                println!("WARNING : {} products are not among the known classes of length {}", classes.len() - reachable, p*q);
+            }
+        }
+        None => {
+            println!("No sequences of length {} have been generated yet, cannot compare with the known classes.", p*q);
+        }
+    }
+
+    let folder = pairs_folder(&seqtype.to_string(), p*q, shape);
+    fs::create_dir_all(&folder).expect("Error when creating the dir");
+
+    let mut result = "".to_string();
+    for quad_seq in &classes {
+        result += &quad_seq.to_qs().to_string_raw();
+        result += &"\n";
+    }
+    let mut f = File::create(folder.clone() + &format!("/product_{p}_{q}.seq")).expect("Invalid file ?");
+    f.write(result.as_bytes()).expect("Error when writing in the file");
+
+    let mut result = "".to_string();
+    for qs in &products.q24_seqs {
+        result += &qs.to_string_raw();
+        result += &"\n";
+    }
+    let mut f = File::create(folder + &format!("/product_{p}_{q}-q24.seq")).expect("Invalid file ?");
+    f.write(result.as_bytes()).expect("Error when writing in the file");
+}
@@ -1,6 +1,9 @@
+use std::collections::HashSet;
+
 use itertools::*;
 
-use crate::sequences::symmetries::SequenceType;
+use crate::sequences::fourier::psd_within_bound;
+use crate::sequences::symmetries::{SequenceShape, SequenceType};
 use crate::QuadSeq;
 use crate::SequenceTag;
 
@@ -17,23 +20,6 @@ pub fn quad_contains_dup(quad : &Quad) -> bool {
     (quad.0 == quad.1) || (quad.0 == quad.2) || (quad.0 == quad.3) || (quad.1 == quad.2) || (quad.1 == quad.3) || (quad.2 == quad.3)
 }
 
-pub fn sequence_to_string(seq: &Vec<i8>) -> String {
-    let mut res_str: String = "".to_owned();
-
-    for i in seq.iter(){
-        let str = match i {
-            1 => "+",
-            -1 => "-",
-            _ => {panic!("not a +-1 sequence !")}
-        };
-        res_str.push_str(&str);
-    }
-
-    res_str.to_string()
-}
-
-
-
 pub fn rowsum(seq : Vec<i8>) -> isize {
     // computes the rowsum of the sequence seq
     seq.iter().map(|i| *i as isize).sum()
@@ -41,7 +27,7 @@ pub fn rowsum(seq : Vec<i8>) -> isize {
 
 // Check if QuadSeq satisfies Proposition 5 in our paper
 pub fn has_sorted_rowsums(seq : &QuadSeq) -> bool {
-    let seqs : Quad = (rowsum(seq.sequence(SequenceTag::W)), rowsum(seq.sequence(SequenceTag::X)), rowsum(seq.sequence(SequenceTag::Y)), rowsum(seq.sequence(SequenceTag::Z)));
+    let seqs : Quad = quad_rowsums(seq);
     if quad_contains_dup(&seqs) || quad_contains_zero(&seqs) {
         0 <= seqs.0 && seqs.0 <= seqs.1 && seqs.1 <= seqs.2 && seqs.2 <= seqs.3
     }
@@ -93,6 +79,142 @@ pub fn gen_seq_rec(seq : &mut Vec<i8>, remaining_ones : usize, current_pos : usi
     results1
 }
 
+pub fn generate_sequences_with_shape(rowsum : isize, size : usize, shape : SequenceShape) -> Vec<Vec<i8>> {
+    // generates the sequences of the shape of length size and whose sum equals rowsum, from all the possible prefixes
+    let prefix = shape.prefix_length(size).min(size);
+    assert!(prefix < 64, "The sequences are too long");
+
+    let mut result = vec![];
+    for m in 0..1u64 << prefix {
+        let seq : Vec<i8> = (0..size).map(|i| if i < prefix && (m >> i) & 1 == 1 {1} else {-1}).collect();
+        let shaped = shape.apply(&seq);
+        // the prefixes whose values are changed by the shape (the first value of a skew sequence) are generated twice
+        if shaped[..prefix] == seq[..prefix] && shape.contains(&shaped) && shaped.iter().map(|&x| x as isize).sum::<isize>() == rowsum {
+            result.push(shaped);
+        }
+    }
+    result
+}
+
+
+
+// * Ranking of the sequences with a given rowsum
+// A sequence of length n and rowsum r has k = (n+r)/2 entries equal to 1, at positions c_1 < c_2 < ... < c_k.
+// Its rank C(c_1, 1) + C(c_2, 2) + ... + C(c_k, k) numbers the C(n, k) sequences of length n and rowsum r from 0 to C(n, k) - 1
+// (combinatorial number system), so that a sequence can be reconstructed from its rank without storing the whole list
+
+pub fn binomial(n : usize, k : usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+
+    // Every intermediate value is itself a binomial coefficient C(n-k+i, i), so each division is exact
+    let mut result : u128 = 1;
+    for i in 1..=k {
+        result = result * (n - k + i) as u128 / i as u128;
+    }
+    result.try_into().expect("Binomial coefficient too large")
+}
+
+pub fn rank_sequence(seq : &Vec<i8>) -> usize {
+    // computes the rank of the sequence among the sequences of the same length and rowsum
+    seq.iter().enumerate().filter(|(_, &elm)| elm == 1).enumerate().map(|(i, (position, _))| binomial(position, i + 1)).sum()
+}
+
+pub fn unrank_sequence(rank : usize, size : usize, rowsum : isize) -> Vec<i8> {
+    // reconstructs the sequence of length size and whose sum equals rowsum with the given rank
+
+    assert!(rowsum.abs() as usize <= size && (rowsum.abs() % 2) as usize == size % 2, "No sequence of length {size} has rowsum {rowsum}");
+    let nb_ones = ((size as isize + rowsum)/2) as usize;
+    assert!(rank < binomial(size, nb_ones), "Rank {rank} too large for length {size} and rowsum {rowsum}");
+
+    let mut seq = vec![-1; size];
+    let mut remaining = rank;
+    let mut position = size;
+
+    // The positions of the ones are found from the last one, each being the largest position c such that C(c, i) <= remaining
+    for i in (1..=nb_ones).rev() {
+        position -= 1;
+        while binomial(position, i) > remaining {
+            position -= 1;
+        }
+        seq[position] = 1;
+        remaining -= binomial(position, i);
+    }
+
+    seq
+}
+
+
+
+// * Sequence generation through compression
+// For n = d*m, the m-compression of a sequence A of length n is the sequence of length d with entries
+// a_j + a_{j+d} + ... + a_{j+(m-1)d}. Its DFT at frequency k is the DFT of A at frequency k*m,
+// so the PSD bound on A also holds for its compression. We enumerate the compressions satisfying the bound,
+// which are much fewer than the sequences, and only decompress those
+
+pub fn compress(seq : &Vec<i8>, m : usize) -> Vec<i8> {
+    assert_eq!(seq.len() % m, 0, "The compression factor must divide the length");
+    let d = seq.len() / m;
+    (0..d).map(|j| (0..m).map(|i| seq[j + i*d]).sum()).collect()
+}
+
+pub fn generate_compressions_with_rowsum(rowsum : isize, d : usize, m : usize) -> Vec<Vec<i8>> {
+    // generates all sequences of length d with entries in {-m, -m+2, ..., m} and whose sum equals rowsum
+    let mut seq = vec![0; d];
+    gen_compression_rec(&mut seq, rowsum, m as isize, 0)
+}
+
+fn gen_compression_rec(seq : &mut Vec<i8>, remaining_sum : isize, m : isize, current_pos : usize) -> Vec<Vec<i8>> {
+    let remaining_entries = (seq.len() - current_pos) as isize;
+    if remaining_sum.abs() > m * remaining_entries || (remaining_sum - m * remaining_entries) % 2 != 0 {
+        // The remaining entries can't add up to the remaining sum
+        return vec![];
+    }
+    if remaining_entries == 0 {
+        return vec![seq.clone()];
+    }
+
+    let mut results = vec![];
+    for value in (-m..=m).step_by(2) {
+        seq[current_pos] = value as i8;
+        results.append(&mut gen_compression_rec(seq, remaining_sum - value, m, current_pos + 1));
+    }
+    seq[current_pos] = 0;
+
+    results
+}
+
+pub fn decompress(compressed : &Vec<i8>, m : usize) -> Vec<Vec<i8>> {
+    // generates all sequences whose m-compression is the given sequence
+    let d = compressed.len();
+    let blocks : Vec<Vec<Vec<i8>>> = compressed.iter().map(|&c| generate_sequences_with_rowsum(c as isize, m)).collect();
+
+    blocks.iter().multi_cartesian_product().map(|choice| {
+        let mut seq = vec![0; d * m];
+        for (j, block) in choice.iter().enumerate() {
+            for i in 0..m {
+                seq[j + i*d] = block[i];
+            }
+        }
+        seq
+    }).collect()
+}
+
+pub fn generate_sequences_with_rowsum_compressed(rowsum : isize, size : usize, m : usize, bound : f64) -> Vec<Vec<i8>> {
+    // generates the sequences of length size and whose sum equals rowsum, whose m-compression has its PSD bounded by bound
+    // (except at frequency 0 which is the square of the rowsum). The bound must already include the slack of the spectral filter
+    assert_eq!(size % m, 0, "The compression factor must divide the length");
+    if (rowsum.abs() % 2) as usize != size % 2 {
+        return vec![];
+    }
+
+    generate_compressions_with_rowsum(rowsum, size / m, m).into_iter()
+        .filter(|compressed| psd_within_bound(compressed, bound))
+        .flat_map(|compressed| decompress(&compressed, m))
+        .collect()
+}
 
 
 // * Rowsum generation
@@ -150,152 +272,175 @@ pub fn sum_of_four_squares(p : usize) -> Vec<Quad> {
     squares_list
 }
 
+// * Rowsums up to equivalence
+// The equivalence operations that negate or reorder the sequences act on the rowsum vectors by signed permutations.
+// We derive this group for each sequence type by applying its equivalences to a probe quadruple whose rowsums have
+// distinct absolute values, so that the signed permutation is read from the rowsums of each image. The operations
+// that keep the rowsums (shifts, automorphisms, ...) give the identity, and the other ones (even alternated negation)
+// aren't signed permutations and are ignored. Only one rowsum vector per orbit of the group needs to be searched.
+
+const PROBE_LENGTH : usize = 9;
+const PROBE_ROWSUMS : [isize; 4] = [1, 3, 5, 7];
+
+// Sends the rowsum vector r to (signs[0] * r[permutation[0]], ..., signs[3] * r[permutation[3]])
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct RowsumAction {
+    permutation : [usize; 4],
+    signs : [isize; 4]
+}
 
+impl RowsumAction {
+    pub fn identity() -> RowsumAction {
+        RowsumAction { permutation : [0, 1, 2, 3], signs : [1; 4] }
+    }
 
+    pub fn apply(&self, quad : &Quad) -> Quad {
+        let values = quad_to_array(quad);
+        let image : Vec<isize> = (0..4).map(|i| self.signs[i] * values[self.permutation[i]]).collect();
+        (image[0], image[1], image[2], image[3])
+    }
 
-pub fn generate_other_quadruplets(quad : &Quad, seqtype : SequenceType) -> Vec<Quad> {
-    // returns all the permutations of the quadruplet up to equivalence
-    
-    // generate all permutations and filter them
-    let quad_vec = vec![quad.0,quad.1,quad.2,quad.3];
-
-    let mut isomorphism = vec![];
-
-    for perm in quad_vec.iter().permutations(4) {
-        let quad = (*perm[0], *perm[1], *perm[2], *perm[3]);
-        isomorphism.push(quad);
+    pub fn compose(&self, other : &RowsumAction) -> RowsumAction {
+        // the action applying other, then self
+        let permutation = self.permutation.map(|i| other.permutation[i]);
+        let signs = [0, 1, 2, 3].map(|i| self.signs[i] * other.signs[self.permutation[i]]);
+        RowsumAction { permutation, signs }
     }
 
-    // filtering and adding negated elements
-    let mut result = vec![];
-    for elm in isomorphism.into_iter().unique_by(|q| equivalent(&q, seqtype)).collect::<Vec<Quad>>() {
-        result.push(elm.clone());
-        if matches!(seqtype, SequenceType::QuaternionType) && !quad_contains_zero(&elm) && !quad_contains_dup(&elm) {
-            let mut nega_elm = elm.clone();
-            nega_elm.3 = - nega_elm.3;
-            result.push(nega_elm)
+    fn between(source : &Quad, image : &Quad) -> Option<RowsumAction> {
+        // the action sending source to image, the entries of source having distinct absolute values
+        let (source, image) = (quad_to_array(source), quad_to_array(image));
+        let mut action = RowsumAction::identity();
+        for (i, value) in image.iter().enumerate() {
+            let j = source.iter().position(|r| r.abs() == value.abs())?;
+            action.permutation[i] = j;
+            action.signs[i] = value / source[j];
         }
+        Some(action)
     }
-
-    result.into_iter().unique().collect()
 }
 
-
-
-
-fn swap(quad : &mut Quad, i1 : usize, i2 : usize) {
-    // swaps two indices of a Quad
-    let val1 = match i1 {
-        0 => {quad.0},
-        1 => {quad.1},
-        2 => {quad.2},
-        3 => {quad.3},
-        _ => {panic!()}
-    };
-    let val2 = match i2 {
-        0 => {quad.0},
-        1 => {quad.1},
-        2 => {quad.2},
-        3 => {quad.3},
-        _ => {panic!()}
-    };
-    match i1 {
-        0 => {quad.0 = val2},
-        1 => {quad.1 = val2},
-        2 => {quad.2 = val2},
-        3 => {quad.3 = val2},
-        _ => {panic!()}
-    };
-    match i2 {
-        0 => {quad.0 = val1},
-        1 => {quad.1 = val1},
-        2 => {quad.2 = val1},
-        3 => {quad.3 = val1},
-        _ => {panic!()}
-    };
-
+fn quad_to_array(quad : &Quad) -> [isize; 4] {
+    [quad.0, quad.1, quad.2, quad.3]
 }
 
-fn better_than(q1 : &Quad, q2 : &Quad) -> bool {
-    // compares two quads
-    if q1.0 < q2.0 {true}
-    else if q1.0 > q2.0 {false}
-    else{
-        if q1.1 < q2.1 {true}
-        else if q1.1 > q2.1 {false}
-        else{
-            if q1.2 < q2.2 {true}
-            else if q1.2 > q2.2 {false}
-            else{
-                if q1.3 < q2.3 {true}
-                else{false}
-            }
-        }
-    }
+pub fn quad_rowsums(seq : &QuadSeq) -> Quad {
+    (rowsum(seq.sequence(SequenceTag::W)), rowsum(seq.sequence(SequenceTag::X)), rowsum(seq.sequence(SequenceTag::Y)), rowsum(seq.sequence(SequenceTag::Z)))
 }
 
-fn equivalent(quad : &Quad, seqtype : SequenceType) -> Quad {
-    // finds the representative of the equivalence class that quad belongs to
-    let mut final_quad = quad.clone();
-
-    // If 0's are present, any swap is legal, so just sort the quad. Same if seqtype is WS/WTS
-    if !matches!(seqtype, SequenceType::QuaternionType) || quad_contains_zero(quad) || quad_contains_dup(quad) {
-        let mut quad_vec = vec![quad.0, quad.1, quad.2, quad.3];
-        for i in 0..3 {
-            
-            // Find smallest element in subarray
-            let mut min_ind = i;
-            for j in (i+1)..4 {
-                if quad_vec[j] < quad_vec[min_ind] {
-                    min_ind = j;
-                }
+pub fn rowsum_actions(seqtype : SequenceType) -> Vec<RowsumAction> {
+    // returns the group of signed permutations of the rowsums generated by the equivalences of the sequence type
+    let sequences : Vec<Vec<i8>> = PROBE_ROWSUMS.iter().map(|&r| {
+        let nb_ones = (PROBE_LENGTH as isize + r) as usize / 2;
+        (0..PROBE_LENGTH).map(|i| if i < nb_ones {1} else {-1}).collect()
+    }).collect();
+    let mut probe = QuadSeq::new(PROBE_LENGTH);
+    probe.set_all_values((&sequences[0], &sequences[1], &sequences[2], &sequences[3]));
+    let probe_rowsums = quad_rowsums(&probe);
+
+    let generators : Vec<RowsumAction> = seqtype.equivalences().iter()
+        .flat_map(|equivalence| equivalence(&probe, seqtype, true))
+        .filter_map(|image| RowsumAction::between(&probe_rowsums, &quad_rowsums(&image)))
+        .unique()
+        .collect();
+
+    // closure of the generators
+    let mut group = vec![RowsumAction::identity()];
+    let mut index = 0;
+    while index < group.len() {
+        for generator in &generators {
+            let action = generator.compose(&group[index]);
+            if !group.contains(&action) {
+                group.push(action);
             }
-            
-            // If not already at start of subarray, swap
-            if min_ind != i {
-                let temp = quad_vec[i];
-                quad_vec[i] = quad_vec[min_ind];
-                quad_vec[min_ind] = temp;
-            }
-        }
-
-        final_quad = (quad_vec[0], quad_vec[1], quad_vec[2], quad_vec[3])
-    }
-    // Otherwise, iterate over all even perms and take the 'least'
-    else {
-        for swaps in [(0,1,0,1), (0,1,0,2), (0,1,0,3), (0,1,1,2), (0,1,1,3), (0,1,2,3), (0,2,0,3), (0,2,1,3), (0,2,2,3), (0,3,1,2), (1,2,2,3), (1,3,3,2)] {
-            let mut test_quad = quad.clone(); // at the start, this is 1 2 3 4
-            swap(&mut test_quad, swaps.0, swaps.1); 
-            swap(&mut test_quad, swaps.2, swaps.3); // 2 1 4 3
-            if better_than(&test_quad, &final_quad) {final_quad = test_quad.clone()}
         }
+        index += 1;
     }
 
-    final_quad
+    group.sort();
+    group
 }
 
+// The representative of an orbit of rowsum vectors, and the number of rowsum vectors in the orbit
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RowsumClass {
+    pub rowsums : Quad,
+    pub multiplicity : usize
+}
 
+impl RowsumClass {
+    pub fn from_str(line : &str) -> RowsumClass {
+        let values : Vec<isize> = line.split_whitespace().map(|elm| elm.parse().expect("Invalid rowsum class")).collect();
+        assert_eq!(values.len(), 5, "A rowsum class is given by four rowsums and a multiplicity");
+        RowsumClass { rowsums : (values[0], values[1], values[2], values[3]), multiplicity : values[4] as usize }
+    }
+}
 
+impl ToString for RowsumClass {
+    fn to_string(&self) -> String {
+        let (a,b,c,d) = self.rowsums;
+        format!("{a} {b} {c} {d} {}", self.multiplicity)
+    }
+}
 
-pub fn generate_rowsums(p : usize, seqtype : SequenceType) -> Vec<Quad>{
-    // generates all quadruplets of integers such that the sum of their squares equal 4*p
-    // it also generates their permutation, but only up to equivalence
-    let quads = sum_of_four_squares(4*p);
+fn rowsum_key(quad : &Quad) -> ([isize; 4], [bool; 4]) {
+    // the representative of an orbit is the rowsum vector with the smallest absolute values, then the fewest negative values first
+    let values = quad_to_array(quad);
+    (values.map(|r| r.abs()), values.map(|r| r < 0))
+}
 
-    let mut total_quadruplets : Vec<Quad> = vec![];
+fn signed_permutations(quad : &Quad) -> Vec<Quad> {
+    let values = quad_to_array(quad);
+    let mut result = vec![];
+    for perm in (0..4).permutations(4) {
+        for signs in 0..16 {
+            let image : Vec<isize> = (0..4).map(|i| if (signs >> i) & 1 == 1 {-values[perm[i]]} else {values[perm[i]]}).collect();
+            result.push((image[0], image[1], image[2], image[3]));
+        }
+    }
+    result.into_iter().unique().collect()
+}
 
+pub fn admissible_rowsums(p : usize, seqtype : SequenceType) -> Vec<Quad> {
+    // returns all the rowsum vectors a quadruple of the type of length p can have: the square sum is 4*p,
+    // every rowsum has the parity of p, and the skew-type sequences of good and best matrices have rowsum 1
     let parity = (p % 2) as isize;
+    let skew : Vec<usize> = seqtype.skew_sequences().iter()
+        .map(|tag| [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].iter().position(|elm| elm == tag).unwrap())
+        .collect();
+
+    sum_of_four_squares(4*p).iter()
+        .filter(|quad| quad_to_array(quad).iter().all(|r| r.rem_euclid(2) == parity))
+        .flat_map(signed_permutations)
+        .filter(|quad| skew.iter().all(|&i| quad_to_array(quad)[i] == 1))
+        .collect()
+}
 
-    for elm in quads {
-        if parity == elm.0 % 2 && parity == elm.1 % 2 && parity == elm.2 % 2 && parity == elm.3 % 2 {
-            total_quadruplets.push(elm);
-
-            if elm.0 != 0 && elm.0 != elm.1 && elm.1 != elm.2 && elm.2 != elm.3 && matches!(seqtype, SequenceType::QuaternionType){
-                let neg_quad : Quad = (elm.0,elm.1,elm.2,-elm.3);
-                total_quadruplets.push(neg_quad);
-            }
+pub fn generate_rowsum_classes(p : usize, seqtype : SequenceType) -> Vec<RowsumClass> {
+    // splits the admissible rowsum vectors into orbits under the equivalences of the type, sorted by representative
+    let actions = rowsum_actions(seqtype);
+    let admissible = admissible_rowsums(p, seqtype);
+    let admissible_set : HashSet<Quad> = admissible.iter().cloned().collect();
+
+    let mut classes : Vec<RowsumClass> = vec![];
+    let mut seen = HashSet::new();
+    for quad in &admissible {
+        if seen.contains(quad) {
+            continue;
         }
+        let orbit : HashSet<Quad> = actions.iter().map(|action| action.apply(quad)).collect();
+        assert!(orbit.iter().all(|elm| admissible_set.contains(elm)), "The equivalences of {} don't keep the rowsums admissible", seqtype.to_string());
+
+        let representative = *orbit.iter().min_by_key(|elm| rowsum_key(elm)).unwrap();
+        classes.push(RowsumClass { rowsums : representative, multiplicity : orbit.len() });
+        seen.extend(orbit);
     }
 
-    total_quadruplets
+    classes.sort_by_key(|class| rowsum_key(&class.rowsums));
+    classes
+}
+
+pub fn generate_rowsums(p : usize, seqtype : SequenceType) -> Vec<Quad>{
+    // generates the rowsums of the quadruples of the type of length p, one per class of rowsum vectors up to equivalence
+    generate_rowsum_classes(p, seqtype).iter().map(|class| class.rowsums).collect()
 }
@@ -0,0 +1,756 @@
+use std::{collections::HashMap, fs::File, io::Write, process::Command};
+use itertools::Itertools;
+
+// * Formulas for the SAT and pseudo-Boolean solvers
+// The variables are numbered from 1 and the literals follow the DIMACS convention, -v being the negation of v.
+// A formula is made of clauses and of cardinality constraints "exactly k of these literals are true", a literal
+// being allowed several times. The cardinality constraints are written as such in the OPB format, and encoded
+// with sequential counters in the CNF format
+
+pub type Lit = isize;
+
+fn var(lit : Lit) -> usize {
+    lit.unsigned_abs()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Formula {
+    num_vars : usize,
+    clauses : Vec<Vec<Lit>>,
+    cardinalities : Vec<(Vec<Lit>, usize)>,
+    xnors : HashMap<(Lit, Lit), Lit>         // the variables already defined by add_xnor
+}
+
+impl Formula {
+
+    pub fn new(num_vars : usize) -> Formula {
+        Formula { num_vars, ..Default::default() }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    pub fn clauses(&self) -> &Vec<Vec<Lit>> {
+        &self.clauses
+    }
+
+    pub fn cardinalities(&self) -> &Vec<(Vec<Lit>, usize)> {
+        &self.cardinalities
+    }
+
+    pub fn new_var(&mut self) -> Lit {
+        self.num_vars += 1;
+        self.num_vars as Lit
+    }
+
+    pub fn add_clause(&mut self, clause : Vec<Lit>) {
+        assert!(clause.iter().all(|&lit| lit != 0 && var(lit) <= self.num_vars), "Unknown variable in the clause");
+        self.clauses.push(clause);
+    }
+
+    pub fn add_equal(&mut self, a : Lit, b : Lit) {
+        self.add_clause(vec![-a, b]);
+        self.add_clause(vec![a, -b]);
+    }
+
+    pub fn add_exactly(&mut self, literals : Vec<Lit>, count : usize) {
+        assert!(literals.iter().all(|&lit| lit != 0 && var(lit) <= self.num_vars), "Unknown variable in the constraint");
+        self.cardinalities.push((literals, count));
+    }
+
+    // Returns a literal which is true iff a and b are equal, i.e. the product of the ±1 values of a and b
+    pub fn add_xnor(&mut self, a : Lit, b : Lit) -> Lit {
+        let key = if var(a) <= var(b) {(a, b)} else {(b, a)};
+        if let Some(&y) = self.xnors.get(&key) {
+            return y;
+        }
+        if let Some(&y) = self.xnors.get(&(-key.0, key.1)) {
+            return -y;
+        }
+
+        let y = self.new_var();
+        self.add_clause(vec![-y, -a, b]);
+        self.add_clause(vec![-y, a, -b]);
+        self.add_clause(vec![y, a, b]);
+        self.add_clause(vec![y, -a, -b]);
+        self.xnors.insert(key, y);
+        y
+    }
+
+    // Returns the literals c_1, ..., c_m where c_j is true iff at least j of the m literals are true. Unlike add_exactly,
+    // the counter is encoded in the clauses, so that its outputs can be used as assumptions by the solver
+    pub fn add_counter(&mut self, literals : Vec<Lit>) -> Vec<Lit> {
+        assert!(literals.iter().all(|&lit| lit != 0 && var(lit) <= self.num_vars), "Unknown variable in the counter");
+        let registers = encode_counter(&literals, literals.len(), &mut self.num_vars, &mut self.clauses);
+        registers[literals.len()].clone()
+    }
+
+    pub fn add_lex_leq(&mut self, xs : &[Lit], ys : &[Lit]) {
+        // forces xs <= ys in the lexicographic order, false being smaller than true
+        // prefix is true when the values before the index are equal, it is only forced, which is enough for the constraint
+        assert_eq!(xs.len(), ys.len());
+        let mut prefix : Option<Lit> = None;       // None stands for true
+        for (&x, &y) in xs.iter().zip(ys.iter()) {
+            if x == y {
+                continue;
+            }
+            let premise : Vec<Lit> = prefix.map_or(vec![], |e| vec![-e]);
+            self.add_clause([premise.clone(), vec![-x, y]].concat());
+
+            let next = self.new_var();
+            self.add_clause([premise.clone(), vec![x, y, next]].concat());
+            self.add_clause([premise, vec![-x, -y, next]].concat());
+            prefix = Some(next);
+        }
+    }
+
+    pub fn to_cnf(&self) -> (usize, Vec<Vec<Lit>>) {
+        // returns the number of variables and the clauses, the cardinality constraints being encoded by sequential counters
+        let mut num_vars = self.num_vars;
+        let mut clauses = self.clauses.clone();
+        for (literals, count) in &self.cardinalities {
+            encode_exactly(literals, *count, &mut num_vars, &mut clauses);
+        }
+        (num_vars, clauses)
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let (num_vars, clauses) = self.to_cnf();
+        dimacs(num_vars, &clauses)
+    }
+
+    pub fn to_opb(&self) -> String {
+        // The negated literals are written as 1 - x, so that the file only uses the linear constraints of the OPB format
+        let mut result = "* #variable= ".to_string() + &self.num_vars.to_string() + &" #constraint= " + &(self.clauses.len() + self.cardinalities.len()).to_string() + &"\n";
+        for clause in &self.clauses {
+            result += &opb_constraint(clause, ">=", 1);
+        }
+        for (literals, count) in &self.cardinalities {
+            result += &opb_constraint(literals, "=", *count as isize);
+        }
+        result
+    }
+
+}
+
+fn opb_constraint(literals : &[Lit], relation : &str, rhs : isize) -> String {
+    let mut coefficients : Vec<(usize, isize)> = vec![];
+    let mut rhs = rhs;
+    for &lit in literals {
+        let coefficient = if lit > 0 {1} else {rhs -= 1; -1};
+        match coefficients.iter_mut().find(|(v, _)| *v == var(lit)) {
+            Some((_, c)) => {*c += coefficient;}
+            None => {coefficients.push((var(lit), coefficient));}
+        }
+    }
+    coefficients.iter().filter(|(_, c)| *c != 0).map(|(v, c)| format!("{:+} x{} ", c, v)).collect::<String>() + relation + " " + &rhs.to_string() + " ;\n"
+}
+
+pub fn dimacs(num_vars : usize, clauses : &Vec<Vec<Lit>>) -> String {
+    let mut result = "p cnf ".to_string() + &num_vars.to_string() + &" " + &clauses.len().to_string() + &"\n";
+    for clause in clauses {
+        result += &(clause.iter().map(|lit| lit.to_string() + " ").collect::<String>() + &"0\n");
+    }
+    result
+}
+
+// The register r(i, j) of the sequential counter is true iff at least j of the first i literals are true.
+// It is a constant for j = 0 (true) and for j > i (false), and the registers are only needed up to j = count + 1
+fn encode_exactly(literals : &Vec<Lit>, count : usize, num_vars : &mut usize, clauses : &mut Vec<Vec<Lit>>) {
+    let m = literals.len();
+    if count > m {
+        clauses.push(vec![]);
+        return;
+    }
+
+    let registers = encode_counter(literals, count + 1, num_vars, clauses);
+    if count > 0 {
+        clauses.push(vec![registers[m][count - 1]]);
+    }
+    if count < m {
+        clauses.push(vec![-registers[m][count]]);
+    }
+}
+
+// Encodes the registers r(i, j) for j <= max and returns them, r(i, j) being at the index j - 1 of the i-th list
+fn encode_counter(literals : &Vec<Lit>, max : usize, num_vars : &mut usize, clauses : &mut Vec<Vec<Lit>>) -> Vec<Vec<Lit>> {
+    let m = literals.len();
+    let mut registers : Vec<Vec<Lit>> = vec![vec![]];
+    for i in 1..=m {
+        registers.push((1..=i.min(max)).map(|_| {*num_vars += 1; *num_vars as Lit}).collect());
+    }
+    // Some(lit) for a register, None for the constant true and Some(0) for the constant false
+    let register = |i : usize, j : usize| -> Option<Lit> {
+        if j == 0 {None} else if j > i {Some(0)} else {Some(registers[i][j-1])}
+    };
+
+    let mut push = |clause : Vec<(Option<Lit>, bool)>| {
+        // each literal is given with its polarity, the clause is dropped if it contains a true constant
+        let mut result = vec![];
+        for (lit, positive) in clause {
+            match lit {
+                None if positive => return,
+                Some(0) if !positive => return,
+                Some(lit) if lit != 0 => result.push(if positive {lit} else {-lit}),
+                _ => {}
+            }
+        }
+        clauses.push(result);
+    };
+
+    for i in 1..=m {
+        let l = Some(literals[i-1]);
+        for j in 1..=i.min(max) {
+            let (r, previous, smaller) = (register(i, j), register(i-1, j), register(i-1, j-1));
+            push(vec![(previous, false), (r, true)]);
+            push(vec![(smaller, false), (l, false), (r, true)]);
+            push(vec![(r, false), (previous, true), (l, true)]);
+            push(vec![(r, false), (previous, true), (smaller, true)]);
+        }
+    }
+    registers
+}
+
+
+// * CDCL solver
+// The usual conflict-driven clause learning : two watched literals per clause, learning of the first UIP clause with
+// the removal of the literals implied by the other ones, VSIDS branching with phase saving, Luby restarts, and the
+// learnt clauses of highest LBD (number of distinct levels) are regularly deleted. Clauses can be added between two
+// calls to solve, which is how the models are enumerated.
+// solve_with takes assumptions, the first decisions of every branch, so that the same solver and its learnt clauses are
+// reused for several related formulas. It also takes a check called on the partial assignment after every propagation,
+// which can return a cut : a clause false under the assignment, implied by the problem but not by the clauses, as in the
+// SAT+CAS searches where a computer algebra system rejects the partial solutions. The cuts are kept with the clauses
+
+const RESTART_BASE : u64 = 100;
+const ACTIVITY_DECAY : f64 = 0.95;
+const FIRST_REDUCTION : u64 = 2000;
+const REDUCTION_INCREMENT : u64 = 300;
+
+fn lit_index(lit : Lit) -> usize {
+    2 * (var(lit) - 1) + (lit < 0) as usize
+}
+
+fn lit_value(values : &[i8], lit : Lit) -> i8 {
+    if lit > 0 {values[var(lit)]} else {-values[var(lit)]}
+}
+
+fn luby(i : u64) -> u64 {
+    // the i-th term of the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+    let (mut size, mut seq) = (1, 0);
+    while size < i + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    let mut i = i;
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        seq -= 1;
+        i %= size;
+    }
+    1 << seq
+}
+
+// Binary heap of the unassigned variables by activity, with the position of every variable to update it
+#[derive(Default)]
+struct VarHeap {
+    heap : Vec<usize>,
+    positions : Vec<Option<usize>>
+}
+
+impl VarHeap {
+
+    fn new(num_vars : usize) -> VarHeap {
+        VarHeap { heap : (1..=num_vars).collect(), positions : (0..=num_vars).map(|v| v.checked_sub(1)).collect() }
+    }
+
+    fn swap(&mut self, i : usize, j : usize) {
+        self.heap.swap(i, j);
+        self.positions[self.heap[i]] = Some(i);
+        self.positions[self.heap[j]] = Some(j);
+    }
+
+    fn sift_up(&mut self, mut i : usize, activity : &[f64]) {
+        while i > 0 && activity[self.heap[(i - 1) / 2]] < activity[self.heap[i]] {
+            self.swap(i, (i - 1) / 2);
+            i = (i - 1) / 2;
+        }
+    }
+
+    fn sift_down(&mut self, mut i : usize, activity : &[f64]) {
+        loop {
+            let mut largest = i;
+            for child in [2*i + 1, 2*i + 2] {
+                if child < self.heap.len() && activity[self.heap[child]] > activity[self.heap[largest]] {
+                    largest = child;
+                }
+            }
+            if largest == i {
+                return;
+            }
+            self.swap(i, largest);
+            i = largest;
+        }
+    }
+
+    fn insert(&mut self, v : usize, activity : &[f64]) {
+        if self.positions[v].is_none() {
+            self.heap.push(v);
+            self.positions[v] = Some(self.heap.len() - 1);
+            self.sift_up(self.heap.len() - 1, activity);
+        }
+    }
+
+    fn increased(&mut self, v : usize, activity : &[f64]) {
+        if let Some(i) = self.positions[v] {
+            self.sift_up(i, activity);
+        }
+    }
+
+    fn pop(&mut self, activity : &[f64]) -> Option<usize> {
+        let top = *self.heap.first()?;
+        let last = self.heap.len() - 1;
+        self.swap(0, last);
+        self.heap.pop();
+        self.positions[top] = None;
+        self.sift_down(0, activity);
+        Some(top)
+    }
+
+}
+
+pub struct Solver {
+    num_vars : usize,
+    clauses : Vec<Vec<Lit>>,
+    lbds : Vec<Option<usize>>,          // the LBD of the learnt clauses, None for the clauses of the formula
+    watches : Vec<Vec<usize>>,          // the clauses whose first two literals contain the literal, by lit_index
+    values : Vec<i8>,                   // 1 for true, -1 for false and 0 for unassigned, by variable
+    levels : Vec<usize>,
+    reasons : Vec<Option<usize>>,
+    trail : Vec<Lit>,
+    trail_limits : Vec<usize>,
+    head : usize,                       // the literals of the trail before head have been propagated
+    activity : Vec<f64>,
+    increment : f64,
+    heap : VarHeap,
+    phases : Vec<bool>,
+    seen : Vec<bool>,
+    unsat : bool,
+    conflicts : u64,
+    next_reduction : u64
+}
+
+impl Solver {
+
+    pub fn new(num_vars : usize, clauses : &Vec<Vec<Lit>>) -> Solver {
+        let mut solver = Solver {
+            num_vars,
+            clauses : vec![],
+            lbds : vec![],
+            watches : vec![vec![]; 2 * num_vars],
+            values : vec![0; num_vars + 1],
+            levels : vec![0; num_vars + 1],
+            reasons : vec![None; num_vars + 1],
+            trail : vec![],
+            trail_limits : vec![],
+            head : 0,
+            activity : vec![0.; num_vars + 1],
+            increment : 1.,
+            heap : VarHeap::new(num_vars),
+            phases : vec![false; num_vars + 1],
+            seen : vec![false; num_vars + 1],
+            unsat : false,
+            conflicts : 0,
+            next_reduction : FIRST_REDUCTION
+        };
+        for clause in clauses {
+            solver.add_clause(clause.clone());
+        }
+        solver
+    }
+
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    pub fn add_clause(&mut self, clause : Vec<Lit>) {
+        // the clause is simplified by the assignments of level 0
+        self.backtrack(0);
+        let mut clause = clause;
+        clause.sort();
+        clause.dedup();
+        if clause.windows(2).any(|pair| pair[0] == -pair[1]) || clause.iter().any(|&lit| lit_value(&self.values, lit) == 1) {
+            return;
+        }
+        clause.retain(|&lit| lit_value(&self.values, lit) == 0);
+
+        match clause.len() {
+            0 => {self.unsat = true;}
+            1 => {self.enqueue(clause[0], None);}
+            _ => {self.attach(clause, None);}
+        }
+    }
+
+    fn attach(&mut self, clause : Vec<Lit>, lbd : Option<usize>) -> usize {
+        let index = self.clauses.len();
+        self.watches[lit_index(clause[0])].push(index);
+        self.watches[lit_index(clause[1])].push(index);
+        self.clauses.push(clause);
+        self.lbds.push(lbd);
+        index
+    }
+
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        // returns a model, indexed by the variables (the index 0 is unused)
+        self.solve_with(&[], &mut |_| None)
+    }
+
+    pub fn solve_with<F>(&mut self, assumptions : &[Lit], check : &mut F) -> Option<Vec<bool>> where F : FnMut(&[i8]) -> Option<Vec<Lit>> {
+        // returns a model satisfying the assumptions and accepted by check, which is given the values by variable
+        // (1 for true, -1 for false and 0 for unassigned). None means that there is no such model
+        if self.unsat {
+            return None;
+        }
+        self.backtrack(0);
+
+        let mut restarts = 0;
+        let mut budget = RESTART_BASE * luby(restarts);
+        loop {
+            let mut conflict = self.propagate();
+            if conflict.is_none() {
+                if let Some(cut) = check(&self.values) {
+                    conflict = self.add_cut(cut);
+                    if self.unsat {
+                        return None;
+                    }
+                }
+            }
+
+            if let Some(conflict) = conflict {
+                self.conflicts += 1;
+                if self.trail_limits.is_empty() {
+                    self.unsat = true;
+                    return None;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                }
+                else {
+                    let lbd = learnt.iter().map(|&lit| self.levels[var(lit)]).unique().count();
+                    let index = self.attach(learnt.clone(), Some(lbd));
+                    self.enqueue(learnt[0], Some(index));
+                }
+                self.increment /= ACTIVITY_DECAY;
+                budget = budget.saturating_sub(1);
+                continue;
+            }
+
+            if budget == 0 {
+                restarts += 1;
+                budget = RESTART_BASE * luby(restarts);
+                self.backtrack(0);
+                if self.conflicts >= self.next_reduction {
+                    self.next_reduction = self.conflicts + FIRST_REDUCTION + REDUCTION_INCREMENT * restarts;
+                    self.reduce_learnt();
+                }
+                continue;
+            }
+
+            // The assumptions are the decisions of the first levels, a level being opened even if the assumption is implied
+            if self.trail_limits.len() < assumptions.len() {
+                let lit = assumptions[self.trail_limits.len()];
+                let value = lit_value(&self.values, lit);
+                if value == -1 {
+                    return None;
+                }
+                self.trail_limits.push(self.trail.len());
+                if value == 0 {
+                    self.enqueue(lit, None);
+                }
+                continue;
+            }
+
+            match self.pick_branch() {
+                None => return Some(self.values.iter().map(|&v| v == 1).collect()),
+                Some(v) => {
+                    self.trail_limits.push(self.trail.len());
+                    self.enqueue(if self.phases[v] {v as Lit} else {-(v as Lit)}, None);
+                }
+            }
+        }
+    }
+
+    fn add_cut(&mut self, cut : Vec<Lit>) -> Option<usize> {
+        // adds a clause whose literals are all false, and returns its index if it is a conflict once the solver has
+        // backtracked to the highest level of its literals. Otherwise it propagates its first literal
+        let mut cut = cut;
+        cut.sort();
+        cut.dedup();
+        assert!(cut.iter().all(|&lit| lit_value(&self.values, lit) == -1), "The cut isn't false under the assignment");
+        cut.retain(|&lit| self.levels[var(lit)] > 0);
+        cut.sort_by_key(|&lit| std::cmp::Reverse(self.levels[var(lit)]));
+
+        if cut.len() <= 1 {
+            self.backtrack(0);
+            match cut.first() {
+                None => {self.unsat = true;}
+                Some(&lit) => {self.enqueue(lit, None);}
+            }
+            return None;
+        }
+
+        self.backtrack(self.levels[var(cut[0])]);
+        if self.levels[var(cut[1])] == self.levels[var(cut[0])] {
+            return Some(self.attach(cut, None));
+        }
+        self.backtrack(self.levels[var(cut[1])]);
+        let first = cut[0];
+        let index = self.attach(cut, None);
+        self.enqueue(first, Some(index));
+        None
+    }
+
+    fn enqueue(&mut self, lit : Lit, reason : Option<usize>) {
+        let v = var(lit);
+        self.values[v] = if lit > 0 {1} else {-1};
+        self.levels[v] = self.trail_limits.len();
+        self.reasons[v] = reason;
+        self.trail.push(lit);
+    }
+
+    fn backtrack(&mut self, level : usize) {
+        if self.trail_limits.len() <= level {
+            return;
+        }
+        for k in self.trail_limits[level]..self.trail.len() {
+            let lit = self.trail[k];
+            let v = var(lit);
+            self.phases[v] = lit > 0;
+            self.values[v] = 0;
+            self.reasons[v] = None;
+            self.heap.insert(v, &self.activity);
+        }
+        self.trail.truncate(self.trail_limits[level]);
+        self.trail_limits.truncate(level);
+        self.head = self.trail.len();
+    }
+
+    fn propagate(&mut self) -> Option<usize> {
+        // returns the index of a clause whose literals are all false, if any
+        while self.head < self.trail.len() {
+            let false_lit = -self.trail[self.head];
+            self.head += 1;
+
+            let mut watchers = std::mem::take(&mut self.watches[lit_index(false_lit)]);
+            let mut i = 0;
+            let mut conflict = None;
+            while i < watchers.len() {
+                let index = watchers[i];
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if lit_value(&self.values, clause[0]) == 1 {
+                    i += 1;
+                    continue;
+                }
+
+                // The clause now watches another literal which isn't false, if there is one
+                if let Some(k) = (2..clause.len()).find(|&k| lit_value(&self.values, clause[k]) != -1) {
+                    clause.swap(1, k);
+                    self.watches[lit_index(clause[1])].push(index);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+
+                let first = clause[0];
+                if lit_value(&self.values, first) == -1 {
+                    conflict = Some(index);
+                    break;
+                }
+                self.enqueue(first, Some(index));
+                i += 1;
+            }
+            self.watches[lit_index(false_lit)].append(&mut watchers);
+
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn analyze(&mut self, conflict : usize) -> (Vec<Lit>, usize) {
+        // returns the first UIP clause, its asserting literal first, and the level to backtrack to
+        let level = self.trail_limits.len();
+        let mut learnt : Vec<Lit> = vec![0];
+        let mut counter = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut implied : Option<Lit> = None;
+
+        loop {
+            let start = if implied.is_some() {1} else {0};
+            for k in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let v = var(lit);
+                if !self.seen[v] && self.levels[v] > 0 {
+                    self.seen[v] = true;
+                    self.bump(v);
+                    if self.levels[v] >= level {
+                        counter += 1;
+                    }
+                    else {
+                        learnt.push(lit);
+                    }
+                }
+            }
+
+            // The last literal of the trail involved in the conflict
+            loop {
+                index -= 1;
+                if self.seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            self.seen[var(lit)] = false;
+            implied = Some(lit);
+            counter -= 1;
+            if counter == 0 {
+                break;
+            }
+            clause = self.reasons[var(lit)].expect("A propagated literal has a reason");
+        }
+        learnt[0] = -implied.expect("No literal in the conflict ?");
+
+        // A literal is removed when the other literals of its reason are in the clause or assigned at level 0
+        let all_lits = learnt.clone();
+        learnt.retain(|&lit| {
+            match self.reasons[var(lit)] {
+                Some(reason) if lit != all_lits[0] => !self.clauses[reason][1..].iter().all(|&other| self.seen[var(other)] || self.levels[var(other)] == 0),
+                _ => true
+            }
+        });
+        for &lit in &all_lits[1..] {
+            self.seen[var(lit)] = false;
+        }
+
+        // The literal of the highest level is watched with the asserting literal
+        let mut backtrack_level = 0;
+        if learnt.len() > 1 {
+            let k = (1..learnt.len()).max_by_key(|&k| self.levels[var(learnt[k])]).expect("No literal ?");
+            learnt.swap(1, k);
+            backtrack_level = self.levels[var(learnt[1])];
+        }
+        (learnt, backtrack_level)
+    }
+
+    fn bump(&mut self, v : usize) {
+        self.activity[v] += self.increment;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.increment *= 1e-100;
+        }
+        self.heap.increased(v, &self.activity);
+    }
+
+    fn pick_branch(&mut self) -> Option<usize> {
+        while let Some(v) = self.heap.pop(&self.activity) {
+            if self.values[v] == 0 {
+                return Some(v);
+            }
+        }
+        None
+    }
+
+    fn reduce_learnt(&mut self) {
+        // deletes half of the learnt clauses, those of highest LBD, at level 0 where no clause is the reason of a literal
+        // that can be involved in a conflict
+        let mut learnt : Vec<usize> = (0..self.clauses.len()).filter(|&i| self.lbds[i].is_some()).collect();
+        learnt.sort_by_key(|&i| (self.lbds[i], self.clauses[i].len()));
+        let mut removed = vec![false; self.clauses.len()];
+        for &i in &learnt[learnt.len() / 2..] {
+            removed[i] = self.lbds[i] > Some(2);
+        }
+
+        let clauses = std::mem::take(&mut self.clauses);
+        let lbds = std::mem::take(&mut self.lbds);
+        self.watches.iter_mut().for_each(|w| w.clear());
+        self.reasons.iter_mut().for_each(|r| *r = None);
+        for (clause, lbd) in clauses.into_iter().zip(lbds).enumerate().filter(|(i, _)| !removed[*i]).map(|(_, c)| c) {
+            self.attach(clause, lbd);
+        }
+    }
+
+}
+
+
+// * Solving and enumerating the models
+// The models are enumerated by adding, after each model, the clause that excludes its values on the first variables
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolverBackend {
+    Embedded,               // the CDCL solver above
+    External(String)        // a solver binary reading a DIMACS file and printing the model on "v" lines, as in the SAT competitions
+}
+
+pub fn solve_external(solver : &String, num_vars : usize, clauses : &Vec<Vec<Lit>>, path : &String) -> Option<Vec<bool>> {
+    // writes the formula in path, calls the solver on it and parses its output
+    let mut f = File::create(path).expect("Invalid file ?");
+    f.write(dimacs(num_vars, clauses).as_bytes()).expect("Error when writing in the file");
+
+    let output = Command::new(solver).arg(path).output().expect("Error when calling the solver");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut satisfiable = None;
+    let mut model = vec![false; num_vars + 1];
+    for line in stdout.lines() {
+        if let Some(status) = line.strip_prefix("s ") {
+            satisfiable = Some(status.trim() == "SATISFIABLE");
+        }
+        if let Some(values) = line.strip_prefix("v ") {
+            for lit in values.split_whitespace().map(|s| s.trim_start_matches('x').parse::<Lit>().expect("Invalid literal in the model")) {
+                if lit > 0 {
+                    model[var(lit)] = true;
+                }
+            }
+        }
+    }
+
+    match satisfiable {
+        Some(true) => Some(model),
+        Some(false) => None,
+        None => panic!("The solver didn't print its status:\n{}", stdout)
+    }
+}
+
+pub fn all_models(formula : &Formula, primary : usize, backend : &SolverBackend, path : &String) -> Vec<Vec<bool>> {
+    // returns the models of the formula restricted to the variables 1..=primary, the models that only differ
+    // on the other variables being found once. path is the file used by the external solvers
+    let (num_vars, mut clauses) = formula.to_cnf();
+    let blocking = |model : &Vec<bool>| -> Vec<Lit> {(1..=primary).map(|v| if model[v] {-(v as Lit)} else {v as Lit}).collect()};
+
+    let mut models = vec![];
+    match backend {
+        SolverBackend::Embedded => {
+            let mut solver = Solver::new(num_vars, &clauses);
+            while let Some(model) = solver.solve() {
+                solver.add_clause(blocking(&model));
+                models.push(model[..=primary].to_vec());
+            }
+        },
+        SolverBackend::External(solver) => {
+            while let Some(model) = solve_external(solver, num_vars, &clauses, path) {
+                clauses.push(blocking(&model));
+                models.push(model[..=primary].to_vec());
+            }
+        }
+    }
+    models
+}
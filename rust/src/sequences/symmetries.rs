@@ -2,19 +2,92 @@ use crate::sequences::{williamson::*, equivalence::*};
 use std::collections::HashSet;
 
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Symmetry{ // enum for the different types of Quaternion Sequences
     I, II, III, IV
 }
 
-#[derive(Clone)]
+// The shapes of ±1 sequences that the pairs stage can enumerate directly, from the values of a prefix of the sequence
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SequenceShape {
+    Symmetric,          // seq[t] = seq[n-t]
+    Skew,               // seq[0] = 1 and seq[t] = -seq[n-t] for t > 0, which needs an odd length
+    Halves(Symmetry)    // the other values are deduced from the first half as in QS::set_value, II, III and IV needing an even length
+}
+
+impl SequenceShape {
+
+    // The length of the prefix that determines the sequence
+    pub fn prefix_length(&self, n : usize) -> usize {
+        match self {
+            Self::Symmetric => n/2 + 1,
+            Self::Skew | Self::Halves(Symmetry::I) => (n + 1)/2,
+            Self::Halves(_) => n/2
+        }
+    }
+
+    // Returns the sequence of this shape with the same prefix
+    pub fn apply(&self, seq : &Vec<i8>) -> Vec<i8> {
+        let n = seq.len();
+        let mut result = seq.clone();
+        for t in self.prefix_length(n).min(n)..n {
+            result[t] = match self {
+                Self::Symmetric => result[n-t],
+                Self::Skew => -result[n-t],
+                Self::Halves(Symmetry::I) => result[n-1-t],
+                Self::Halves(Symmetry::II) => if (t - n/2) % 2 == 0 {result[t - n/2]} else {-result[t - n/2]},
+                Self::Halves(Symmetry::III) => if ((t - n/2)/2) % 2 == 0 {result[t - n/2]} else {-result[t - n/2]},
+                Self::Halves(Symmetry::IV) => -result[t - n/2]
+            };
+        }
+        if *self == Self::Skew && n > 0 {
+            result[0] = 1;
+        }
+        result
+    }
+
+    pub fn contains(&self, seq : &Vec<i8>) -> bool {
+        match self {
+            Self::Skew => skew_type(seq),
+            Self::Halves(Symmetry::II | Symmetry::III | Symmetry::IV) if seq.len() % 2 == 1 => false,
+            _ => self.apply(seq) == *seq
+        }
+    }
+}
+
+impl ToString for SequenceShape {
+    fn to_string(&self) -> String {
+        match self {
+            SequenceShape::Symmetric => "sym".to_string(),
+            SequenceShape::Skew => "skew".to_string(),
+            SequenceShape::Halves(Symmetry::I) => "I".to_string(),
+            SequenceShape::Halves(Symmetry::II) => "II".to_string(),
+            SequenceShape::Halves(Symmetry::III) => "III".to_string(),
+            SequenceShape::Halves(Symmetry::IV) => "IV".to_string()
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
 pub enum RowsumPairing{
-    WX, WY, WZ
+    WX, WY, WZ, AUTO    // AUTO chooses the pairing giving the fewest pairs for each rowsum
+}
+
+impl ToString for RowsumPairing {
+    fn to_string(&self) -> String {
+        match self {
+            RowsumPairing::WX => "WX".to_string(),
+            RowsumPairing::WY => "WY".to_string(),
+            RowsumPairing::WZ => "WZ".to_string(),
+            RowsumPairing::AUTO => "AUTO".to_string()
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
 pub enum SequenceType{ // enum for the different types of Quadruplets of sequences
-    Williamson, WilliamsonType, QuaternionType, ItoType, ExtraTypeI, ExtraTypeII, ExtraTypeIII
+    Williamson, WilliamsonType, QuaternionType, ItoType, ExtraTypeI, ExtraTypeII, ExtraTypeIII,
+    Good, Best      // good matrices have W skew-type and X, Y, Z symmetric, best matrices have W, X, Y skew-type and Z symmetric
 }
 
 impl SequenceType {
@@ -24,6 +97,39 @@ impl SequenceType {
             Self::QuaternionType => vec![equivalent_uniform_shift, equivalent_even_alternated_negation, equivalent_automorphism, equivalent_negate_swap, equivalent_dual_half_shift],
             Self::WilliamsonType => vec![equivalent_negate, equivalent_uniform_shift, equivalent_reorder, equivalent_even_alternated_negation, equivalent_automorphism, equivalent_reverse],
             Self::Williamson => vec![equivalent_negate, equivalent_uniform_half_shift, equivalent_reorder, equivalent_even_alternated_negation, equivalent_automorphism],
+            Self::Good | Self::Best => vec![equivalent_negate_symmetric, equivalent_reorder_same_kind, equivalent_transpose, equivalent_automorphism],
+            _ => vec![]
+        }
+    }
+
+    // Returns the equivalence operations that keep every sequence in place with the same rowsum,
+    // so that they act on the first sequence alone during the search
+    pub fn rowsum_preserving_equivalences(&self) -> Vec<fn(&QuadSeq, SequenceType, bool) -> HashSet<QuadSeq>> {
+        match self {
+            Self::QuaternionType => vec![equivalent_uniform_shift, equivalent_automorphism, equivalent_dual_half_shift],
+            Self::WilliamsonType => vec![equivalent_uniform_shift, equivalent_automorphism, equivalent_reverse],
+            Self::Williamson => vec![equivalent_uniform_half_shift, equivalent_automorphism],
+            Self::Good | Self::Best => vec![equivalent_transpose, equivalent_automorphism],
+            _ => vec![]
+        }
+    }
+
+    // Returns the shape that the sequence tag must have in a quadruple of this type, if any
+    pub fn shape(&self, tag : &SequenceTag) -> Option<SequenceShape> {
+        match self {
+            Self::Williamson => Some(SequenceShape::Symmetric),
+            Self::Good | Self::Best if self.skew_sequences().contains(tag) => Some(SequenceShape::Skew),
+            Self::Good | Self::Best => Some(SequenceShape::Symmetric),
+            _ => None
+        }
+    }
+
+    // Returns the sequences that must be skew-type, the other ones being symmetric
+    // A skew-type sequence has rowsum 1, so these are the first sequences once the rowsums are sorted
+    pub fn skew_sequences(&self) -> Vec<SequenceTag> {
+        match self {
+            Self::Good => vec![SequenceTag::W],
+            Self::Best => vec![SequenceTag::W, SequenceTag::X, SequenceTag::Y],
             _ => vec![]
         }
     }
@@ -38,7 +144,9 @@ impl ToString for SequenceType {
             SequenceType::ItoType => "its".to_string(),
             SequenceType::ExtraTypeI => "et1".to_string(),
             SequenceType::ExtraTypeII => "et2".to_string(),
-            SequenceType::ExtraTypeIII => "et3".to_string()
+            SequenceType::ExtraTypeIII => "et3".to_string(),
+            SequenceType::Good => "gm".to_string(),
+            SequenceType::Best => "bm".to_string()
         }
     }
 }
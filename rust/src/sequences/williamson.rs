@@ -173,6 +173,8 @@ impl QuadSeq{
             SequenceType::QuaternionType => {self.verify_qts()},
             SequenceType::WilliamsonType => {self.verify_wts()},
             SequenceType::Williamson => {self.verify_ws()},
+            SequenceType::Good => {self.verify_gm()},
+            SequenceType::Best => {self.verify_bm()},
             _ => {false}
         }
     }
@@ -199,6 +201,22 @@ impl QuadSeq{
         true
     }
 
+    pub fn verify_gm(&self) -> bool {
+        self.is_periodic_complementary() && self.has_skew_structure(SequenceType::Good)
+    }
+
+    pub fn verify_bm(&self) -> bool {
+        self.is_periodic_complementary() && self.has_skew_structure(SequenceType::Best)
+    }
+
+    pub fn has_skew_structure(&self, seqtype : SequenceType) -> bool {
+        // tests if the sequences given by seqtype.skew_sequences() are skew-type and the other ones symmetric
+        let skew = seqtype.skew_sequences();
+        [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z].iter().all(|tag| {
+            if skew.contains(tag) {skew_type(&self.sequence(*tag))} else {symmetric(&self.sequence(*tag))}
+        })
+    }
+
     pub fn is_periodic_complementary(&self) -> bool{
         // tests if the sequences are periodic complementary
         for offset in 1..=((self.size-1)) {
@@ -209,6 +227,26 @@ impl QuadSeq{
         true
     }
 
+    pub fn is_aperiodic_complementary(&self) -> bool {
+        // tests if the sequences are aperiodic complementary, which implies both the periodic and negaperiodic properties
+        for offset in 1..self.size {
+            if aperiodic_autocorrelation(&self.a, offset) + aperiodic_autocorrelation(&self.b, offset) + aperiodic_autocorrelation(&self.c, offset) + aperiodic_autocorrelation(&self.d, offset) != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn is_negaperiodic_complementary(&self) -> bool {
+        // tests if the sequences are negaperiodic complementary
+        for offset in 1..self.size {
+            if negaperiodic_autocorrelation(&self.a, offset) + negaperiodic_autocorrelation(&self.b, offset) + negaperiodic_autocorrelation(&self.c, offset) + negaperiodic_autocorrelation(&self.d, offset) != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
     pub fn is_amicable(&self) -> bool { // This function is a stronger version of the condition in verify_cross_correlation
         for offset in 1..self.size {
             if !(cross_correlation(&self.a, &self.b, offset) == cross_correlation(&self.b, &self.a, offset) &&
@@ -310,6 +348,25 @@ pub fn periodic_autocorrelation(seq : &Vec<i8>, offset : usize) -> isize {
 }
 
 
+pub fn symmetric(seq : &Vec<i8>) -> bool {
+    // tests if the sequence is symmetric
+    let n = seq.len();
+
+    for t in 1..=(n/2) { // Trying half the values is sufficient
+        if seq[t] != seq[n-t] {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn skew_type(seq : &Vec<i8>) -> bool {
+    // tests if the sequence is skew-type, i.e. seq[0] = 1 and seq[n-i] = -seq[i], so that its circulant matrix A satisfies A + A^T = 2I
+    let n = seq.len();
+    n > 0 && seq[0] == 1 && (1..n).all(|t| seq[n-t] == -seq[t])
+}
+
+
 pub fn cross_correlation(seq1 : &Vec<i8>, seq2 : &Vec<i8>, offset : usize) -> isize {
     // computes the periodic cross correlation fo the sequences
     assert!(seq1.len() == seq2.len());
@@ -324,6 +381,58 @@ pub fn cross_correlation(seq1 : &Vec<i8>, seq2 : &Vec<i8>, offset : usize) -> is
 }
 
 
+pub fn aperiodic_autocorrelation(seq : &Vec<i8>, offset : usize) -> isize {
+    // computes the aperiodic (non-periodic) auto correlation of the sequence, the terms that would wrap around are dropped
+    let n = seq.len();
+    let mut res = 0;
+    for i in 0..n.saturating_sub(offset) {
+        res += (seq[i]*seq[i + offset]) as isize;
+    }
+
+    res
+}
+
+
+pub fn aperiodic_cross_correlation(seq1 : &Vec<i8>, seq2 : &Vec<i8>, offset : usize) -> isize {
+    // computes the aperiodic cross correlation of the sequences, with the same offset convention as cross_correlation
+    // the sequences may have different lengths, the missing values count as 0
+    let mut res = 0;
+    for i in offset..seq1.len().min(seq2.len() + offset) {
+        res += (seq1[i]*seq2[i - offset]) as isize;
+    }
+
+    res
+}
+
+
+pub fn negaperiodic_autocorrelation(seq : &Vec<i8>, offset : usize) -> isize {
+    // computes the negaperiodic auto correlation of the sequence, the terms that wrap around are negated
+    let n = seq.len();
+    let mut res = 0;
+    for i in 0..n {
+        let term = (seq[i]*seq[(i + offset) % n]) as isize;
+        res += if i + offset >= n {-term} else {term};
+    }
+
+    res
+}
+
+
+pub fn negaperiodic_cross_correlation(seq1 : &Vec<i8>, seq2 : &Vec<i8>, offset : usize) -> isize {
+    // computes the negaperiodic cross correlation of the sequences, with the same offset convention as cross_correlation
+    assert!(seq1.len() == seq2.len());
+
+    let n = seq1.len();
+    let mut res = 0;
+    for i in 0..n {
+        let term = (seq1[i]*seq2[(i + n - offset) % n]) as isize;
+        res += if i < offset {-term} else {term};
+    }
+
+    res
+}
+
+
 
 fn element_to_string(elem : i8) -> String {
     match elem {
@@ -2,9 +2,16 @@ mod test_sequence;
 mod test_williamson;
 mod test_rowsum;
 mod test_fourier;
-mod test_equation;
 mod test_matrices;
 mod test_equivalence;
 mod test_matching;
 mod test_product;
 mod test_mum;
+mod test_matrix_invariants;
+mod test_hurwitz;
+mod test_constructions;
+mod test_backtrack;
+mod test_aperiodic;
+mod test_complex;
+mod test_sat;
+mod pipeline;
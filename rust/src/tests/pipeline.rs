@@ -0,0 +1,54 @@
+// Helpers shared by the tests that run the pairs, sort and join stages of a search, and by the brute force searches
+// they are compared to
+
+#[cfg(test)]
+pub mod helpers {
+    use std::{collections::HashMap, fs, hash::Hash};
+
+    use itertools::Itertools;
+
+    // Runs the pairs stage in a temporary folder, sorts the .pair files in memory and returns what the join stage finds
+    pub fn run_pipeline<T>(name : &str, write_pairs : impl FnOnce(&String), join : impl FnOnce(&String) -> Vec<T>) -> Vec<T> {
+        let folder = std::env::temp_dir().join(name).to_str().unwrap().to_string();
+        let _ = fs::remove_dir_all(&folder);
+        write_pairs(&folder);
+
+        for directory in fs::read_dir(&folder).unwrap() {
+            for file in fs::read_dir(directory.unwrap().path()).unwrap() {
+                let path = file.unwrap().path().display().to_string();
+                if path.ends_with(".pair") {
+                    let mut lines : Vec<String> = fs::read_to_string(&path).unwrap().lines().map(|line| line.to_string() + "\n").collect();
+                    lines.sort();
+                    fs::write(path.clone() + ".sorted", lines.concat()).unwrap();
+                    fs::remove_file(&path).unwrap();
+                }
+            }
+        }
+
+        let found = join(&folder);
+        fs::remove_dir_all(&folder).expect("Error when removing the dir");
+        found
+    }
+
+    // Every sequence of length len with its entries in values
+    pub fn all_sequences<T : Copy>(values : &[T], len : usize) -> Vec<Vec<T>> {
+        itertools::repeat_n(values.iter().copied(), len).multi_cartesian_product().collect()
+    }
+
+    // Matches every element of left with the elements of right of the same key, as the join stage does with the sorted files
+    pub fn match_by_key<A : Clone, B : Clone, K : Eq + Hash>(left : impl IntoIterator<Item = A>, right : impl IntoIterator<Item = B>,
+                                                            left_key : impl Fn(&A) -> K, right_key : impl Fn(&B) -> K) -> Vec<(A, B)> {
+        let mut table : HashMap<K, Vec<B>> = HashMap::new();
+        for elm in right {
+            table.entry(right_key(&elm)).or_default().push(elm);
+        }
+
+        let mut matches = vec![];
+        for elm in left {
+            for other in table.get(&left_key(&elm)).unwrap_or(&vec![]) {
+                matches.push((elm.clone(), other.clone()));
+            }
+        }
+        matches
+    }
+}
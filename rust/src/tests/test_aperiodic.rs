@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::find::find_aperiodic::{join_aperiodic_pairs, write_aperiodic_pairs, APERIODIC_OVERSAMPLING};
+    use crate::tests::pipeline::helpers::{all_sequences, match_by_key, run_pipeline};
+    use crate::sequences::{aperiodic::AperiodicFamily, fourier::SpectralFilter, williamson::aperiodic_autocorrelation};
+
+    // Every quadruple of the family up to equivalence, found by matching all the pairs of sequences in memory
+    fn brute_force(family : AperiodicFamily, n : usize) -> HashSet<[Vec<i8>; 4]> {
+        let lengths = family.lengths(n);
+        let weights = family.weights();
+        let key = |(seq1, seq2) : &(Vec<i8>, Vec<i8>), w : isize| -> Vec<isize> {
+            (1..lengths[0]).map(|t| w * (aperiodic_autocorrelation(seq1, t) + aperiodic_autocorrelation(seq2, t))).collect()
+        };
+        let pairs = |len1, len2| itertools::iproduct!(all_sequences(&[1, -1], len1), all_sequences(&[1, -1], len2));
+
+        let mut result = HashSet::new();
+        for ((seq_a, seq_b), (seq_c, seq_d)) in match_by_key(pairs(lengths[0], lengths[1]), pairs(lengths[2], lengths[3]),
+                                                             |pair| key(pair, weights[0]), |pair| key(pair, -weights[2])) {
+            let quad = [seq_a, seq_b, seq_c, seq_d];
+            assert!(family.verify(&quad));
+            result.insert(family.canonical(&quad));
+        }
+        result
+    }
+
+    #[test]
+    fn test_rowsums() {
+        for (family, n) in [(AperiodicFamily::TurynType, 6), (AperiodicFamily::TurynType, 7), (AperiodicFamily::Base(1), 5), (AperiodicFamily::Base(2), 4)] {
+            let lengths = family.lengths(n);
+            let weights = family.weights();
+            for rs in family.generate_rowsums(n) {
+                let rowsums = [rs.0, rs.1, rs.2, rs.3];
+                assert_eq!((0..4).map(|i| weights[i] * rowsums[i] * rowsums[i]).sum::<isize>(), family.psd_total(n));
+                assert!((0..4).all(|i| rowsums[i] >= 0 && rowsums[i] as usize <= lengths[i] && (lengths[i] - rowsums[i] as usize) % 2 == 0));
+            }
+        }
+        assert!(AperiodicFamily::TurynType.generate_rowsums(7).is_empty());
+    }
+
+    #[test]
+    fn test_canonical() {
+        let family = AperiodicFamily::Base(1);
+        let quad = [vec![1,1,-1], vec![1,1,1], vec![1,-1], vec![-1,1]];
+        assert!(family.verify(&quad));
+
+        // The images of the quadruple by the equivalences have the same canonical form
+        let canonical = family.canonical(&quad);
+        let negated = [quad[0].clone(), quad[1].iter().map(|x| -x).collect(), quad[2].clone(), quad[3].clone()];
+        let reversed = [quad[0].iter().rev().cloned().collect(), quad[1].clone(), quad[2].clone(), quad[3].clone()];
+        let swapped = [quad[1].clone(), quad[0].clone(), quad[3].clone(), quad[2].clone()];
+        let alternated = quad.clone().map(|seq| seq.iter().enumerate().map(|(i, &x)| if i % 2 == 1 {-x} else {x}).collect::<Vec<i8>>());
+        for image in [negated, reversed, swapped, alternated] {
+            assert!(family.verify(&image));
+            assert_eq!(family.canonical(&image), canonical);
+        }
+    }
+
+    #[test]
+    fn test_same_as_brute_force() {
+        // The pairs, sort and join stages find every quadruple up to equivalence
+        for (family, n) in [(AperiodicFamily::TurynType, 6), (AperiodicFamily::TurynType, 5), (AperiodicFamily::Base(1), 5), (AperiodicFamily::Base(2), 3)] {
+            let found : HashSet<[Vec<i8>; 4]> = run_pipeline(&("test_aperiodic_".to_string() + &family.to_string() + &"_" + &n.to_string()),
+                |folder| {write_aperiodic_pairs(folder, family, n, SpectralFilter::new(0.0001, APERIODIC_OVERSAMPLING));},
+                |folder| join_aperiodic_pairs(folder, family, n)).into_iter().collect();
+            assert_eq!(found, brute_force(family, n));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::find::{find_backtrack::{find_odd_sequences, find_sequences}, find_optim};
+    use crate::sequences::{equivalence::{odd_perfect_equivalents, reduce_odd_perfect}, hurwitz::Hurwitz, sequence::{QS, QPLUS, Q24}, symmetries::Symmetry};
+
+    fn strings(size : usize, symmetry : Option<Symmetry>) -> HashSet<String> {
+        find_sequences(size, symmetry, &QPLUS).iter().map(|qs| qs.to_string_raw()).collect()
+    }
+
+    #[test]
+    fn test_same_as_exhaustive() {
+        // The pruning doesn't lose any sequence found by trying every value
+        for n in 1..=5 {
+            let sequences = find_sequences(n, None, &QPLUS);
+            assert!(sequences.iter().all(|qs| qs.is_perfect()));
+            assert_eq!(sequences.len(), find_optim::find(n, None));
+        }
+        for n in 1..=9 {
+            assert_eq!(find_sequences(n, Some(Symmetry::I), &QPLUS).len(), find_optim::find(n, Some(Symmetry::I)));
+        }
+        // find_optim only enforces the symmetries II and III correctly, it doesn't negate the image of the first value for IV
+        for n in [4, 8] {
+            assert_eq!(find_sequences(n, Some(Symmetry::II), &QPLUS).len(), find_optim::find(n, Some(Symmetry::II)));
+            assert_eq!(find_sequences(n, Some(Symmetry::III), &QPLUS).len(), find_optim::find(n, Some(Symmetry::III)));
+        }
+        for n in 1..=4 {
+            assert_eq!(find_sequences(n, None, &Q24).len(), find_optim::find_q24(n, None));
+        }
+    }
+
+    #[test]
+    fn test_symmetries() {
+        // The sequences found with a symmetry are exactly the perfect sequences with that symmetry
+        for n in [4, 6] {
+            let all = strings(n, None);
+            for (symmetry, sign) in [(Symmetry::II, (|i : usize| i % 2 == 1) as fn(usize) -> bool), (Symmetry::III, |i| (i/2) % 2 == 1), (Symmetry::IV, |_| true)] {
+                let expected : HashSet<String> = find_sequences(n, None, &QPLUS).into_iter()
+                    .filter(|qs| (0..n/2).all(|i| qs.values()[n/2 + i] == if sign(i) {-qs.values()[i]} else {qs.values()[i]}))
+                    .map(|qs| qs.to_string_raw()).collect();
+                let found = strings(n, Some(symmetry));
+                assert!(found.is_subset(&all));
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_odd_same_as_exhaustive() {
+        // Every odd-perfect sequence starting with 1 is found
+        for n in 1..=4 {
+            let mut expected : HashSet<String> = HashSet::new();
+            for index in 0..QPLUS.len().pow(n as u32 - 1) {
+                let mut qs = QS::new(n, None);
+                qs.set_values((0..n).map(|i| if i == 0 {QPLUS[0]} else {QPLUS[(index / QPLUS.len().pow(i as u32 - 1)) % QPLUS.len()]}).collect());
+                if qs.is_odd_perfect() {
+                    expected.insert(qs.to_string_raw());
+                }
+            }
+            let found : HashSet<String> = find_odd_sequences(n, None, &QPLUS).iter().map(|qs| qs.to_string_raw()).collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_odd_equivalences() {
+        for n in 3..=6 {
+            let sequences = find_odd_sequences(n, None, &QPLUS);
+            for qs in &sequences {
+                let seq : Vec<Hurwitz> = qs.values().iter().map(Hurwitz::from_quaternion).collect();
+                for equivalent in odd_perfect_equivalents(&seq) {
+                    let mut image = QS::new(n, None);
+                    image.set_values(equivalent.iter().map(|q| q.to_quaternion()).collect());
+                    assert!(image.is_odd_perfect());
+                }
+            }
+
+            let reduced = reduce_odd_perfect(&sequences, &QPLUS);
+            let strings = |list : &Vec<QS>| list.iter().map(|qs| qs.to_string_raw()).collect::<Vec<String>>();
+            assert_eq!(strings(&reduce_odd_perfect(&reduced, &QPLUS)), strings(&reduced));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::find::find_complex::{join_complex_pairs, write_complex_pairs};
+    use crate::tests::pipeline::helpers::{self, match_by_key, run_pipeline};
+    use crate::sequences::{complex::*, fourier::SpectralFilter};
+
+    fn all_sequences(len : usize) -> Vec<CS> {
+        helpers::all_sequences(&CS_VALUES, len).into_iter().map(CS::from_values).collect()
+    }
+
+    // Every complementary pair up to equivalence, found by matching all the sequences in memory
+    fn brute_force(n : usize) -> HashSet<(CS, CS)> {
+        let key = |seq : &CS, sign : isize| -> Vec<Gaussian> {(1..=n/2).map(|t| seq.periodic_autocorrelation(t) * sign).collect()};
+
+        let mut result = HashSet::new();
+        for pair in match_by_key(all_sequences(n), all_sequences(n), |seq| key(seq, 1), |seq| key(seq, -1)) {
+            assert!(is_periodic_complementary(&[pair.0.clone(), pair.1.clone()]));
+            result.insert(canonical_pair(&pair));
+        }
+        result
+    }
+
+    #[test]
+    fn test_from_pair() {
+        for seq in all_sequences(4) {
+            let (a, b) = seq.to_pair();
+            assert_eq!(CS::from_pair(&a, &b), seq);
+
+            // The real parts of the autocorrelations of c are the mean of those of a and b
+            for t in 0..4 {
+                let correlation = |s : &Vec<i8>| -> isize {(0..4).map(|i| (s[i] * s[(i+t) % 4]) as isize).sum()};
+                assert_eq!(2 * seq.periodic_autocorrelation(t).re, correlation(&a) + correlation(&b));
+            }
+        }
+        assert_eq!(CS::from_str(&"+-iI".to_string()).to_string(), "[+-iI]");
+    }
+
+    #[test]
+    fn test_perfect() {
+        assert!(CS::from_str(&"+i".to_string()).is_perfect());
+        assert!(CS::from_str(&"+++-".to_string()).is_perfect());
+        assert!(!CS::from_str(&"++++".to_string()).is_perfect());
+        assert_eq!(all_sequences(3).iter().filter(|seq| seq.is_perfect()).count(), 0);
+    }
+
+    #[test]
+    fn test_complementary() {
+        // A Golay pair is complementary for both correlations
+        let golay = (CS::from_str(&"++".to_string()), CS::from_str(&"+-".to_string()));
+        assert!(is_periodic_complementary(&[golay.0.clone(), golay.1.clone()]));
+        assert!(is_aperiodic_complementary(&[golay.0.clone(), golay.1.clone()]));
+
+        let pair = (CS::from_str(&"++i".to_string()), CS::from_str(&"+-I".to_string()));
+        assert!(!is_periodic_complementary(&[pair.0.clone(), pair.1.clone()]));
+        assert!(complex_hadamard(&pair).is_none());
+
+        for n in 1..=5 {
+            for pair in brute_force(n) {
+                let hm = complex_hadamard(&pair).expect("Not a complementary pair ?");
+                assert!(hm.is_complex_hadamard());
+                assert_eq!(canonical_pair(&(pair.1.shifted(1), pair.0.multiplied(CS_VALUES[2]))), pair);
+                assert_eq!(canonical_pair(&(pair.0.conjugate().transposed(), pair.1.conjugate().transposed())), pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rowsums() {
+        // Every complementary pair has rowsums in the list, up to the units and the swap of its sequences
+        let rowsums = |seq : &CS| -> (isize, isize) {
+            let (a, b) = seq.to_pair();
+            (a.iter().map(|&x| x as isize).sum(), b.iter().map(|&x| x as isize).sum())
+        };
+        for n in 1..=5 {
+            let list = generate_complex_rowsums(n);
+            for rs in &list {
+                assert_eq!(rs.0*rs.0 + rs.1*rs.1 + rs.2*rs.2 + rs.3*rs.3, 4 * n as isize);
+            }
+            for (c, d) in brute_force(n) {
+                assert!(CS_VALUES.iter().any(|u| CS_VALUES.iter().any(|v| {
+                    let (rs_c, rs_d) = (rowsums(&c.multiplied(*u)), rowsums(&d.multiplied(*v)));
+                    list.contains(&(rs_c.0, rs_c.1, rs_d.0, rs_d.1)) || list.contains(&(rs_d.0, rs_d.1, rs_c.0, rs_c.1))
+                })));
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_as_brute_force() {
+        // The pairs, sort and join stages find every complementary pair up to equivalence
+        for n in 1..=5 {
+            let found : HashSet<(CS, CS)> = run_pipeline(&("test_complex_".to_string() + &n.to_string()),
+                |folder| {write_complex_pairs(folder, n, SpectralFilter::periodic(0.));},
+                |folder| join_complex_pairs(folder, n)).into_iter().collect();
+            assert_eq!(found, brute_force(n));
+        }
+    }
+}
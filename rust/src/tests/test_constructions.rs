@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::sequences::{constructions::*, sequence::QS, symmetries::SequenceType, williamson::QuadSeq};
+
+    fn quad_seq(s : &str) -> QuadSeq {
+        QuadSeq::from_pqs(&QS::from_str(&s.to_string()))
+    }
+
+    #[test]
+    fn test_double() {
+        let seq = quad_seq("+JJ+x");
+
+        let doubled = double(&seq, SequenceType::WilliamsonType).expect("Doubling keeps Williamson type sequences");
+        assert_eq!(doubled.size(), 10);
+        assert!(doubled.verify(SequenceType::QuaternionType));
+
+        // Only for odd lengths
+        assert!(double(&quad_seq("+YIQ"), SequenceType::WilliamsonType).is_none());
+    }
+
+    #[test]
+    fn test_product() {
+        let product = product(&quad_seq("+J"), &quad_seq("+JJ+x"), SequenceType::WilliamsonType).expect("The product has its entries in QPLUS");
+        assert_eq!(product.size(), 10);
+    }
+
+    #[test]
+    fn test_goethals_seidel() {
+        let hm = goethals_seidel(&quad_seq("+YIQ")).expect("The sequences are periodic complementary");
+        assert_eq!(hm.size(), 16);
+        assert!(hm.verify());
+    }
+
+    #[test]
+    fn test_skew_hadamard() {
+        let mut good = QuadSeq::new(7);
+        good.set_all_values((&vec![1,-1,-1,1,-1,1,1], &vec![-1,-1,-1,1,1,-1,-1], &vec![-1,-1,1,-1,-1,1,-1], &vec![-1,1,-1,-1,-1,-1,1]));
+        assert!(good.verify(SequenceType::Good));
+
+        let hm = skew_hadamard(&good).expect("W is skew-type");
+        assert_eq!(hm.size(), 28);
+        assert!(hm.verify() && hm.is_skew());
+
+        // The first sequence isn't skew-type
+        assert!(skew_hadamard(&quad_seq("+YIQ")).is_none());
+    }
+}
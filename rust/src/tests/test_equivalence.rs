@@ -5,10 +5,14 @@ mod tests {
 
     use std::{collections::{HashMap, HashSet}, env, time::Instant};
 
-    use crate::sequences::{equivalence::*, symmetries::SequenceType, williamson::{QuadSeq, QUADRUPLETS}};
+    use crate::sequences::{equivalence::*, symmetries::SequenceType, williamson::{skew_type, symmetric, QuadSeq, SequenceTag, QUADRUPLETS}};
     use crate::sequences::sequence::*;
     use crate::find::find_unique::reduce_to_equivalence;
     use crate::read_lines;
+    use crate::find::find_unique::reduce_to_canonical_reps;
+    use crate::sequences::{rowsum::{generate_rowsums, generate_sequences_with_rowsum}, williamson::periodic_autocorrelation};
+    use crate::tests::pipeline::helpers::match_by_key;
+    use itertools::iproduct;
 
     #[test]
     fn check_equiv() {
@@ -69,7 +73,7 @@ mod tests {
 
         let sym = generate_symmetry_group(8, SequenceType::QuaternionType, &vec![equivalent_alternated_negation, equivalent_automorphism, equivalent_uniform_shift]);
 
-        println!("Canonical form:\n{}", qt_canonical(&qs, &sym).to_string());
+        println!("Canonical form:\n{}", qt_canonical(&qs, &sym, SequenceType::QuaternionType).to_string());
     }
 
     #[test]
@@ -520,4 +524,70 @@ mod tests {
 
         }
     }
-}
\ No newline at end of file
+
+    // Finds all the quadruples with the rowsums used by the search, keeping only the canonical first sequences if prune is set,
+    // and returns the number of equivalence classes
+    fn count_classes_with_rowsums(p : usize, seqtype : SequenceType, prune : bool) -> usize {
+        let mut result = vec![];
+        for rs in generate_rowsums(p, seqtype) {
+            let generate = |rowsum, tag| -> Vec<Vec<i8>> {
+                let sequences = generate_sequences_with_rowsum(rowsum, p);
+                match seqtype {
+                    SequenceType::Williamson => sequences.into_iter().filter(|seq| symmetric(seq)).collect(),
+                    SequenceType::Good | SequenceType::Best if seqtype.skew_sequences().contains(&tag) => sequences.into_iter().filter(|seq| skew_type(seq)).collect(),
+                    SequenceType::Good | SequenceType::Best => sequences.into_iter().filter(|seq| symmetric(seq)).collect(),
+                    _ => sequences
+                }
+            };
+            let sequences_w = if prune {canonical_first_sequences(generate(rs.0, SequenceTag::W), seqtype)} else {generate(rs.0, SequenceTag::W)};
+            let (sequences_x, sequences_y, sequences_z) = (generate(rs.1, SequenceTag::X), generate(rs.2, SequenceTag::Y), generate(rs.3, SequenceTag::Z));
+
+            // Matching the periodic autocorrelations of the pairs WZ and XY
+            let paf = |(seq1, seq2) : &(&Vec<i8>, &Vec<i8>), sign : isize| -> Vec<isize> {
+                (1..p).map(|offset| sign * (periodic_autocorrelation(seq1, offset) + periodic_autocorrelation(seq2, offset))).collect()
+            };
+            for ((x, y), (w, z)) in match_by_key(iproduct!(&sequences_x, &sequences_y), iproduct!(&sequences_w, &sequences_z),
+                                                 |pair| paf(pair, -1), |pair| paf(pair, 1)) {
+                let mut quad = QuadSeq::new(p);
+                quad.set_all_values((w, x, y, z));
+                if quad.verify(seqtype) {
+                    result.push(quad);
+                }
+            }
+        }
+
+        if result.is_empty() {0} else {reduce_to_canonical_reps(&result, seqtype).len()}
+    }
+
+    #[test]
+    fn test_canonical_first_sequence() {
+        // Pruning the first sequence keeps a representative of every equivalence class
+        for p in 2..=7 {
+            for seqtype in [SequenceType::QuaternionType, SequenceType::WilliamsonType, SequenceType::Williamson, SequenceType::Good, SequenceType::Best] {
+                let classes = count_classes_with_rowsums(p, seqtype, false);
+                assert_eq!(count_classes_with_rowsums(p, seqtype, true), classes, "Classes lost for {} of length {p}", seqtype.to_string());
+            }
+        }
+
+        // And removes most of the sequences
+        let sequences = generate_sequences_with_rowsum(1, 9);
+        let canonical = canonical_first_sequences(sequences.clone(), SequenceType::QuaternionType);
+        assert!(canonical.len() * 9 <= sequences.len() * 2);
+        assert!(canonical.iter().all(|seq| sequences.contains(seq)));
+    }
+
+    #[test]
+    fn test_good_best_classes() {
+        // The numbers of good and best matrices of length 7 up to equivalence
+        assert_eq!(count_classes_with_rowsums(7, SequenceType::Good, true), 3);
+        assert_eq!(count_classes_with_rowsums(7, SequenceType::Best, true), 2);
+
+        // The equivalences keep the structure of good matrices
+        let mut quad = QuadSeq::new(7);
+        quad.set_all_values((&vec![1,-1,-1,1,-1,1,1], &vec![-1,-1,-1,1,1,-1,-1], &vec![-1,-1,1,-1,-1,1,-1], &vec![-1,1,-1,-1,-1,-1,1]));
+        let class = generate_equivalence_class(&quad, SequenceType::Good, &SequenceType::Good.equivalences(), false);
+        assert!(class.len() > 1);
+        assert!(class.iter().all(|seq| seq.verify(SequenceType::Good)));
+        assert!(class.iter().all(|seq| equiv(seq, &quad, SequenceType::Good)));
+    }
+}
@@ -3,7 +3,7 @@
 mod tests {
     use num_complex::Complex64;
 
-    use crate::sequences::{fourier::{dft_sequence, inverse_dft}, rowsum::generate_sequences_with_rowsum};
+    use crate::sequences::{fourier::{dft_sequence, inverse_dft, aperiodic_psd, aperiodic_autocorrelations_dft, negaperiodic_psd, SpectralFilter, SpectralPruning}, rowsum::generate_sequences_with_rowsum, williamson::{aperiodic_autocorrelation, negaperiodic_autocorrelation}};
 
 
     #[test]
@@ -55,4 +55,84 @@ mod tests {
         }
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_spectral_pruning() {
+        let p = 7;
+        let seqs1 = generate_sequences_with_rowsum(1, p);
+        let seqs2 = generate_sequences_with_rowsum(3, p);
+        let bound = 18.;       // tighter than 4p so that all the stages remove something
+
+        // Same pairs as checking every couple directly
+        let pruning = SpectralPruning::new(&seqs1, &seqs2, bound, SpectralFilter::periodic(0.0001));
+        let kept : Vec<(usize, usize)> = pruning.couples().map(|pair| (pair.seq_enum1.0, pair.seq_enum2.0)).collect();
+        let mut expected = vec![];
+        for (i, seq1) in seqs1.iter().enumerate() {
+            for (j, seq2) in seqs2.iter().enumerate() {
+                let (dft1, dft2) = (dft_sequence(seq1), dft_sequence(seq2));
+                if dft1.iter().zip(dft2.iter()).skip(1).all(|(a, b)| a.norm_sqr() + b.norm_sqr() <= bound + 0.0001) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(kept, expected);
+
+        // Every pair is either kept or removed by one of the stages
+        let (removed1, removed2) = pruning.removed_sequences();
+        let remaining = ((seqs1.len() - removed1) * (seqs2.len() - removed2)) as u64;
+        assert_eq!(remaining, kept.len() as u64 + pruning.removed_pairs());
+        assert!(removed1 + removed2 > 0 && pruning.removed_pairs() > 0);
+        assert_eq!(pruning.removed_fine_pairs(), 0);
+    }
+
+    #[test]
+    fn test_fine_grid() {
+        // A Golay pair has |DFT1|^2 + |DFT2|^2 = 2n at every frequency, not only at the n frequencies of the DFT
+        let seq_a = vec![1,1,-1,1,-1,1,-1,-1,1,1];
+        let seq_b = vec![1,1,-1,1,1,1,1,1,-1,-1];
+        // A rotation of the sequence has the same periodic PSD, but the pair isn't complementary anymore
+        let mut rotated = seq_a.clone();
+        rotated.rotate_left(3);
+
+        let seqs1 = vec![seq_a, rotated];
+        let seqs2 = vec![seq_b];
+        let bound = 20.;
+
+        let pruning = SpectralPruning::new(&seqs1, &seqs2, bound, SpectralFilter::periodic(0.0001));
+        assert_eq!(pruning.couples().count(), 2);
+
+        let pruning = SpectralPruning::new(&seqs1, &seqs2, bound, SpectralFilter::new(0.0001, 4));
+        let kept : Vec<usize> = pruning.couples().map(|pair| pair.seq_enum1.0).collect();
+        assert_eq!(kept, vec![0]);
+        assert_eq!(pruning.removed_sequences().0 as u64 + pruning.removed_fine_pairs(), 1);
+    }
+
+    #[test]
+    fn test_aperiodic_psd() {
+        let eps = 0.0001;
+        for seq in generate_sequences_with_rowsum(1, 7) {
+            let autocorrelations : Vec<isize> = (0..7).map(|t| aperiodic_autocorrelation(&seq, t)).collect();
+            assert_eq!(aperiodic_autocorrelations_dft(&seq), autocorrelations);
+
+            // The PSD at the odd frequencies is the transform of the negaperiodic autocorrelations
+            for (k, psd) in negaperiodic_psd(&seq).iter().enumerate() {
+                let expected : f64 = (0..7).map(|t| negaperiodic_autocorrelation(&seq, t) as f64 * (std::f64::consts::PI * ((2*k+1)*t) as f64 / 7.).cos()).sum();
+                assert!((psd - expected).abs() <= eps);
+            }
+        }
+
+        // The PSDs of an aperiodic complementary quadruple add up to 4n at every frequency
+        let quad : Vec<Vec<i8>> = vec![vec![1,1,-1], vec![1,-1,-1], vec![1,1,1], vec![1,-1,1]];
+        for len in [5, 6, 16] {
+            let psds : Vec<Vec<f64>> = quad.iter().map(|seq| aperiodic_psd(seq, len)).collect();
+            for k in 0..psds[0].len() {
+                assert!((psds.iter().map(|psd| psd[k]).sum::<f64>() - 12.).abs() <= eps);
+            }
+        }
+        let psds : Vec<Vec<f64>> = quad.iter().map(negaperiodic_psd).collect();
+        assert_eq!(psds[0].len(), 2);
+        for k in 0..psds[0].len() {
+            assert!((psds.iter().map(|psd| psd[k]).sum::<f64>() - 12.).abs() <= eps);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::sequences::{hurwitz::*, sequence::Q24, matrices::QHM, sequence::QS};
+
+    #[test]
+    fn test_units() {
+        let units : Vec<Hurwitz> = Q24.iter().map(Hurwitz::from_quaternion).collect();
+
+        for a in &units {
+            assert!(a.is_unit());
+            assert_eq!(*a * a.conjugate(), H1);
+            assert_eq!(Hurwitz::from_quaternion(&a.to_quaternion()), *a);
+            for b in &units {
+                // The units form a group, and the product agrees with the floating point one
+                assert!(units.contains(&(*a * *b)));
+                assert_eq!((*a * *b).to_quaternion(), a.to_quaternion() * b.to_quaternion());
+            }
+        }
+        assert_eq!(units.iter().filter(|u| u.is_half()).count(), 16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_not_hurwitz() {
+        Hurwitz::from_doubled([1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_exact_verify() {
+        let qhm = QHM::from_pqs(QS::from_str(&"+JJ+x".to_string()));
+        assert!(qhm.verify());
+
+        let dephased = qhm.dephased();
+        assert!(dephased.verify());
+        for i in 0..dephased.size() {
+            assert_eq!(dephased.get_hurwitz(0, i), H1);
+            assert_eq!(dephased.get_hurwitz(i, 0), H1);
+        }
+
+        let mut broken = QHM::from_pqs(QS::from_str(&"+JJ+x".to_string()));
+        broken.set_value(2, 3, -broken.get(2, 3));
+        assert!(!broken.verify());
+    }
+
+}
@@ -2,8 +2,11 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::{find::{find_with_rowsum::sort, find_write::join_pairs}, sequences::{fourier::dft_sequence, matching::{compute_auto_correlation, compute_auto_correlation_dft, compute_auto_correlation_pair, compute_auto_correlation_pair_dft, compute_complementary_auto_correlations, compute_complementary_cross_correlations, compute_cross_correlations, verify_cross_correlation}, symmetries::SequenceType}};
+    use std::fs;
+
+    use crate::{find::{find_with_rowsum::sort, find_write::{choose_pairing, estimate_seq_pairs, format_bytes, join_pairs, write_seq_pairs, EquationSide, MatchOption}}, sequences::{fourier::dft_sequence, matching::{compute_auto_correlation, compute_auto_correlation_dft, compute_auto_correlation_pair, compute_auto_correlation_pair_dft, compute_complementary_auto_correlations, compute_complementary_cross_correlations, compute_cross_correlations, compute_cross_psd_pair, compute_exact_cross_psd_pair, compute_exact_psd_pair, compute_psd_pair, verify_cross_correlation}, symmetries::SequenceType}};
     use crate::sequences::williamson::SequenceTag;
+    use crate::sequences::{fourier::SpectralFilter, rowsum::generate_sequences_with_rowsum, symmetries::RowsumPairing};
 
 
     #[test]
@@ -110,6 +113,79 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_exact_psd() {
+        let seq_x = vec![1,-1,-1,-1,1,1,-1,1,-1,1];
+        let seq_y = vec![-1,1,1,1,-1,1,-1,-1,-1,1];
+        let seq_z = vec![1,1,1,1,1,-1,-1,1,-1,-1];
+        let seq_w = vec![1,1,-1,1,1,1,1,-1,1,1];
+        let p = seq_x.len();
+
+        let dft_x = dft_sequence(&seq_x);
+        let dft_y = dft_sequence(&seq_y);
+        let psd_x : Vec<f64> = dft_x.iter().map(|elm| elm.norm_sqr()).collect();
+        let psd_y : Vec<f64> = dft_y.iter().map(|elm| elm.norm_sqr()).collect();
+
+        // Same values as the DFT computations
+        let tags = (SequenceTag::X, SequenceTag::Y);
+        for side in [EquationSide::LEFT, EquationSide::RIGHT] {
+            let exact = compute_exact_psd_pair(&seq_x, &seq_y, side);
+            let dft = compute_psd_pair(&psd_x, &psd_y, p, side);
+            assert_eq!(exact.len(), dft.len());
+            assert!(exact.iter().zip(dft.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+        let exact = compute_exact_cross_psd_pair(&seq_x, &seq_y, &tags, EquationSide::LEFT);
+        let dft = compute_cross_psd_pair(&dft_x, &dft_y, &tags, p);
+        assert!(exact.iter().zip(dft.iter()).all(|(a, b)| (a - b.im).abs() < 1e-9));
+        let exact = compute_exact_cross_psd_pair(&seq_x, &seq_y, &tags, EquationSide::RIGHT);
+        assert!(exact.iter().zip(dft.iter()).all(|(a, b)| (a + b.im).abs() < 1e-9));
+
+        // Both sides of the equation give exactly the same values for a matching pair
+        assert_eq!(compute_exact_psd_pair(&seq_x, &seq_y, EquationSide::LEFT), compute_exact_psd_pair(&seq_z, &seq_w, EquationSide::RIGHT));
+        assert_eq!(compute_exact_cross_psd_pair(&seq_x, &seq_y, &tags, EquationSide::LEFT), compute_exact_cross_psd_pair(&seq_z, &seq_w, &(SequenceTag::Z, SequenceTag::W), EquationSide::RIGHT));
+    }
+
+    #[test]
+    fn test_choose_pairing() {
+        let p = 10;
+        let sequences = generate_sequences_with_rowsum(2, p);
+        let subset = |size : usize| sequences[0..size].to_vec();
+
+        // With a huge slack every sequence passes the PSD bound, so the estimates are 52 pairs for WX, 20 for WY and 25 for WZ
+        let filter = SpectralFilter::periodic(1e9);
+        let pairing = choose_pairing([&subset(1), &subset(2), &subset(10), &subset(5)], p, filter);
+        assert!(pairing == RowsumPairing::WY);
+
+        // Ties keep the default pairing
+        let pairing = choose_pairing([&subset(3), &subset(3), &subset(3), &subset(3)], p, filter);
+        assert!(pairing == RowsumPairing::WZ);
+    }
+
+    #[test]
+    fn test_estimate_seq_pairs() {
+        let p = 10;
+        let sequences = generate_sequences_with_rowsum(2, p);
+        let (seqs1, seqs2) = (sequences[0..30].to_vec(), sequences[30..60].to_vec());
+        let tags = (&SequenceTag::W, &SequenceTag::Z);
+        let filter = SpectralFilter::periodic(0.);
+
+        // With fewer couples than the sample, the projection is the exact size of the .pair file
+        let folder = std::env::temp_dir().join("test_estimate_seq_pairs").to_str().unwrap().to_string();
+        fs::create_dir_all(&folder).expect("Error when creating the dir");
+        let counter = write_seq_pairs((&seqs1, &seqs2), tags, SequenceType::QuaternionType, (2, 2, 2, 2), p, &folder, EquationSide::LEFT, MatchOption::PSD, filter);
+        let size = fs::metadata(folder.clone() + "/pair_WZ.pair").expect("Missing .pair file").len();
+        fs::remove_dir_all(&folder).expect("Error when removing the dir");
+
+        let estimate = estimate_seq_pairs((&seqs1, &seqs2), tags, SequenceType::QuaternionType, p, EquationSide::LEFT, MatchOption::PSD, filter);
+        assert!(counter > 0);
+        assert_eq!(estimate.pairs, counter);
+        assert_eq!(estimate.bytes, size);
+        assert_eq!(estimate.sequences, 60);
+
+        assert_eq!(format_bytes(512), "512.00 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.50 MB");
+    }
+
     //#[test]
     //fn test_crosscorrelation_dft() {
     //    let seq_x = vec![1,-1,-1,-1,1,1,-1,1,-1,1];
@@ -158,7 +234,7 @@ mod tests {
 
     #[test]
     fn test_join() {
-        join_pairs(7, SequenceType::QuaternionType);
+        join_pairs(7, SequenceType::QuaternionType, None);
     }
 
 }
\ No newline at end of file
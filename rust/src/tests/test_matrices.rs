@@ -3,7 +3,7 @@ mod tests {
 
     use cgmath::Quaternion;
 
-    use crate::sequences::{sequence::QS, matrices::{QHM, HM, OpMat}, williamson::QuadSeq, symmetries::SequenceType, matrix_equivalence::reduce_to_hadamard_equivalence, equivalence::generate_equivalent_quad_seqs};
+    use crate::sequences::{sequence::{QS, Q24}, matrices::{QHM, HM, OpMat}, williamson::QuadSeq, symmetries::SequenceType, matrix_equivalence::{reduce_to_hadamard_equivalence, reduce_to_qhm_equivalence}, equivalence::generate_equivalent_quad_seqs};
 
     #[test]
     fn qhmtest() {
@@ -133,4 +133,45 @@ mod tests {
 
     }
 
+    #[test]
+    fn qhm_equ() {
+        let qhm = QHM::from_pqs(QS::from_str(&"+JJ+x".to_string())).dephased();
+        let n = qhm.size();
+
+        // Permute rows and columns, multiply rows on the left and columns on the right by units
+        let mut scrambled = QHM::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                let entry = qhm.get((2*i + 1) % n, (n - 1 - j) % n);
+                scrambled.set_value(i, j, Q24[(7*i + 3) % 24] * entry * Q24[(5*j + 10) % 24]);
+            }
+        }
+        assert!(scrambled.verify());
+
+        let mats = vec![qhm, scrambled];
+        assert_eq!(reduce_to_qhm_equivalence(&mats).len(), 1);
+
+        // Two inequivalent sequences of length 4 give inequivalent QHM : the products H[r][c] H[r'][c]^-1 H[r'][c'] H[r][c']^-1
+        // are only conjugated by the equivalence, and they are all real for the matrix of +++- but not for the one of +YIQ
+        let mats = vec![QHM::from_pqs(QS::from_str(&"+YIQ".to_string())), QHM::from_pqs(QS::from_str(&"+++-".to_string()))];
+        assert_eq!(reduce_to_qhm_equivalence(&mats).len(), 2);
+    }
+
+    #[test]
+    fn qhm_representations() {
+        // The entries of a sequence coming from a quadruple of binary sequences are in Q8 and q*Q8, so a single unit is enough to get half units
+        let qhm = QHM::from_pqs(QS::from_str(&"+JJ+x".to_string()));
+
+        let real = qhm.to_real_matrix().expect("Entries can be brought to half units");
+        assert_eq!(real.size(), 20);
+        assert!(real.verify());
+
+        let complex = qhm.to_complex_matrix().expect("Entries can be brought to half units");
+        assert_eq!(complex.size(), 10);
+        assert!(complex.is_complex_hadamard());
+
+        // A matrix of ones is not Hadamard
+        assert!(!HM::new(4).verify());
+    }
+
 }
\ No newline at end of file
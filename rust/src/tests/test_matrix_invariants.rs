@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::sequences::{matrices::HM, matrix_invariants::*, sequence::QS, symmetries::SequenceType, williamson::QuadSeq};
+
+    fn sylvester(order : usize) -> HM {
+        // Sylvester Hadamard matrix of order 2^k, entry (i,j) is (-1)^<i,j>
+        let mut hm = HM::new(order);
+        for i in 0..order {
+            for j in 0..order {
+                hm.set_value(i, j, if (i & j).count_ones() % 2 == 0 {1} else {-1});
+            }
+        }
+        hm
+    }
+
+    fn scrambled(mat : &HM) -> HM {
+        // Applies some row and column permutations and negations
+        let n = mat.size();
+        let mut res = HM::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                let row = (3*i + 1) % n;
+                let col = (n - 1 - j + 2) % n;
+                let sign = if i % 3 == 0 {-1} else {1} * if j % 5 == 2 {-1} else {1};
+                res.set_value(i, j, sign * mat.get(row, col));
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_smith_normal_form() {
+        assert_eq!(smith_normal_form(&sylvester(4)), vec![(1,1), (2,2), (4,1)]);
+        assert_eq!(smith_normal_form(&sylvester(8)), vec![(1,1), (2,3), (4,3), (8,1)]);
+    }
+
+    #[test]
+    fn test_ranks() {
+        // The dephased Sylvester matrix of order 2^k comes from the inner product on GF(2)^k, so its 2-rank is k
+        assert_eq!(rank_gf2(&sylvester(8)), 3);
+        assert_eq!(rank_gf2(&sylvester(16)), 4);
+
+        // A Hadamard matrix whose order is not divisible by 3 is invertible over GF(3)
+        assert_eq!(rank_mod_p(&sylvester(16), 3), 16);
+    }
+
+    #[test]
+    fn test_profile() {
+        // Every 4 rows of the Sylvester matrix either sum to a row (product is +-n) or not (product is 0)
+        let invariants = HadamardInvariants::from_hm(&sylvester(8));
+        assert_eq!(invariants.profile(), &vec![(0, 56), (8, 14)]);
+    }
+
+    #[test]
+    fn test_invariants_under_equivalence() {
+        let qs = QS::from_str(&"+JJ+x".to_string());
+        let hm = HM::from_williamson(&QuadSeq::from_pqs(&qs), SequenceType::QuaternionType);
+
+        let invariants = HadamardInvariants::from_hm(&hm);
+        assert_eq!(invariants, HadamardInvariants::from_hm(&scrambled(&hm)));
+        assert_eq!(invariants, HadamardInvariants::from_hm(&hm.dephased()));
+
+        let sylvester_invariants = HadamardInvariants::from_hm(&sylvester(16));
+        assert_eq!(sylvester_invariants, HadamardInvariants::from_hm(&scrambled(&sylvester(16))));
+    }
+
+}
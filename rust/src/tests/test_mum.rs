@@ -2,6 +2,8 @@
 #[cfg(test)]
 mod tests {
 
+    use num_complex::Complex;
+
     use crate::sequences::{mum::*, sequence::*, matrices::QHM, williamson::QuadSeq};
 
 
@@ -46,4 +48,49 @@ mod tests {
         println!("{}", mum.to_string());
     }
 
+    #[test]
+    fn test_operator_algebra() {
+        let i = Complex::new(0., 1.);
+
+        assert!((Operator::identity(4) * &OPX.tensor(&OP1)).approx_eq(&OPX.tensor(&OP1), 0.));
+        assert_eq!(Operator::identity(4).trace(), Complex::new(4., 0.));
+        assert_eq!(Operator::identity(4).frobenius_norm(), 2.);
+
+        // Pauli matrices : XY = iZ and [X,Y] = 2iZ
+        assert!((OPX.clone() * &*OPY).approx_eq(&(OPZ.clone() * i), 1e-6));
+        assert!(OPX.commutator(&OPY).approx_eq(&(OPZ.clone() * (i * 2.)), 1e-6));
+        assert!(!OPX.approx_eq(&OPY, 1e-6));
+
+        // The embedding of the quaternions is a morphism
+        for (a, b) in itertools::iproduct!(Q24.iter(), Q24.iter()) {
+            let product = quaternion_to_operator(a) * &quaternion_to_operator(b);
+            assert!(product.approx_eq(&quaternion_to_operator(&(a * b)), 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_mum_verify() {
+        // The circulant matrix of a perfect sequence gives a MUM and a unitary HMUO
+        let qhm = QHM::from_pqs(QS::from_str(&"+JJ+x".to_string()));
+
+        assert!(MUM::from_qhm(&qhm).verify());
+        assert!(HMUO::from_qhm(&qhm).is_unitary());
+
+        // The normalized construction doesn't depend on the matrix being circulant
+        let tolerance = f32::EPSILON.sqrt();
+        let dephased = qhm.dephased();
+        for matrix in [&qhm, &dephased] {
+            let mum = MUM::from_qhm_normalized(matrix);
+            assert!(mum.verify());
+            assert!(mum.is_positive(tolerance) && mum.is_complete(tolerance) && mum.is_unbiased(tolerance));
+            assert!(HMUO::from_qhm_normalized(matrix).is_unitary_with_tolerance(tolerance));
+        }
+
+        // Reading back the output of convert qhm
+        assert!(MUM::from_qhm_normalized(&QHM::from_str(&dephased.to_string())).is_complete(tolerance));
+
+        assert!(!MUM::empty(5, 10).verify());
+        assert!(!HMUO::empty(10).is_unitary());
+    }
+
 }
\ No newline at end of file
@@ -2,7 +2,8 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::sequences::{product::{sequence_product, mum_product}, williamson::QuadSeq, matrices::QHM, mum::{MUM, HMUO}};
+    use crate::find::find_unique::reduce_to_canonical_reps;
+    use crate::sequences::{product::{sequence_product, mum_product, products}, sequence::QS, symmetries::SequenceType, williamson::QuadSeq, matrices::QHM, mum::{MUM, HMUO}};
 
 
     #[test]
@@ -141,5 +142,38 @@ mod tests {
         assert!(false);
     }
 
+    #[test]
+    fn test_products() {
+        let to_qs = |seqs : &[&str]| -> Vec<QS> {seqs.iter().map(|s| QS::from_str(&s.to_string())).collect()};
+
+        // The classes of the sequences of each type and length, those of lengths 2 and 3 are the same for the three types
+        let qts2 = ["+J"];
+        let qts3 = ["++Q"];
+        let qts4 = ["+YIQ", "+++-", "+Y+y"];
+        let qts5 = ["+JJ+x"];
+        let wts4 = ["+YIQ", "+++-"];
+
+        // (type, sequences of length p, sequences of length q,
+        //  (number of classes of the type, number of Q24 classes, number of products of another type))
+        let cases = [
+            (SequenceType::QuaternionType, &qts2[..], &qts3[..], (1, 0, 0)),
+            (SequenceType::QuaternionType, &qts2[..], &qts5[..], (1, 0, 0)),
+            (SequenceType::QuaternionType, &qts3[..], &qts4[..], (1, 2, 0)),
+            (SequenceType::WilliamsonType, &qts3[..], &wts4[..], (1, 1, 0)),
+            (SequenceType::Williamson, &qts2[..], &qts3[..], (0, 0, 1)),
+        ];
+
+        for (seqtype, seqs1, seqs2, expected) in cases {
+            let products = products(&to_qs(seqs1), &to_qs(seqs2), seqtype);
+
+            assert_eq!((products.quad_seqs.len(), products.q24_seqs.len(), products.num_other_type), expected);
+            assert_eq!(products.num_not_perfect, 0);
+        }
+
+        // The product of length 6 is the only class of quaternion type sequences of this length
+        let products = products(&to_qs(&qts2), &to_qs(&qts3), SequenceType::QuaternionType);
+        let known = reduce_to_canonical_reps(&vec![QuadSeq::from_pqs(&QS::from_str(&"+JKJ+j".to_string()))], SequenceType::QuaternionType);
+        assert_eq!(products.quad_seqs, known);
+    }
 
 }
\ No newline at end of file
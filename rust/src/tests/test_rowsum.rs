@@ -5,7 +5,7 @@
 mod tests {
     use std::{collections::HashSet, env};
 
-    use crate::{find::find_unique::reduce_to_equivalence, read_lines, sequences::{equivalence::{filter_by_rowsums, generate_equivalent_quad_seqs}, rowsum::*, sequence::QS, symmetries::SequenceType, williamson::QuadSeq}};
+    use crate::{find::find_unique::reduce_to_equivalence, read_lines, tests::pipeline::helpers::{all_sequences, match_by_key}, sequences::{equivalence::{filter_by_rowsums, generate_equivalent_quad_seqs}, fourier::dft_sequence, rowsum::*, sequence::QS, symmetries::{SequenceShape, SequenceType, Symmetry}, williamson::{periodic_autocorrelation, QuadSeq, SequenceTag}}};
 
     #[test]
     fn test_prop_5() {
@@ -86,9 +86,9 @@ mod tests {
 
     #[test]
     fn test_rowsum_gen() {
-        for p in 1..23 {
-            println!("\nn={}", p);
-            for rs in generate_rowsums(p) {
+        for (p, seqtype) in itertools::iproduct!(1..23, TYPES) {
+            println!("\nn={} {}", p, seqtype.to_string());
+            for rs in generate_rowsums(p, seqtype) {
                 println!("{:?}", rs);
                 assert_eq!(rs.0*rs.0 + rs.1*rs.1 + rs.2*rs.2 + rs.3*rs.3, 4*p as isize);
             }
@@ -166,42 +166,90 @@ mod tests {
     }
 
 
+    const TYPES : [SequenceType; 5] = [SequenceType::QuaternionType, SequenceType::WilliamsonType, SequenceType::Williamson, SequenceType::Good, SequenceType::Best];
+
     #[test]
-    fn test_gen_quads(){
+    fn test_rowsum_actions(){
+        // The equivalences act on the rowsums by the signed permutations keeping the type of each sequence,
+        // with an even number of negations for an even permutation for qts
+        let orders = [192, 384, 384, 48, 12];
+        for (seqtype, order) in TYPES.into_iter().zip(orders) {
+            let actions = rowsum_actions(seqtype);
+            assert_eq!(actions.len(), order, "{}", seqtype.to_string());
+            for (a1, a2) in itertools::iproduct!(&actions, &actions) {
+                assert!(actions.contains(&a1.compose(a2)));
+            }
+        }
 
-        let quads = generate_other_quadruplets(&(1,1,5,7), SequenceType::QuaternionType);
-        assert_eq!(quads.len(), 2);
-        assert_eq!(quads[0], (1,1,5,7));
-        assert_eq!(quads[1], (-1,1,5,7));
-        
-        let quads = generate_other_quadruplets(&(1,3,3,7), SequenceType::QuaternionType);
-        assert_eq!(quads.len(), 2);
-        assert_eq!(quads[0], (1,3,3,7));
-        assert_eq!(quads[1], (-1,3,3,7));
+        assert_eq!(rowsum_actions(SequenceType::ItoType), vec![RowsumAction::identity()]);
+    }
 
-        let quads = generate_other_quadruplets(&(3,3,3,8), SequenceType::QuaternionType);
-        assert_eq!(quads.len(), 2);
-        assert_eq!(quads[0], (3,3,3,8));
-        assert_eq!(quads[1], (-3,3,3,8));
+    #[test]
+    fn test_possible_rowsums(){
 
-        let quads = generate_other_quadruplets(&(3,3,5,5), SequenceType::QuaternionType);
-        assert_eq!(quads.len(), 2);
-        assert_eq!(quads[0], (3,3,5,5));
-        assert_eq!(quads[1], (-3,3,5,5));
-    }
+        // The duplicated rowsums allow every signed permutation
+        let rowsums = generate_rowsum_classes(17, SequenceType::QuaternionType);
+        assert_eq!(rowsums, vec![RowsumClass { rowsums : (1,3,3,7), multiplicity : 192 }, RowsumClass { rowsums : (3,3,5,5), multiplicity : 96 }]);
 
+        // Otherwise the rowsums of qts come in two classes
+        let rowsums = generate_rowsum_classes(21, SequenceType::QuaternionType);
+        assert!(rowsums.contains(&RowsumClass { rowsums : (1,3,5,7), multiplicity : 192 }));
+        assert!(rowsums.contains(&RowsumClass { rowsums : (1,3,5,-7), multiplicity : 192 }));
+        assert_eq!(generate_rowsums(21, SequenceType::Williamson), vec![(1,1,1,9), (1,3,5,7), (3,5,5,5)]);
+
+        // The classes are written and read back from the rowsums.quad file
+        for class in rowsums {
+            assert_eq!(RowsumClass::from_str(&class.to_string()), class);
+        }
+    }
 
+    fn brute_force_rowsums(n : usize, seqtype : SequenceType) -> HashSet<Quad> {
+        // returns the rowsums of all the quadruples of the type of length n, matching the pairs (W,X) and (Y,Z) by their autocorrelations
+        let tags = [SequenceTag::W, SequenceTag::X, SequenceTag::Y, SequenceTag::Z];
+        let candidates : Vec<Vec<Vec<i8>>> = tags.iter().map(|tag| all_sequences(&[1, -1], n).into_iter()
+            .filter(|seq| seqtype.shape(tag).is_none_or(|shape| shape.contains(seq)))
+            .collect()).collect();
+        let autocorrelations = |(a, b) : &(&Vec<i8>, &Vec<i8>), sign : isize| -> Vec<isize> {
+            (1..n).map(|t| sign * (periodic_autocorrelation(a, t) + periodic_autocorrelation(b, t))).collect()
+        };
+
+        let mut rowsums = HashSet::new();
+        for ((w, x), (y, z)) in match_by_key(itertools::iproduct!(&candidates[0], &candidates[1]), itertools::iproduct!(&candidates[2], &candidates[3]),
+                                             |pair| autocorrelations(pair, 1), |pair| autocorrelations(pair, -1)) {
+            let mut seq = QuadSeq::new(n);
+            seq.set_all_values((w, x, y, z));
+            if seq.verify(seqtype) {
+                rowsums.insert(quad_rowsums(&seq));
+            }
+        }
+        rowsums
+    }
 
     #[test]
-    fn test_possible_rowsums(){
-
-        let rowsums = generate_rowsums(17);
-        
-        assert_eq!(rowsums.len(), 4);
-        assert_eq!(rowsums[0], (1,3,3,7));
-        assert_eq!(rowsums[1], (-1,3,3,7));
-        assert_eq!(rowsums[2], (3,3,5,5));
-        assert_eq!(rowsums[3], (-3,3,5,5));
+    fn test_rowsum_classes_complete(){
+        for seqtype in TYPES {
+            let max = if seqtype.shape(&SequenceTag::W).is_some() {9} else {5};
+            for n in 1..=max {
+                let classes = generate_rowsum_classes(n, seqtype);
+                let actions = rowsum_actions(seqtype);
+                let admissible : HashSet<Quad> = admissible_rowsums(n, seqtype).into_iter().collect();
+
+                // The classes split the admissible rowsums
+                assert_eq!(classes.iter().map(|class| class.multiplicity).sum::<usize>(), admissible.len());
+
+                let found = brute_force_rowsums(n, seqtype);
+                println!("{} {n}: {} rowsums found, {} classes", seqtype.to_string(), found.len(), classes.len());
+                for rs in &found {
+                    assert!(admissible.contains(rs), "{:?} isn't admissible", rs);
+                    // The actions are equivalences, so every rowsum of the orbit is found...
+                    for action in &actions {
+                        assert!(found.contains(&action.apply(rs)), "{:?} is found but not {:?}", rs, action.apply(rs));
+                    }
+                    // ...and the orbit has exactly one representative in the classes
+                    assert_eq!(classes.iter().filter(|class| actions.iter().any(|action| action.apply(rs) == class.rowsums)).count(), 1, "{:?}", rs);
+                }
+            }
+        }
     }
 
 
@@ -216,4 +264,97 @@ mod tests {
         }
         
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_compression() {
+        let seq = vec![1,-1,-1,1,1,1,-1,1,-1,1,1,-1];
+        assert_eq!(compress(&seq, 3), vec![1,1,-1,1]);
+        assert_eq!(compress(&seq, 2), vec![0,0,-2,2,2,0]);
+        assert!(decompress(&compress(&seq, 3), 3).contains(&seq));
+
+        // The compressions with a given rowsum are exactly the compressions of the sequences with that rowsum
+        let (n, m, rowsum) = (12, 3, 2);
+        let compressions : HashSet<Vec<i8>> = generate_compressions_with_rowsum(rowsum, n / m, m).into_iter().collect();
+        let expected : HashSet<Vec<i8>> = generate_sequences_with_rowsum(rowsum, n).iter().map(|seq| compress(seq, m)).collect();
+        assert_eq!(compressions, expected);
+
+        // Without a bound, decompressing gives back every sequence
+        let sequences : HashSet<Vec<i8>> = generate_sequences_with_rowsum_compressed(rowsum, n, m, f64::INFINITY).into_iter().collect();
+        assert_eq!(sequences, generate_sequences_with_rowsum(rowsum, n).into_iter().collect());
+
+        // With the PSD bound, we only lose sequences whose PSD exceeds the bound
+        let bound = 4. * n as f64;
+        let sequences = generate_sequences_with_rowsum_compressed(rowsum, n, m, bound);
+        assert!(sequences.len() < generate_sequences_with_rowsum(rowsum, n).len());
+        for seq in generate_sequences_with_rowsum(rowsum, n) {
+            if !sequences.contains(&seq) {
+                assert!(dft_sequence(&seq).iter().skip(1).any(|elm| elm.norm_sqr() > bound));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_sequence() {
+        assert_eq!(binomial(10, 3), 120);
+        assert_eq!(binomial(3, 5), 0);
+        assert_eq!(binomial(60, 30), 118264581564861424);
+
+        // The ranks number the sequences of each length and rowsum from 0, and give back the sequences
+        for size in 1..=12 {
+            for rowsum in (-(size as isize)..=size as isize).step_by(2) {
+                let sequences = generate_sequences_with_rowsum(rowsum, size);
+                let ranks : HashSet<usize> = sequences.iter().map(|seq| rank_sequence(seq)).collect();
+                assert_eq!(ranks, (0..sequences.len()).collect());
+                for seq in sequences {
+                    assert_eq!(unrank_sequence(rank_sequence(&seq), size, rowsum), seq);
+                }
+            }
+        }
+
+        let seq = vec![1,-1,-1,1,1,1,-1,1,-1,1,1,-1];
+        assert_eq!(unrank_sequence(rank_sequence(&seq), seq.len(), rowsum(seq.clone())), seq);
+    }
+
+    #[test]
+    fn test_skew_rowsums() {
+        // The skew-type sequences have rowsum 1
+        let rowsums = generate_rowsums(7, SequenceType::Good);
+        assert_eq!(rowsums, vec![(1,1,1,5), (1,3,3,3)]);
+        assert!(generate_rowsums(8, SequenceType::Good).is_empty());
+
+        assert_eq!(generate_rowsums(7, SequenceType::Best), vec![(1,1,1,5)]);
+        assert!(generate_rowsums(5, SequenceType::Best).is_empty());
+    }
+
+    #[test]
+    fn test_generate_with_shape() {
+        let shapes = [SequenceShape::Symmetric, SequenceShape::Skew, SequenceShape::Halves(Symmetry::I), SequenceShape::Halves(Symmetry::II),
+                      SequenceShape::Halves(Symmetry::III), SequenceShape::Halves(Symmetry::IV)];
+        for (size, shape) in itertools::iproduct!(1..=12, shapes) {
+            // The sequences of a shape are those of all the sequences that have the shape
+            let mut total = 0;
+            for rowsum in (-(size as isize)..=size as isize).step_by(2) {
+                let sequences : HashSet<Vec<i8>> = generate_sequences_with_shape(rowsum, size, shape).into_iter().collect();
+                let filtered : HashSet<Vec<i8>> = generate_sequences_with_rowsum(rowsum, size).into_iter().filter(|seq| shape.contains(seq)).collect();
+                assert_eq!(sequences, filtered);
+                total += sequences.len();
+            }
+
+            // and they are determined by their free values
+            let free = match shape {
+                SequenceShape::Skew if size % 2 == 0 => None,
+                SequenceShape::Skew => Some((size - 1)/2),
+                SequenceShape::Halves(Symmetry::II | Symmetry::III | Symmetry::IV) if size % 2 == 1 => None,
+                _ => Some(shape.prefix_length(size))
+            };
+            assert_eq!(total, free.map_or(0, |k| 1 << k));
+        }
+
+        assert!(SequenceShape::Symmetric.contains(&vec![1, -1, 1, 1, -1]));
+        assert!(SequenceShape::Halves(Symmetry::I).contains(&vec![1, -1, -1, 1]));
+        assert!(SequenceShape::Halves(Symmetry::II).contains(&vec![1, 1, -1, 1, 1, -1, -1, -1]));
+        assert!(SequenceShape::Halves(Symmetry::III).contains(&vec![1, 1, -1, 1, 1, 1, 1, -1]));
+        assert!(SequenceShape::Halves(Symmetry::IV).contains(&vec![1, -1, -1, 1]));
+        assert!(!SequenceShape::Halves(Symmetry::IV).contains(&vec![1, -1, 1, -1]));
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use crate::find::{find_with_rowsum::{find_with_cuts, model_to_quad}, find_write::reduce_matches};
+    use crate::sequences::{equations::generate_formula, rowsum::generate_rowsums, sat::*, symmetries::SequenceType};
+
+    #[test]
+    fn test_solver() {
+        // The pigeonhole principle for 3 pigeons and 2 holes, the variable 2i + j + 1 meaning that the pigeon i is in the hole j
+        let mut clauses : Vec<Vec<Lit>> = (0..3).map(|i| vec![2*i + 1, 2*i + 2]).collect();
+        for (i1, i2, j) in itertools::iproduct!(0..3, 0..3, 0..2) {
+            if i1 < i2 {
+                clauses.push(vec![-(2*i1 + j + 1), -(2*i2 + j + 1)]);
+            }
+        }
+        assert!(Solver::new(6, &clauses).solve().is_none());
+
+        clauses.truncate(3);
+        let model = Solver::new(6, &clauses).solve().expect("No model ?");
+        assert!(clauses.iter().all(|clause| clause.iter().any(|&lit| model[lit.unsigned_abs()] == (lit > 0))));
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let mut formula = Formula::new(5);
+        formula.add_exactly(vec![1, 2, 3, 4, 5], 2);
+        let models = all_models(&formula, 5, &SolverBackend::Embedded, &"".to_string());
+        assert_eq!(models.len(), 10);
+        assert!(models.iter().all(|model| model.iter().filter(|&&b| b).count() == 2));
+
+        // The literals can be repeated or negated
+        let mut formula = Formula::new(2);
+        formula.add_exactly(vec![1, 1, -2], 2);
+        assert_eq!(all_models(&formula, 2, &SolverBackend::Embedded, &"".to_string()), vec![vec![false, true, true]]);
+        assert_eq!(formula.to_opb(), "* #variable= 2 #constraint= 1\n+2 x1 -1 x2 = 1 ;\n");
+
+        let mut formula = Formula::new(3);
+        formula.add_exactly(vec![1, 2, 3], 4);
+        assert!(all_models(&formula, 3, &SolverBackend::Embedded, &"".to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_xnor_lex() {
+        let mut formula = Formula::new(2);
+        let y = formula.add_xnor(1, 2);
+        assert_eq!(formula.add_xnor(2, 1), y);
+        assert_eq!(formula.add_xnor(-1, 2), -y);
+        formula.add_clause(vec![y]);
+        assert_eq!(all_models(&formula, 2, &SolverBackend::Embedded, &"".to_string()).len(), 2);
+
+        // The sequences of 3 values which are smaller than their rotations
+        let mut formula = Formula::new(3);
+        formula.add_lex_leq(&[1, 2, 3], &[2, 3, 1]);
+        formula.add_lex_leq(&[1, 2, 3], &[3, 1, 2]);
+        assert_eq!(all_models(&formula, 3, &SolverBackend::Embedded, &"".to_string()).len(), 4);
+    }
+
+    #[test]
+    fn test_assumptions_cuts() {
+        // The outputs of a counter of 5 literals, exactly 2 of them being true under the assumptions
+        let mut formula = Formula::new(5);
+        let counter = formula.add_counter(vec![1, 2, 3, 4, 5]);
+        let (num_vars, clauses) = formula.to_cnf();
+        let mut solver = Solver::new(num_vars, &clauses);
+        let enumerate = |solver : &mut Solver, check : &mut dyn FnMut(&[i8]) -> Option<Vec<Lit>>| -> usize {
+            let mut count = 0;
+            while let Some(model) = solver.solve_with(&[counter[1], -counter[2]], &mut |values| check(values)) {
+                assert_eq!((1..=5).filter(|&v| model[v]).count(), 2);
+                solver.add_clause((1..=5).map(|v| if model[v] {-(v as Lit)} else {v as Lit}).collect());
+                count += 1;
+            }
+            count
+        };
+
+        // The cut forbids the variable 1 once the variable 2 is assigned, and the cuts are kept for the next calls
+        let mut cuts = 0;
+        assert_eq!(enumerate(&mut solver, &mut |values| if values[1] == 1 && values[2] != 0 {cuts += 1; Some(vec![-1, if values[2] == 1 {-2} else {2}])} else {None}), 6);
+        assert!(cuts > 0);
+        assert_eq!(enumerate(&mut solver, &mut |_| None), 0);
+        assert!(solver.solve_with(&[counter[0], -counter[0]], &mut |_| None).is_none());
+        assert!(solver.solve().is_some());
+    }
+
+    #[test]
+    fn test_same_as_pairs() {
+        // The numbers of quadruples up to equivalence found by the pair pipeline
+        for (seqtype, n, count) in [(SequenceType::QuaternionType, 4, 3), (SequenceType::QuaternionType, 7, 3), (SequenceType::WilliamsonType, 4, 2),
+                                    (SequenceType::Williamson, 7, 2), (SequenceType::Good, 7, 3), (SequenceType::Best, 7, 2), (SequenceType::Best, 5, 0)] {
+            let mut result = vec![];
+            for rs in generate_rowsums(n, seqtype) {
+                for model in all_models(&generate_formula(seqtype, n, &rs), 4 * n, &SolverBackend::Embedded, &"".to_string()) {
+                    let quad = model_to_quad(&model, n);
+                    assert!(quad.verify(seqtype));
+                    result.push(quad);
+                }
+            }
+            assert_eq!(reduce_matches(&result, seqtype).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_cuts_same_as_pairs() {
+        for (seqtype, n, count) in [(SequenceType::QuaternionType, 6, 1), (SequenceType::WilliamsonType, 7, 2), (SequenceType::Williamson, 10, 2),
+                                    (SequenceType::Good, 9, 1), (SequenceType::Best, 7, 2), (SequenceType::Best, 5, 0)] {
+            assert_eq!(find_with_cuts(n, seqtype).len(), count);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 #[cfg(test)]
 mod tests {
 
-    use crate::sequences::williamson::{QuadSeq, QUADRUPLETS, periodic_autocorrelation, cross_correlation};
+    use crate::sequences::{symmetries::SequenceType, williamson::{skew_type, QuadSeq, QUADRUPLETS, periodic_autocorrelation, cross_correlation, aperiodic_autocorrelation, aperiodic_cross_correlation, negaperiodic_autocorrelation, negaperiodic_cross_correlation}};
 
     #[test]
     fn test_conversion() {
@@ -70,6 +70,55 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_aperiodic_correlation() {
+        let seq = vec![1,1,-1,1,1,-1,1];
+
+        assert_eq!(aperiodic_autocorrelation(&seq, 0), 7);
+        assert_eq!(aperiodic_autocorrelation(&seq, 2), -1);
+        assert_eq!(aperiodic_autocorrelation(&seq, 6), 1);
+        assert_eq!(aperiodic_autocorrelation(&seq, 7), 0);
+        assert_eq!(negaperiodic_autocorrelation(&seq, 2), -1);
+
+        let seq2 = vec![1,-1,1];
+        assert_eq!(aperiodic_cross_correlation(&seq, &seq2, 1), 3);
+        assert_eq!(aperiodic_cross_correlation(&seq2, &seq, 1), 0);
+
+        // The periodic and negaperiodic correlations are the sum and the difference of two aperiodic correlations
+        let n = 5;
+        let sequences : Vec<Vec<i8>> = (0..1 << n).map(|m| (0..n).map(|i| if (m >> i) & 1 == 1 {-1} else {1}).collect()).collect();
+        for seq1 in &sequences {
+            for offset in 1..n {
+                let (direct, wrapped) = (aperiodic_autocorrelation(seq1, offset), aperiodic_autocorrelation(seq1, n - offset));
+                assert_eq!(periodic_autocorrelation(seq1, offset), direct + wrapped);
+                assert_eq!(negaperiodic_autocorrelation(seq1, offset), direct - wrapped);
+            }
+            for seq2 in &sequences {
+                for offset in 1..n {
+                    let (direct, wrapped) = (aperiodic_cross_correlation(seq1, seq2, offset), aperiodic_cross_correlation(seq2, seq1, n - offset));
+                    assert_eq!(cross_correlation(seq1, seq2, offset), direct + wrapped);
+                    assert_eq!(negaperiodic_cross_correlation(seq1, seq2, offset), direct - wrapped);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aperiodic_complementary() {
+        let mut will = QuadSeq::new(3);
+        will.set_all_values((&vec![1,1,-1], &vec![1,-1,-1], &vec![1,1,1], &vec![1,-1,1]));
+        assert!(will.is_aperiodic_complementary());
+        assert!(will.is_negaperiodic_complementary());
+        assert!(will.is_periodic_complementary());
+
+        // A rotation keeps the periodic property only
+        will.set_all_values((&vec![1,1,-1], &vec![1,-1,-1], &vec![1,1,1], &vec![-1,1,1]));
+        assert!(will.is_periodic_complementary());
+        assert!(!will.is_aperiodic_complementary());
+        assert!(!will.is_negaperiodic_complementary());
+    }
+
+
     #[test]
     fn test_symmetric() {
         let mut will = QuadSeq::new(4);
@@ -165,6 +214,29 @@ mod tests {
         
         assert!(will.to_qs().is_perfect());
     }
-}
-
 
+    #[test]
+    fn test_good_best() {
+        assert!(skew_type(&vec![1,1,-1]));
+        assert!(!skew_type(&vec![-1,1,-1]));
+        assert!(!skew_type(&vec![1,1,1,-1]));
+
+        // W skew-type and X, Y, Z symmetric
+        let mut good = QuadSeq::new(3);
+        good.set_all_values((&vec![1,1,-1], &vec![1,1,1], &vec![1,-1,-1], &vec![1,-1,-1]));
+        assert!(good.verify(SequenceType::Good));
+        assert!(!good.verify(SequenceType::Best));
+
+        // W, X, Y skew-type and Z symmetric
+        let mut best = QuadSeq::new(3);
+        best.set_all_values((&vec![1,1,-1], &vec![1,1,-1], &vec![1,1,-1], &vec![1,1,1]));
+        assert!(best.verify(SequenceType::Best));
+        assert!(!best.verify(SequenceType::Good));
+
+        // Williamson sequences aren't good matrices
+        let mut will = QuadSeq::new(3);
+        will.set_all_values((&vec![1,1,1], &vec![1,-1,-1], &vec![1,-1,-1], &vec![1,-1,-1]));
+        assert!(will.verify(SequenceType::Williamson));
+        assert!(!will.verify(SequenceType::Good));
+    }
+}
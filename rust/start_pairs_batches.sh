@@ -8,6 +8,7 @@ then
 	echo "Optional flags:"
     echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include XY, XZ, XW. Default is XW"
+	echo "  * -y <shape>: Only search for quadruples whose sequences all have the shape: sym (symmetric), skew (skew-type) or I, II, III, IV (the symmetries of the pqs search). Default is none"
 	exit 0
 fi
 
@@ -15,17 +16,21 @@ type=$1
 n=$2
 shift
 shift
-foldername="./results/pairs/$type/find_$n"
 rowsum_pairing="XW"
+shape=none
+cleanup=false
 
-while getopts "dp:" flag; do
+while getopts "dp:y:" flag; do
 	case $flag in
 		d)
-		./pair_file_cleanup.sh $n
+		cleanup=true
 		;;
 		p)
 		rowsum_pairing=$OPTARG
 		;;
+		y)
+		shape=$OPTARG
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -35,6 +40,13 @@ done
 
 
 
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+
+if [ $cleanup = true ]; then
+	./pair_file_cleanup.sh $type $n -y $shape
+fi
+
 # Check if rowsum directories still exist
 for d in "$foldername"/rowsum_*; do
   if [ -d "$d" ]; then
@@ -43,14 +55,17 @@ for d in "$foldername"/rowsum_*; do
   fi
 done
 
-./target/release/rust rowsums $type $n 
+./target/release/rust rowsums $type $n $shape
 
 
 
 # read the rowsums file
-input="results/pairs/$type/find_$n/rowsums.quad"
-while IFS= read -r rowsum
+input="$foldername/rowsums.quad"
+# each line holds the rowsums w x y z of a class and its multiplicity, the lines starting with # are comments
+while read -r w x y z multiplicity
 do
+	[[ -z "$w" || "$w" == \#* ]] && continue
+	rowsum="$w $x $y $z"
     #launch the batches for each rowsum
-    sbatch ./job_pairs_rowsum.sh $type $n $rowsum $rowsum_pairing
+    sbatch ./job_pairs_rowsum.sh $type $n $rowsum $rowsum_pairing $shape
 done < "$input"
@@ -7,6 +7,7 @@ then
 	echo "Optional flags:"
     echo "  * -d: Delete existing .seq, .pair and .sorted files"
 	echo "  * -p <pairing>: Specify rowsum pairing to be used. Options include XY, XZ, XW. Default is XW"
+	echo "  * -y <shape>: Only search for quadruples whose sequences all have the shape: sym (symmetric), skew (skew-type) or I, II, III, IV (the symmetries of the pqs search). Default is none"
 	exit 0
 fi
 
@@ -14,21 +15,28 @@ type=$1
 n=$2
 shift
 shift
-foldername="./results/pairs/$type/find_$n"
 rowsum_pairing="XW"
 match_option="psd"
+shape=none
+cleanup=false
 
-while getopts "dcp:" flag; do
+while getopts "dcep:y:" flag; do
 	case $flag in
 		d)
-		./pair_file_cleanup.sh $type $n
+		cleanup=true
 		;;
 		p)
 		rowsum_pairing=$OPTARG
 		;;
+		y)
+		shape=$OPTARG
+		;;
 		c)
 		match_option="correlation"
 		;;
+		e)
+		match_option="exact"
+		;;
 		/?)
 		echo "Invalid argument(s) passed. Exiting."
 		exit 1
@@ -36,6 +44,13 @@ while getopts "dcp:" flag; do
 	esac
 done
 
+. ./pairs_folder.sh
+foldername=$(pairs_folder $type $n $shape)
+
+if [ $cleanup = true ]; then
+	./pair_file_cleanup.sh $type $n -y $shape
+fi
+
 # Check if rowsum directories still exist
 for d in "$foldername"/rowsum_*; do
   if [ -d "$d" ]; then
@@ -45,14 +60,17 @@ for d in "$foldername"/rowsum_*; do
 done
 
 # Generate rowsums
-./target/release/rust rowsums $type $n 
+./target/release/rust rowsums $type $n $shape
 
 
 # read the rowsums file and submit jobs
-input="results/pairs/$type/find_$n/rowsums.quad"
-while IFS= read -r rowsum
+input="$foldername/rowsums.quad"
+# each line holds the rowsums w x y z of a class and its multiplicity, the lines starting with # are comments
+while read -r w x y z multiplicity
 do
+	[[ -z "$w" || "$w" == \#* ]] && continue
+	rowsum="$w $x $y $z"
     # Submit job for first pair, capturing job ID
-	sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 1
-	sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 2
+	sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 1 $shape
+	sbatch ./job_pair_single_rowsum.sh $type $n $rowsum $match_option $rowsum_pairing 2 $shape
 done < "$input"